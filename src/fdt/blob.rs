@@ -1,9 +1,19 @@
-#[derive(Clone, Copy)]
+/* FDT Token */
+pub(crate) const FDT_BEGIN_NODE: u32 = 0x00000001;
+pub(crate) const FDT_END_NODE: u32 = 0x00000002;
+pub(crate) const FDT_PROP: u32 = 0x00000003;
+pub(crate) const FDT_NOP: u32 = 0x00000004;
+pub(crate) const FDT_END: u32 = 0x00000009;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct FdtReserveEntry {
     pub(crate) address: u64,
     pub(crate) size: u64
 }
 
+/// Memory reservation block, borrowed from the blob including its terminating entry
+pub struct FdtReserveBlock<'a>(pub(crate) &'a [u8]);
+
 pub struct FdtStructBlock<'a>(pub(crate) &'a [u8]);
 
 pub struct FdtStringsBlock<'a>(pub(crate) &'a [u8]);
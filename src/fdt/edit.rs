@@ -0,0 +1,601 @@
+use log::debug;
+
+use crate::{
+    DeviceTreeError,
+    DeviceTreeBlob
+};
+use super::{
+    header::{
+        FdtHeader,
+        FDT_HEADER_SIZE
+    },
+    blob::{
+        FDT_BEGIN_NODE,
+        FDT_END_NODE,
+        FDT_PROP,
+        FDT_NOP
+    },
+    view::Fdt,
+    name::{
+        split_node_name,
+        validate_prop_name
+    },
+    events::{
+        FdtEvents,
+        FdtEventKind,
+        align
+    }
+};
+
+/* Offsets of the header fields */
+const TOTALSIZE: usize = 4;
+const BOOT_CPUID_PHYS: usize = 28;
+const OFF_DT_STRUCT: usize = 8;
+const OFF_DT_STRINGS: usize = 12;
+const OFF_MEM_RSVMAP: usize = 16;
+const SIZE_DT_STRINGS: usize = 32;
+const SIZE_DT_STRUCT: usize = 36;
+
+/// Size in bytes of an entry of the memory reservation block
+const RESERVE_ENTRY_SIZE: usize = 16;
+
+/// Size in bytes of the FDT_PROP token together with the length and name offset fields
+const PROP_HEADER_SIZE: usize = 12;
+
+/// Devicetree Blob in a mutable buffer, edited in place like libfdt's read-write functions
+///
+/// The blob occupies the first 'totalsize' bytes of the buffer; the rest is spare capacity for the edits.
+/// Blocks are shifted inside the buffer, so the blob must have the usual layout:
+/// header, memory reservation block, structure block, then strings block.
+/// An edit that does not fit returns DeviceTreeError::NoSpace and leaves the blob unchanged.
+pub struct FdtBlobMut<'a> {
+    buf: &'a mut [u8]
+}
+
+/// Position of a node in the structure block
+struct NodeSpan {
+    /// Offset of the FDT_BEGIN_NODE token
+    begin: usize,
+    /// Offset of the first token after the node name
+    body: usize,
+    /// Offset of the FDT_END_NODE token of the node
+    end: usize
+}
+
+impl<'a> FdtBlobMut<'a> {
+    /// Open the blob at the start of `buf`
+    pub fn new(buf: &'a mut [u8]) -> Result<Self, DeviceTreeError> {
+        if buf.len() < FDT_HEADER_SIZE {
+            return Err(DeviceTreeError::BadTotalSize);
+        }
+
+        let header = FdtHeader::from_bytes(&mut &buf[..])?;
+
+        if header.totalsize() > buf.len() || header.totalsize() < FDT_HEADER_SIZE {
+            return Err(DeviceTreeError::BadTotalSize);
+        }
+
+        let in_order = header.off_mem_rsvmap() >= FDT_HEADER_SIZE
+            && header.off_dt_struct() >= header.off_mem_rsvmap()
+            && header.off_dt_strings() >= header.off_dt_struct() + header.size_dt_struct()
+            && header.off_dt_strings() + header.size_dt_strings() <= header.totalsize();
+
+        if !in_order {
+            return Err(DeviceTreeError::BadLayout);
+        }
+
+        Ok(Self { buf })
+    }
+
+    /// The blob, without the spare capacity
+    pub fn bytes(&self) -> &[u8] {
+        &self.buf[..self.totalsize()]
+    }
+
+    pub fn totalsize(&self) -> usize {
+        self.header_field(TOTALSIZE)
+    }
+
+    /// Bytes left in the buffer for edits
+    pub fn free_space(&self) -> usize {
+        self.buf.len() - self.totalsize()
+    }
+
+    /// Zero-copy view of the current content
+    pub fn view(&self) -> Result<Fdt<'_>, DeviceTreeError> {
+        Fdt::new(self.structure(), self.strings())
+    }
+
+    /// Set the value of a property, adding the property after the last one of the node if it does not exist
+    ///
+    /// Returns DeviceTreeError::BadPropName if `name` is not a valid property name, see validate_prop_name.
+    pub fn setprop(&mut self, path: &str, name: &str, value: &[u8]) -> Result<(), DeviceTreeError> {
+        validate_prop_name(name)?;
+
+        let node = self.find_node(path)?;
+
+        let (prop, insert_at) = self.find_prop(&node, name)?;
+
+        match prop {
+            Some((offset, len)) => {
+                self.splice_struct(offset + PROP_HEADER_SIZE, align(len), align(value.len()))?;
+
+                self.write_prop_value(offset, value);
+            },
+            None => {
+                let name_off = self.find_string(name);
+
+                let needed = PROP_HEADER_SIZE + align(value.len()) + if name_off.is_none() { name.len() + 1 } else { 0 };
+
+                if needed > self.free_space() {
+                    return Err(DeviceTreeError::NoSpace);
+                }
+
+                let name_off = match name_off {
+                    Some(name_off) => name_off,
+                    None => self.add_string(name)?
+                };
+
+                self.splice_struct(insert_at, 0, PROP_HEADER_SIZE + align(value.len()))?;
+
+                let offset = self.header_field(OFF_DT_STRUCT) + insert_at;
+
+                self.set_u32(offset, FDT_PROP);
+                self.set_u32(offset + 8, name_off as u32);
+
+                self.write_prop_value(insert_at, value);
+            }
+        }
+
+        debug!("Set property '{}' of node '{}'.", name, path);
+
+        Ok(())
+    }
+
+    /// Append `value` to the value of a property, adding the property if it does not exist
+    pub fn appendprop(&mut self, path: &str, name: &str, value: &[u8]) -> Result<(), DeviceTreeError> {
+        let node = self.find_node(path)?;
+
+        let (offset, len) = match self.find_prop(&node, name)?.0 {
+            Some(prop) => prop,
+            None => return self.setprop(path, name, value)
+        };
+
+        let new_len = len + value.len();
+
+        self.splice_struct(offset + PROP_HEADER_SIZE + align(len), 0, align(new_len) - align(len))?;
+
+        let value_start = self.header_field(OFF_DT_STRUCT) + offset + PROP_HEADER_SIZE;
+
+        self.set_u32(value_start - 8, new_len as u32);
+        self.buf[value_start + len..value_start + new_len].copy_from_slice(value);
+        self.buf[value_start + new_len..value_start + align(new_len)].fill(0);
+
+        Ok(())
+    }
+
+    /// Delete a property. Its name stays in the strings block.
+    pub fn delprop(&mut self, path: &str, name: &str) -> Result<(), DeviceTreeError> {
+        let node = self.find_node(path)?;
+
+        let (offset, len) = self.find_prop(&node, name)?.0.ok_or(DeviceTreeError::NotFound)?;
+
+        self.splice_struct(offset, PROP_HEADER_SIZE + align(len), 0)
+    }
+
+    /// Add an empty node named `name` as the last child of the node at `parent`
+    ///
+    /// Returns DeviceTreeError::BadNodeName if `name` is not a valid node name, see NodeName::parse.
+    pub fn add_subnode(&mut self, parent: &str, name: &str) -> Result<(), DeviceTreeError> {
+        split_node_name(name)?;
+
+        let node = self.find_node(parent)?;
+
+        if self.view()?.find_node(parent).and_then(|node| node.find_child(name)).is_some() {
+            return Err(DeviceTreeError::NodeExists);
+        }
+
+        let name_size = align(name.len() + 1);
+
+        self.splice_struct(node.end, 0, 4 + name_size + 4)?;
+
+        let offset = self.header_field(OFF_DT_STRUCT) + node.end;
+
+        self.set_u32(offset, FDT_BEGIN_NODE);
+        self.buf[offset + 4..offset + 4 + name.len()].copy_from_slice(name.as_bytes());
+        self.buf[offset + 4 + name.len()..offset + 4 + name_size].fill(0);
+        self.set_u32(offset + 4 + name_size, FDT_END_NODE);
+
+        debug!("Added subnode '{}' to node '{}'.", name, parent);
+
+        Ok(())
+    }
+
+    /// Delete a node together with its subtree
+    pub fn del_node(&mut self, path: &str) -> Result<(), DeviceTreeError> {
+        let node = self.find_node(path)?;
+
+        if node.begin == self.view()?.root().offset() {
+            return Err(DeviceTreeError::BadPath);
+        }
+
+        self.splice_struct(node.begin, node.end + 4 - node.begin, 0)
+    }
+
+    /// Add an entry at the end of the memory reservation block
+    pub fn add_mem_rsv(&mut self, address: u64, size: u64) -> Result<(), DeviceTreeError> {
+        let offset = self.header_field(OFF_MEM_RSVMAP) + self.num_mem_rsv() * RESERVE_ENTRY_SIZE;
+
+        self.splice(offset, 0, RESERVE_ENTRY_SIZE)?;
+
+        self.buf[offset..offset + 8].copy_from_slice(&address.to_be_bytes());
+        self.buf[offset + 8..offset + 16].copy_from_slice(&size.to_be_bytes());
+
+        self.move_blocks_after_rsvmap(RESERVE_ENTRY_SIZE as isize);
+
+        Ok(())
+    }
+
+    /// Delete the entry at `index` of the memory reservation block
+    pub fn del_mem_rsv(&mut self, index: usize) -> Result<(), DeviceTreeError> {
+        if index >= self.num_mem_rsv() {
+            return Err(DeviceTreeError::NotFound);
+        }
+
+        let offset = self.header_field(OFF_MEM_RSVMAP) + index * RESERVE_ENTRY_SIZE;
+
+        self.splice(offset, RESERVE_ENTRY_SIZE, 0)?;
+
+        self.move_blocks_after_rsvmap(-(RESERVE_ENTRY_SIZE as isize));
+
+        Ok(())
+    }
+
+    /// Remove NOP tokens, unused strings and the padding between and after the blocks, and shrink 'totalsize'
+    ///
+    /// Afterwards the blocks follow each other right behind the header.
+    pub fn pack(&mut self) -> Result<(), DeviceTreeError> {
+        while let Some(offset) = self.find_token(FDT_NOP)? {
+            self.splice_struct(offset, 4, 0)?;
+        }
+
+        let mut start = 0;
+
+        while start < self.header_field(SIZE_DT_STRINGS) {
+            let len = match self.strings()[start..].iter().position(|&b| b == 0) {
+                Some(nul) => nul + 1,
+                None => return Err(DeviceTreeError::BadStringsBlockOffset)
+            };
+
+            if self.string_used(start, len)? {
+                start += len;
+            } else {
+                self.remove_string(start, len)?;
+            }
+        }
+
+        let rsvmap_size = (self.num_mem_rsv() + 1) * RESERVE_ENTRY_SIZE;
+        let struct_size = self.header_field(SIZE_DT_STRUCT);
+        let strings_size = self.header_field(SIZE_DT_STRINGS);
+
+        let blocks = [
+            (OFF_MEM_RSVMAP, rsvmap_size),
+            (OFF_DT_STRUCT, struct_size),
+            (OFF_DT_STRINGS, strings_size)
+        ];
+
+        // The blocks are in order, so each one only moves towards the header
+        let mut end = FDT_HEADER_SIZE;
+
+        for (field, size) in blocks {
+            let offset = self.header_field(field);
+
+            self.buf.copy_within(offset..offset + size, end);
+            self.set_header_field(field, end);
+
+            end += size;
+        }
+
+        let totalsize = self.totalsize();
+
+        self.buf[end..totalsize].fill(0);
+        self.set_header_field(TOTALSIZE, end);
+
+        debug!("Packed blob from {} to {} bytes.", totalsize, end);
+
+        Ok(())
+    }
+
+    /// Move the blob into `buf`, which is usually bigger, to get room for edits
+    ///
+    /// The blocks are laid out right behind each other in the usual order.
+    pub fn open_into<'b>(&self, buf: &'b mut [u8]) -> Result<FdtBlobMut<'b>, DeviceTreeError> {
+        let rsvmap_offset = self.header_field(OFF_MEM_RSVMAP);
+        let rsvmap = &self.buf[rsvmap_offset..rsvmap_offset + (self.num_mem_rsv() + 1) * RESERVE_ENTRY_SIZE];
+
+        copy_blocks(buf, self.header_field(BOOT_CPUID_PHYS) as u32, rsvmap, self.structure(), self.strings())
+    }
+
+    /// Number of entries of the memory reservation block, without the terminating one
+    pub fn num_mem_rsv(&self) -> usize {
+        let rsvmap = &self.buf[self.header_field(OFF_MEM_RSVMAP)..self.header_field(OFF_DT_STRUCT)];
+
+        rsvmap.chunks_exact(RESERVE_ENTRY_SIZE).take_while(|entry| entry.iter().any(|&b| b != 0)).count()
+    }
+
+    pub(crate) fn structure(&self) -> &[u8] {
+        let offset = self.header_field(OFF_DT_STRUCT);
+
+        &self.buf[offset..offset + self.header_field(SIZE_DT_STRUCT)]
+    }
+
+    pub(crate) fn strings(&self) -> &[u8] {
+        let offset = self.header_field(OFF_DT_STRINGS);
+
+        &self.buf[offset..offset + self.header_field(SIZE_DT_STRINGS)]
+    }
+
+    fn find_node(&self, path: &str) -> Result<NodeSpan, DeviceTreeError> {
+        if !path.starts_with('/') {
+            return Err(DeviceTreeError::BadPath);
+        }
+
+        let node = self.view()?.find_node(path).ok_or(DeviceTreeError::NotFound)?;
+
+        let begin = node.offset();
+        let body = align(begin + 4 + node.name().len() + 1);
+
+        let mut events = FdtEvents::at(self.structure(), self.strings(), body, 1);
+
+        events.skip_subtree()?;
+
+        Ok(NodeSpan { begin, body, end: events.offset() - 4 })
+    }
+
+    /// Offset of the first `token` in the structure block
+    fn find_token(&self, token: u32) -> Result<Option<usize>, DeviceTreeError> {
+        for event in FdtEvents::new(self.structure(), self.strings()) {
+            let event = event?;
+
+            let found = match event.kind {
+                FdtEventKind::BeginNode(_) => token == FDT_BEGIN_NODE,
+                FdtEventKind::EndNode => token == FDT_END_NODE,
+                FdtEventKind::Prop(..) => token == FDT_PROP,
+                FdtEventKind::Nop => token == FDT_NOP,
+                FdtEventKind::End => false
+            };
+
+            if found {
+                return Ok(Some(event.offset));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Offsets in the structure block of all FDT_PROP tokens, passed to `f` one by one
+    fn for_each_prop<F: FnMut(&mut Self, usize)>(&mut self, mut f: F) -> Result<(), DeviceTreeError> {
+        let mut offset = 0;
+
+        loop {
+            let mut events = FdtEvents::at(self.structure(), self.strings(), offset, 1);
+
+            let (is_prop, is_end) = match events.next() {
+                Some(event) => {
+                    let kind = event?.kind;
+
+                    (matches!(kind, FdtEventKind::Prop(..)), kind == FdtEventKind::End)
+                },
+                None => return Ok(())
+            };
+
+            let next = events.offset();
+
+            if is_end {
+                return Ok(());
+            }
+
+            if is_prop {
+                f(self, offset);
+            }
+
+            offset = next;
+        }
+    }
+
+    /// Whether a property name starts within the string of `len` bytes at `start` of the strings block
+    fn string_used(&mut self, start: usize, len: usize) -> Result<bool, DeviceTreeError> {
+        let mut used = false;
+
+        self.for_each_prop(|blob, offset| {
+            let name_off = blob.struct_u32(offset + 8);
+
+            used |= name_off >= start && name_off < start + len;
+        })?;
+
+        Ok(used)
+    }
+
+    /// Remove the string of `len` bytes at `start` of the strings block and move the names behind it
+    fn remove_string(&mut self, start: usize, len: usize) -> Result<(), DeviceTreeError> {
+        self.for_each_prop(|blob, offset| {
+            let name_off = blob.struct_u32(offset + 8);
+
+            if name_off >= start + len {
+                let field = blob.header_field(OFF_DT_STRUCT) + offset + 8;
+
+                blob.set_u32(field, (name_off - len) as u32);
+            }
+        })?;
+
+        let offset = self.header_field(OFF_DT_STRINGS) + start;
+
+        self.splice(offset, len, 0)?;
+
+        let size = self.header_field(SIZE_DT_STRINGS) - len;
+
+        self.set_header_field(SIZE_DT_STRINGS, size);
+
+        Ok(())
+    }
+
+    fn struct_u32(&self, offset: usize) -> usize {
+        self.header_field(self.header_field(OFF_DT_STRUCT) + offset)
+    }
+
+    /// Look for a property of `node`
+    ///
+    /// Returns the offset and value length of the property if it exists,
+    /// and the offset right after the last property of the node.
+    fn find_prop(&self, node: &NodeSpan, name: &str) -> Result<(Option<(usize, usize)>, usize), DeviceTreeError> {
+        let mut found = None;
+
+        for event in FdtEvents::at(self.structure(), self.strings(), node.body, 1) {
+            let event = event?;
+
+            match event.kind {
+                FdtEventKind::Prop(prop_name, value) if prop_name == name => found = Some((event.offset, value.len())),
+                FdtEventKind::Prop(..) | FdtEventKind::Nop => (),
+                _ => return Ok((found, event.offset))
+            }
+        }
+
+        Err(DeviceTreeError::BadToken)
+    }
+
+    /// Write the length and the zero-padded value of the property at `offset` of the structure block,
+    /// whose value field already has the right size
+    fn write_prop_value(&mut self, offset: usize, value: &[u8]) {
+        let offset = self.header_field(OFF_DT_STRUCT) + offset;
+        let value_start = offset + PROP_HEADER_SIZE;
+
+        self.set_u32(offset + 4, value.len() as u32);
+        self.buf[value_start..value_start + value.len()].copy_from_slice(value);
+        self.buf[value_start + value.len()..value_start + align(value.len())].fill(0);
+    }
+
+    /// Offset of `name` in the strings block, also matching the tail of a longer string
+    fn find_string(&self, name: &str) -> Option<usize> {
+        let strings = self.strings();
+        let name = name.as_bytes();
+
+        (0..strings.len()).find(|&i| {
+            strings[i..].starts_with(name) && strings.get(i + name.len()) == Some(&0)
+        })
+    }
+
+    /// Append `name` to the strings block and return its offset
+    fn add_string(&mut self, name: &str) -> Result<usize, DeviceTreeError> {
+        let size = self.header_field(SIZE_DT_STRINGS);
+        let end = self.header_field(OFF_DT_STRINGS) + size;
+
+        self.splice(end, 0, name.len() + 1)?;
+
+        self.buf[end..end + name.len()].copy_from_slice(name.as_bytes());
+        self.buf[end + name.len()] = 0;
+
+        self.set_header_field(SIZE_DT_STRINGS, size + name.len() + 1);
+
+        Ok(size)
+    }
+
+    /// Replace `old_len` bytes at `offset` of the structure block by `new_len` bytes
+    fn splice_struct(&mut self, offset: usize, old_len: usize, new_len: usize) -> Result<(), DeviceTreeError> {
+        let off_dt_struct = self.header_field(OFF_DT_STRUCT);
+
+        self.splice(off_dt_struct + offset, old_len, new_len)?;
+
+        let size_dt_struct = self.header_field(SIZE_DT_STRUCT) + new_len - old_len;
+        let off_dt_strings = self.header_field(OFF_DT_STRINGS) + new_len - old_len;
+
+        self.set_header_field(SIZE_DT_STRUCT, size_dt_struct);
+        self.set_header_field(OFF_DT_STRINGS, off_dt_strings);
+
+        Ok(())
+    }
+
+    fn move_blocks_after_rsvmap(&mut self, delta: isize) {
+        for field in [OFF_DT_STRUCT, OFF_DT_STRINGS] {
+            let offset = self.header_field(field) as isize + delta;
+
+            self.set_header_field(field, offset as usize);
+        }
+    }
+
+    /// Replace `old_len` bytes at `offset` of the buffer by `new_len` bytes, shifting the rest of the blob
+    ///
+    /// Only 'totalsize' is updated, the caller updates the offsets and sizes of the blocks.
+    fn splice(&mut self, offset: usize, old_len: usize, new_len: usize) -> Result<(), DeviceTreeError> {
+        let totalsize = self.totalsize();
+        let new_totalsize = totalsize + new_len - old_len;
+
+        if new_totalsize > self.buf.len() {
+            return Err(DeviceTreeError::NoSpace);
+        }
+
+        self.buf.copy_within(offset + old_len..totalsize, offset + new_len);
+
+        if new_totalsize < totalsize {
+            self.buf[new_totalsize..totalsize].fill(0);
+        }
+
+        self.set_header_field(TOTALSIZE, new_totalsize);
+
+        Ok(())
+    }
+
+    /// Read the u32 at `field` of the buffer, usually a header field
+    fn header_field(&self, field: usize) -> usize {
+        u32::from_be_bytes(self.buf[field..field + 4].try_into().unwrap()) as usize
+    }
+
+    fn set_header_field(&mut self, field: usize, value: usize) {
+        self.set_u32(field, value as u32);
+    }
+
+    fn set_u32(&mut self, offset: usize, value: u32) {
+        self.buf[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+}
+
+impl<'a> DeviceTreeBlob<'a> {
+    /// Copy the blob into `buf` to edit it in place, see FdtBlobMut::open_into
+    pub fn open_into<'b>(&self, buf: &'b mut [u8]) -> Result<FdtBlobMut<'b>, DeviceTreeError> {
+        copy_blocks(
+            buf,
+            self.header.boot_cpuid_phys(),
+            self.memory_reservation_block.bytes(),
+            self.structure_block.bytes(),
+            self.strings_block.0
+        )
+    }
+}
+
+/// Write a new header and the blocks right behind each other into `buf`
+fn copy_blocks<'b>(buf: &'b mut [u8], boot_cpuid_phys: u32, rsvmap: &[u8], structure: &[u8], strings: &[u8]) -> Result<FdtBlobMut<'b>, DeviceTreeError> {
+    let off_mem_rsvmap = FDT_HEADER_SIZE;
+    let off_dt_struct = off_mem_rsvmap + rsvmap.len();
+    let off_dt_strings = off_dt_struct + structure.len();
+    let totalsize = off_dt_strings + strings.len();
+
+    if totalsize > buf.len() {
+        return Err(DeviceTreeError::NoSpace);
+    }
+
+    let header = FdtHeader::new(
+        totalsize as u32,
+        off_dt_struct as u32,
+        off_dt_strings as u32,
+        off_mem_rsvmap as u32,
+        boot_cpuid_phys,
+        strings.len() as u32,
+        structure.len() as u32
+    );
+
+    buf[..FDT_HEADER_SIZE].copy_from_slice(&header.to_bytes());
+    buf[off_mem_rsvmap..off_dt_struct].copy_from_slice(rsvmap);
+    buf[off_dt_struct..off_dt_strings].copy_from_slice(structure);
+    buf[off_dt_strings..totalsize].copy_from_slice(strings);
+
+    FdtBlobMut::new(buf)
+}
@@ -0,0 +1,231 @@
+use core::str;
+
+use crate::{
+    DeviceTreeError,
+    DeviceTreeBlob
+};
+use super::blob::{
+    FdtStructBlock,
+    FdtStringsBlock,
+    FDT_BEGIN_NODE,
+    FDT_END_NODE,
+    FDT_PROP,
+    FDT_NOP,
+    FDT_END
+};
+
+/// What a token of the structure block stands for, with its payload borrowed from the blob
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FdtEventKind<'a> {
+    /// Start of a node with its name, empty for the root node
+    BeginNode(&'a str),
+    /// A property with its name and value
+    Prop(&'a str, &'a [u8]),
+    EndNode,
+    Nop,
+    End
+}
+
+/// A token of the structure block, as reported by FdtEvents
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FdtEvent<'a> {
+    /// Offset in bytes of the token from the beginning of the structure block
+    pub offset: usize,
+    /// Depth of the node the token belongs to. The root node has depth 0.
+    pub depth: usize,
+    pub kind: FdtEventKind<'a>
+}
+
+/// What FdtEvents::walk does after a callback returned
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WalkAction {
+    Continue,
+    /// Skip the rest of the current node, including its children and its EndNode
+    SkipSubtree,
+    Stop
+}
+
+/// Streaming iterator over the tokens of the structure block
+///
+/// Nothing is allocated: names and values borrow from the blob.
+/// After the first error or the End token, the iterator returns None.
+#[derive(Clone, Debug)]
+pub struct FdtEvents<'a> {
+    structure: &'a [u8],
+    strings: &'a [u8],
+    offset: usize,
+    depth: usize,
+    done: bool
+}
+
+impl<'a> FdtStructBlock<'a> {
+    pub fn events(&self, strings_block: &FdtStringsBlock<'a>) -> FdtEvents<'a> {
+        FdtEvents::new(self.0, strings_block.0)
+    }
+}
+
+impl<'a> DeviceTreeBlob<'a> {
+    /// Iterate over the tokens of the structure block, see FdtEvents
+    pub fn events(&self) -> FdtEvents<'a> {
+        self.structure_block.events(&self.strings_block)
+    }
+
+    /// Walk through the structure block, see FdtEvents::walk
+    pub fn walk<F>(&self, f: F) -> Result<(), DeviceTreeError>
+    where
+        F: FnMut(&FdtEvent<'a>) -> WalkAction
+    {
+        self.events().walk(f)
+    }
+}
+
+impl<'a> FdtEvents<'a> {
+    pub fn new(structure: &'a [u8], strings: &'a [u8]) -> Self {
+        Self::at(structure, strings, 0, 0)
+    }
+
+    /// Start reading at `offset`, which must be the offset of a token of a node at depth `depth`
+    pub(crate) fn at(structure: &'a [u8], strings: &'a [u8], offset: usize, depth: usize) -> Self {
+        Self { structure, strings, offset, depth, done: false }
+    }
+
+    /// Offset of the next token to be read
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Skip the rest of the node entered by the last BeginNode, up to and including its EndNode
+    pub fn skip_subtree(&mut self) -> Result<(), DeviceTreeError> {
+        let depth = self.depth;
+
+        if depth == 0 {
+            return Ok(());
+        }
+
+        while let Some(event) = self.next() {
+            if let FdtEventKind::EndNode = event?.kind {
+                if self.depth < depth {
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(DeviceTreeError::BadToken)
+    }
+
+    /// Call `f` with every token until the End token or until `f` returns WalkAction::Stop
+    ///
+    /// Returning WalkAction::SkipSubtree from a BeginNode skips that whole node,
+    /// from any other token it skips the rest of the current node.
+    pub fn walk<F>(mut self, mut f: F) -> Result<(), DeviceTreeError>
+    where
+        F: FnMut(&FdtEvent<'a>) -> WalkAction
+    {
+        while let Some(event) = self.next() {
+            let event = event?;
+
+            match f(&event) {
+                WalkAction::Continue => (),
+                WalkAction::SkipSubtree => match event.kind {
+                    FdtEventKind::EndNode | FdtEventKind::End => (),
+                    _ => self.skip_subtree()?
+                },
+                WalkAction::Stop => break
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for FdtEvents<'a> {
+    type Item = Result<FdtEvent<'a>, DeviceTreeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let (kind, next) = match read_event(self.structure, self.strings, self.offset) {
+            Ok(event) => event,
+            Err(error) => {
+                self.done = true;
+
+                return Some(Err(error));
+            }
+        };
+
+        let offset = self.offset;
+
+        self.offset = next;
+
+        let depth = match kind {
+            FdtEventKind::BeginNode(_) => {
+                self.depth += 1;
+                self.depth - 1
+            },
+            FdtEventKind::EndNode => {
+                match self.depth.checked_sub(1) {
+                    Some(depth) => self.depth = depth,
+                    None => {
+                        self.done = true;
+
+                        return Some(Err(DeviceTreeError::BadToken));
+                    }
+                }
+
+                self.depth
+            },
+            FdtEventKind::End => {
+                self.done = true;
+
+                self.depth
+            },
+            _ => self.depth.saturating_sub(1)
+        };
+
+        Some(Ok(FdtEvent { offset, depth, kind }))
+    }
+}
+
+/// Read the token at `offset` and return it with the offset of the next token
+pub(crate) fn read_event<'a>(structure: &'a [u8], strings: &'a [u8], offset: usize) -> Result<(FdtEventKind<'a>, usize), DeviceTreeError> {
+    let token = read_be_u32(structure, offset).ok_or(DeviceTreeError::BadToken)?;
+    let offset = offset + 4;
+
+    match token {
+        FDT_BEGIN_NODE => {
+            let name = read_str(structure.get(offset..).ok_or(DeviceTreeError::BadToken)?)?;
+
+            Ok((FdtEventKind::BeginNode(name), align(offset + name.len() + 1)))
+        },
+        FDT_PROP => {
+            let len = read_be_u32(structure, offset).ok_or(DeviceTreeError::BadToken)? as usize;
+            let name_off = read_be_u32(structure, offset + 4).ok_or(DeviceTreeError::BadToken)? as usize;
+
+            let name = read_str(strings.get(name_off..).ok_or(DeviceTreeError::BadStringsBlockOffset)?)?;
+            let value = structure.get(offset + 8..offset + 8 + len).ok_or(DeviceTreeError::BadPropValue)?;
+
+            Ok((FdtEventKind::Prop(name, value), align(offset + 8 + len)))
+        },
+        FDT_END_NODE => Ok((FdtEventKind::EndNode, offset)),
+        FDT_NOP => Ok((FdtEventKind::Nop, offset)),
+        FDT_END => Ok((FdtEventKind::End, offset)),
+        _ => Err(DeviceTreeError::NotAToken)
+    }
+}
+
+pub(crate) fn read_be_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(offset..offset + 4)?.try_into().unwrap()))
+}
+
+/// Read a NUL-terminated UTF-8 string from the start of `bytes`
+fn read_str(bytes: &[u8]) -> Result<&str, DeviceTreeError> {
+    let nul = bytes.iter().position(|&b| b == 0).ok_or(DeviceTreeError::BadToken)?;
+
+    str::from_utf8(&bytes[..nul]).map_err(|_| DeviceTreeError::BadToken)
+}
+
+pub(crate) fn align(offset: usize) -> usize {
+    (offset + 3) & !3
+}
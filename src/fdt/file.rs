@@ -0,0 +1,33 @@
+use std::{
+    fs,
+    path::Path,
+    io::Error
+};
+
+use crate::{
+    DeviceTree,
+    DeviceTreeError,
+    tree::overlay::WriteOptions
+};
+
+impl From<Error> for DeviceTreeError {
+    fn from(error: Error) -> Self {
+        Self::Io(error.kind())
+    }
+}
+
+impl DeviceTree {
+    /// Read and parse a Devicetree Blob file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, DeviceTreeError> {
+        let bytes = fs::read(path)?;
+
+        Self::from_bytes(&mut bytes.as_slice())
+    }
+
+    /// Serialize the tree and write it to a Devicetree Blob file
+    pub fn write_file<P: AsRef<Path>>(&self, path: P, options: &WriteOptions) -> Result<(), DeviceTreeError> {
+        fs::write(path, self.to_dtb(options)?)?;
+
+        Ok(())
+    }
+}
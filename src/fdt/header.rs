@@ -1,8 +1,4 @@
-use log::{
-    info,
-    debug,
-    error
-};
+use log::debug;
 
 use crate::{
     utils, 
@@ -17,6 +13,15 @@ const FDT_MAGIC: u32 = 0xd00dfeed;
 /// Note: The version is 17 if using the structure as defined in https://github.com/devicetree-org/devicetree-specification/releases/tag/v0.4-rc1
 const VERSION_NUMBER: u32 = 17;
 
+/// Lowest version the written blobs are backwards compatible with
+const LAST_COMP_VERSION: u32 = 16;
+
+/// Size in bytes of the header
+pub const FDT_HEADER_SIZE: usize = 40;
+
+/// Alignment in bytes of a blob in memory
+pub const FDT_ALIGN: usize = 8;
+
 pub struct FdtHeader {
     /// The magic value, shall be 0xd00dfeed (big-endian).
 	magic: u32,
@@ -42,6 +47,46 @@ pub struct FdtHeader {
 }
 
 impl FdtHeader {
+    /// Header of a version 17 blob with the given layout
+    pub fn new(totalsize: u32, off_dt_struct: u32, off_dt_strings: u32, off_mem_rsvmap: u32, boot_cpuid_phys: u32, size_dt_strings: u32, size_dt_struct: u32) -> Self {
+        Self {
+            magic: FDT_MAGIC,
+            totalsize,
+            off_dt_struct,
+            off_dt_strings,
+            off_mem_rsvmap,
+            version: VERSION_NUMBER,
+            last_comp_version: LAST_COMP_VERSION,
+            boot_cpuid_phys,
+            size_dt_strings,
+            size_dt_struct
+        }
+    }
+
+    /// Serialize the header in big endian
+    pub fn to_bytes(&self) -> [u8; FDT_HEADER_SIZE] {
+        let fields = [
+            self.magic,
+            self.totalsize,
+            self.off_dt_struct,
+            self.off_dt_strings,
+            self.off_mem_rsvmap,
+            self.version,
+            self.last_comp_version,
+            self.boot_cpuid_phys,
+            self.size_dt_strings,
+            self.size_dt_struct
+        ];
+
+        let mut bytes = [0u8; FDT_HEADER_SIZE];
+
+        for (chunk, field) in bytes.chunks_exact_mut(4).zip(fields.iter()) {
+            chunk.copy_from_slice(&field.to_be_bytes());
+        }
+
+        bytes
+    }
+
     pub fn from_bytes(bytes: &mut &[u8]) -> Result<Self, DeviceTreeError> {
         debug!("Parsing FDT header from bytes.");
 
@@ -63,11 +108,11 @@ impl FdtHeader {
         match check {
             Ok(_) => {
                 debug!("Valid header!");
-                return Ok(header);
+                Ok(header)
             },
             Err(error) => {
                 debug!("Invalid magic number and/or version!");
-                return Err(error);
+                Err(error)
             }
         }
     }
@@ -96,6 +141,30 @@ impl FdtHeader {
         self.totalsize as usize
     }
 
+    pub fn off_dt_struct(&self) -> usize {
+        self.off_dt_struct as usize
+    }
+
+    pub fn off_dt_strings(&self) -> usize {
+        self.off_dt_strings as usize
+    }
+
+    pub fn off_mem_rsvmap(&self) -> usize {
+        self.off_mem_rsvmap as usize
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn last_comp_version(&self) -> u32 {
+        self.last_comp_version
+    }
+
+    pub fn boot_cpuid_phys(&self) -> u32 {
+        self.boot_cpuid_phys
+    }
+
     pub fn size_dt_struct(&self) -> usize {
         self.size_dt_struct as usize
     }
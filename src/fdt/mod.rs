@@ -1,4 +1,14 @@
 pub mod header;
 pub mod blob;
+pub mod view;
+pub mod events;
+pub mod edit;
 
 mod parsing;
+pub(crate) mod name;
+#[cfg(feature = "alloc")]
+mod unflatten;
+#[cfg(feature = "alloc")]
+mod writer;
+#[cfg(feature = "std")]
+mod file;
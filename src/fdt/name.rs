@@ -0,0 +1,37 @@
+use crate::DeviceTreeError;
+
+/// Characters allowed in node names and unit addresses by the Devicetree Specification
+pub(crate) const NODE_NAME_CHARS: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ,._+-";
+
+/// Characters allowed in property names by the Devicetree Specification
+pub(crate) const PROP_NAME_CHARS: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ,._+?#-";
+
+/// Maximum length of the base name of a node and of a property name
+pub const NAME_MAX_LEN: usize = 31;
+
+/// Validate a node name other than the root and split it into its base name and unit address
+///
+/// The base name has 1 to 31 characters and the unit address at least one, all of them from NODE_NAME_CHARS.
+pub(crate) fn split_node_name(name: &str) -> Result<(&str, Option<&str>), DeviceTreeError> {
+    let (base, unit_address) = match name.split_once('@') {
+        Some((base, unit_address)) => (base, Some(unit_address)),
+        None => (name, None)
+    };
+
+    let valid = |s: &str| !s.is_empty() && s.chars().all(|c| NODE_NAME_CHARS.contains(c));
+
+    if !valid(base) || base.len() > NAME_MAX_LEN || !unit_address.is_none_or(valid) {
+        return Err(DeviceTreeError::BadNodeName);
+    }
+
+    Ok((base, unit_address))
+}
+
+/// Check that a property name has 1 to 31 characters from PROP_NAME_CHARS
+pub fn validate_prop_name(name: &str) -> Result<(), DeviceTreeError> {
+    if name.is_empty() || name.len() > NAME_MAX_LEN || !name.chars().all(|c| PROP_NAME_CHARS.contains(c)) {
+        return Err(DeviceTreeError::BadPropName);
+    }
+
+    Ok(())
+}
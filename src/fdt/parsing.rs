@@ -1,95 +1,169 @@
 use core::ffi::CStr;
-use alloc::{
-    rc::Rc,
-    vec::Vec,
-};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use log::{
     info,
     debug,
     error
 };
 
-use super::header::FdtHeader;
+use super::header::{
+    FdtHeader,
+    FDT_HEADER_SIZE,
+    FDT_ALIGN
+};
 use crate::{
     utils,
-    DeviceTree, 
     DeviceTreeError, 
-    DeviceTreeBlob,
-    tree::{
-        node::{
-            DeviceTreeNode, 
-            AddChild
-        }, 
-        prop::DeviceTreeProperty,
-    }
+    DeviceTreeBlob
 };
 use super::blob::{
     FdtReserveEntry,
+    FdtReserveBlock,
     FdtPropDescribe,
     FdtStructBlock,
     FdtStringsBlock,
-    Token
+    Token,
+    FDT_BEGIN_NODE,
+    FDT_END_NODE,
+    FDT_PROP,
+    FDT_NOP,
+    FDT_END
 };
-
-/* FDT Token */
-const FDT_BEGIN_NODE: u32 = 0x00000001;
-const FDT_END_NODE: u32 = 0x00000002;
-const FDT_PROP: u32 = 0x00000003;
-const FDT_NOP: u32 = 0x00000004;
-const FDT_END: u32 = 0x00000009;
-
-impl DeviceTree {
-    pub fn from_bytes(bytes: &mut &[u8]) -> Result<Self, DeviceTreeError> {
-        let mut dtb = DeviceTreeBlob::from_bytes(bytes)?;
-
-        dtb.to_tree()
-    }
-}
+#[cfg(feature = "alloc")]
+use super::events::FdtEventKind;
 
 impl<'a> DeviceTreeBlob<'a> {
+    /// Parse the blob at the start of `bytes`, which is advanced past its 'totalsize' bytes
+    /// 
+    /// Each block is taken at the offset the header gives. Returns DeviceTreeError::BadTotalSize if `bytes` is
+    /// shorter than the header or than 'totalsize', or if a block does not lie within 'totalsize'.
     pub fn from_bytes(bytes: &mut &'a [u8]) -> Result<Self, DeviceTreeError> {
         info!("Device-Tree-Blob located at {:#x}", bytes as *const _ as usize);
 
-        let header = FdtHeader::from_bytes(bytes)?;
+        if bytes.len() < FDT_HEADER_SIZE {
+            return Err(DeviceTreeError::BadTotalSize);
+        }
 
-        let mut memory_reservation_vec: Vec<FdtReserveEntry> = Vec::new();
+        let header = FdtHeader::from_bytes(&mut &bytes[..])?;
 
-        while let Some(entry) = FdtReserveEntry::from_bytes(bytes) {
-            if !entry.end_of_list() {
-                debug!("Adding reserved memory entry.");
-                memory_reservation_vec.push(entry);
-            } else {
-                debug!("End of adding reserved memory entry.");
-                break;
-            }
+        let totalsize = header.totalsize();
+
+        if totalsize < FDT_HEADER_SIZE || totalsize > bytes.len() {
+            error!("Devicetree blob of {} bytes does not fit its total size of {} bytes.", bytes.len(), totalsize);
+
+            return Err(DeviceTreeError::BadTotalSize);
         }
 
-        let structure_block_size = header.size_dt_struct();
-        let string_block_size = header.size_dt_strings();
+        let blob = utils::pop_slice(bytes, totalsize).unwrap();
+
+        let block = |offset: usize, size: usize| {
+            offset.checked_add(size)
+                .and_then(|end| blob.get(offset..end))
+                .ok_or(DeviceTreeError::BadTotalSize)
+        };
+
+        let structure_block = block(header.off_dt_struct(), header.size_dt_struct())?;
+        let strings_block = block(header.off_dt_strings(), header.size_dt_strings())?;
+
+        let mut memory_reservation_block = blob.get(header.off_mem_rsvmap()..).ok_or(DeviceTreeError::BadTotalSize)?;
 
         Ok( Self {
-            header: header,
-            memory_reservation_block: memory_reservation_vec,
-            structure_block: FdtStructBlock::from_bytes(bytes.take(..structure_block_size).unwrap()),
-            strings_block: FdtStringsBlock::from_bytes(bytes.take(..string_block_size).unwrap()) 
+            header,
+            memory_reservation_block: FdtReserveBlock::from_bytes(&mut memory_reservation_block),
+            structure_block: FdtStructBlock::from_bytes(structure_block),
+            strings_block: FdtStringsBlock::from_bytes(strings_block)
         })
     }
 
-    pub fn to_tree(&mut self) -> Result<DeviceTree, DeviceTreeError> {
-        self.structure_block.parsing(&self.strings_block)
+    /// Build a blob from a pointer to its first byte, e.g. the address a kernel receives in a register
+    /// 
+    /// The header is read first. Only after its magic and version are valid, a slice of 'totalsize' bytes is created.
+    /// 
+    /// # Safety
+    /// 
+    /// `ptr` must point to readable memory holding at least the header, and, if the header is valid,
+    /// 'totalsize' bytes. This memory must not be modified for the lifetime 'a.
+    pub unsafe fn from_ptr(ptr: *const u8) -> Result<Self, DeviceTreeError> {
+        let mut header_bytes = core::slice::from_raw_parts(ptr, FDT_HEADER_SIZE);
+
+        let totalsize = FdtHeader::from_bytes(&mut header_bytes)?.totalsize();
+
+        if totalsize < FDT_HEADER_SIZE {
+            return Err(DeviceTreeError::BadTotalSize);
+        }
+
+        let mut bytes = core::slice::from_raw_parts(ptr, totalsize);
+
+        Self::from_bytes(&mut bytes)
+    }
+
+    /// Like from_ptr, but rejects a null pointer and a pointer that is not aligned to 8 bytes,
+    /// as the Devicetree Specification requires for a blob in memory
+    /// 
+    /// # Safety
+    /// 
+    /// Whether the memory behind `ptr` is readable cannot be checked, so the requirements of from_ptr still apply
+    /// to any pointer that passes the checks.
+    pub unsafe fn from_ptr_checked(ptr: *const u8) -> Result<Self, DeviceTreeError> {
+        check_ptr(ptr)?;
+
+        Self::from_ptr(ptr)
     }
 
-    pub fn structure_block(&self) -> &FdtStructBlock {
+    /// Safe counterpart of from_ptr_checked for a blob already available as a slice, e.g. one the bootloader
+    /// handed over and that was mapped by the caller
+    /// 
+    /// Returns DeviceTreeError::BadPointer if `bytes` is not aligned to 8 bytes, then parses like from_bytes.
+    pub fn from_aligned_bytes(bytes: &'a [u8]) -> Result<Self, DeviceTreeError> {
+        check_ptr(bytes.as_ptr())?;
+
+        Self::from_bytes(&mut &bytes[..])
+    }
+
+    pub fn header(&self) -> &FdtHeader {
+        &self.header
+    }
+
+    pub fn memory_reservation_block(&self) -> &FdtReserveBlock<'_> {
+        &self.memory_reservation_block
+    }
+
+    pub fn structure_block(&self) -> &FdtStructBlock<'_> {
         &self.structure_block
     }
 
-    pub fn strings_block(&self) -> &FdtStringsBlock {
+    pub fn strings_block(&self) -> &FdtStringsBlock<'_> {
         &self.strings_block
     }
 }
 
+/// Reject a null pointer and a pointer that is not aligned to 8 bytes, as the Devicetree Specification requires
+/// for a blob in memory
+fn check_ptr(ptr: *const u8) -> Result<(), DeviceTreeError> {
+    if ptr.is_null() || !(ptr as usize).is_multiple_of(FDT_ALIGN) {
+        error!("Devicetree blob pointer {:p} is null or not aligned to {} bytes.", ptr, FDT_ALIGN);
+
+        return Err(DeviceTreeError::BadPointer);
+    }
+
+    Ok(())
+}
+
 
 impl FdtReserveEntry {
+    pub fn new(address: u64, size: u64) -> Self {
+        Self { address, size }
+    }
+
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
     pub fn from_bytes(bytes: &mut &[u8]) -> Option<Self> {
         Some( Self {
             address: utils::take_be_u64(bytes)?,
@@ -103,6 +177,50 @@ impl FdtReserveEntry {
     }
 }
 
+impl<'a> FdtReserveBlock<'a> {
+    /// Take the memory reservation block from the front of `bytes`, including its terminating entry
+    pub fn from_bytes(bytes: &mut &'a [u8]) -> Self {
+        let block = *bytes;
+
+        while let Some(entry) = FdtReserveEntry::from_bytes(bytes) {
+            if !entry.end_of_list() {
+                debug!("Adding reserved memory entry.");
+            } else {
+                debug!("End of adding reserved memory entry.");
+                break;
+            }
+        }
+
+        Self(&block[..block.len() - bytes.len()])
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        self.0
+    }
+
+    /// Iterate over the entries, without the terminating one
+    pub fn iter(&self) -> FdtReserveIter<'a> {
+        FdtReserveIter(self.0)
+    }
+}
+
+/// Iterator over the entries of a memory reservation block
+pub struct FdtReserveIter<'a>(&'a [u8]);
+
+impl<'a> Iterator for FdtReserveIter<'a> {
+    type Item = FdtReserveEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = FdtReserveEntry::from_bytes(&mut self.0)?;
+
+        if entry.end_of_list() {
+            return None;
+        }
+
+        Some(entry)
+    }
+}
+
 impl FdtPropDescribe {
     pub fn from_bytes(bytes: &mut &[u8]) -> Option<Self> {
         Some( Self {
@@ -115,6 +233,10 @@ impl FdtPropDescribe {
         self.len as usize
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn name_off(&self) -> usize {
         self.name_off as usize
     }
@@ -134,84 +256,72 @@ impl<'a> FdtStructBlock<'a> {
     pub fn bytes(&self) -> &[u8] {
         self.0
     }
+}
 
-    pub fn parsing(&mut self, strings_block: &FdtStringsBlock) -> Result<DeviceTree, DeviceTreeError> {
-        debug!("Converting dtb to tree structure.");
-
-        let mut current = DeviceTreeNode::new_wrap();
-
-        current.borrow_mut().set_name("root");
-
-        let mut bytes = self.0;
-
-        loop {
-            let token = Token::from_bytes(&mut bytes)?;
-
-            match token {
-                Token::TokenBeginNode => { 
-                    let name = utils::take_utf8_until_nul_aligned(&mut bytes, 4).unwrap();
-    
-                    if name.is_empty() {
-                        debug!("Adding root node.");
-                        continue;
-                    }
-    
-                    let next = DeviceTreeNode::new_wrap();
+impl<'a> FdtStringsBlock<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self(bytes)
+    }
 
-                    current.add_child(name, Rc::clone(&next));
+    pub fn find(&self, offset: usize) -> Result<&str, DeviceTreeError> {
+        if offset > self.0.len() {
+            return Err(DeviceTreeError::BadStringsBlockOffset);
+        }
 
-                    current = Rc::clone(&next);
-                }
-                Token::TokenProp => {
-                    let prop_describe = FdtPropDescribe::from_bytes(&mut bytes).unwrap();
-        
-                    let name = strings_block.find(prop_describe.name_off()).unwrap();
+        let find = self.0.get(offset..).unwrap();
 
-                    let mut raw_value = utils::take_aligned(&mut bytes, prop_describe.len(), 4).unwrap();
-        
-                    let prop = DeviceTreeProperty::from_bytes(name, &mut raw_value);
+        let name = CStr::from_bytes_until_nul(find).unwrap().to_str().unwrap();
 
-                    current.borrow_mut().add_prop(prop);
-                }
-                Token::TokenEndNode => {
-                    debug!("End of node '{}'.", current.borrow().name());
+        Ok(name)
+    }
 
-                    if !current.borrow().has_parent() {
-                        break;
-                    }
+    /// Iterate over the strings of the block together with their offsets
+    pub fn iter(&self) -> FdtStringsIter<'a> {
+        FdtStringsIter { bytes: self.0, offset: 0 }
+    }
 
-                    let parent = Rc::clone(&current.borrow().parent().unwrap());
+    /// Strings that no property name of `structure_block` points into
+    ///
+    /// A name pointing into the middle of a string shares its suffix, so that string counts as used.
+    #[cfg(feature = "alloc")]
+    pub fn unused(&self, structure_block: &FdtStructBlock<'a>) -> Result<Vec<(usize, &'a str)>, DeviceTreeError> {
+        let mut name_offsets = Vec::new();
 
-                    current = Rc::clone(&parent);
-                }
-                Token::TokenEnd => {
-                    break;
-                }
-                _ => ()
+        for event in structure_block.events(self) {
+            if let FdtEventKind::Prop(name, _) = event?.kind {
+                name_offsets.push(name.as_ptr() as usize - self.0.as_ptr() as usize);
             }
         }
 
-        debug!("End of parsing.");
+        let unused = self.iter()
+            .filter(|(offset, s)| !name_offsets.iter().any(|name_off| (*offset..=offset + s.len()).contains(name_off)))
+            .collect();
 
-        Ok(DeviceTree::new(current))
+        Ok(unused)
     }
 }
 
-impl<'a> FdtStringsBlock<'a> {
-    pub fn from_bytes(bytes: &'a [u8]) -> Self {
-        Self(bytes)
-    }
+/// Iterator over the strings of a strings block, yielding the offset and the string
+///
+/// Stops at the first string that is not NUL-terminated UTF-8.
+pub struct FdtStringsIter<'a> {
+    bytes: &'a [u8],
+    offset: usize
+}
 
-    pub fn find(&self, offset: usize) -> Result<&str, DeviceTreeError> {
-        if offset > self.0.len() {
-            return Err(DeviceTreeError::BadStringsBlockOffset);
-        }
+impl<'a> Iterator for FdtStringsIter<'a> {
+    type Item = (usize, &'a str);
 
-        let find = self.0.get(offset..).unwrap();
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.bytes.get(self.offset..)?;
 
-        let name = CStr::from_bytes_until_nul(find).unwrap().to_str().unwrap();
+        let s = CStr::from_bytes_until_nul(rest).ok()?.to_str().ok()?;
 
-        Ok(name)
+        let offset = self.offset;
+
+        self.offset += s.len() + 1;
+
+        Some((offset, s))
     }
 }
 
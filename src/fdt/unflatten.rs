@@ -0,0 +1,132 @@
+use alloc::rc::Rc;
+use log::debug;
+
+use crate::{
+    utils,
+    DeviceTree, 
+    DeviceTreeError, 
+    DeviceTreeBlob,
+    tree::{
+        node::{
+            self,
+            DeviceTreeNode
+        }, 
+        prop::DeviceTreeProperty,
+    }
+};
+use super::blob::{
+    FdtPropDescribe,
+    FdtStructBlock,
+    FdtStringsBlock,
+    Token
+};
+
+impl DeviceTree {
+    pub fn from_bytes(bytes: &mut &[u8]) -> Result<Self, DeviceTreeError> {
+        let mut dtb = DeviceTreeBlob::from_bytes(bytes)?;
+
+        dtb.to_tree()
+    }
+
+    /// Parse the blob located at `ptr`, see DeviceTreeBlob::from_ptr
+    /// 
+    /// # Safety
+    /// 
+    /// Same as DeviceTreeBlob::from_ptr. The tree owns its data, so the memory is only read during this call.
+    pub unsafe fn from_ptr(ptr: *const u8) -> Result<Self, DeviceTreeError> {
+        DeviceTreeBlob::from_ptr(ptr)?.to_tree()
+    }
+
+    /// Parse the blob located at `ptr`, see DeviceTreeBlob::from_ptr_checked
+    /// 
+    /// # Safety
+    /// 
+    /// Same as DeviceTreeBlob::from_ptr_checked.
+    pub unsafe fn from_ptr_checked(ptr: *const u8) -> Result<Self, DeviceTreeError> {
+        DeviceTreeBlob::from_ptr_checked(ptr)?.to_tree()
+    }
+
+    /// Parse a blob that must be aligned to 8 bytes, see DeviceTreeBlob::from_aligned_bytes
+    pub fn from_aligned_bytes(bytes: &[u8]) -> Result<Self, DeviceTreeError> {
+        DeviceTreeBlob::from_aligned_bytes(bytes)?.to_tree()
+    }
+}
+
+impl<'a> DeviceTreeBlob<'a> {
+    pub fn to_tree(&mut self) -> Result<DeviceTree, DeviceTreeError> {
+        let mut tree = self.structure_block.parsing(&self.strings_block)?;
+
+        for entry in self.memory_reservation_block.iter() {
+            tree.add_memory_reservation(entry);
+        }
+
+        tree.set_boot_cpuid_phys(self.header.boot_cpuid_phys());
+
+        Ok(tree)
+    }
+}
+
+impl<'a> FdtStructBlock<'a> {
+    pub fn parsing(&mut self, strings_block: &FdtStringsBlock) -> Result<DeviceTree, DeviceTreeError> {
+        debug!("Converting dtb to tree structure.");
+
+        let root = DeviceTreeNode::new_wrap();
+
+        root.borrow_mut().set_name("root");
+
+        let mut current = Rc::clone(&root);
+
+        let mut bytes = self.0;
+
+        loop {
+            let token = Token::from_bytes(&mut bytes)?;
+
+            match token {
+                Token::TokenBeginNode => { 
+                    let name = utils::take_utf8_until_nul_aligned(&mut bytes, 4).unwrap();
+    
+                    if name.is_empty() {
+                        debug!("Adding root node.");
+                        continue;
+                    }
+    
+                    let next = DeviceTreeNode::new_wrap();
+
+                    node::insert_child(&current, name, Rc::clone(&next));
+
+                    current = Rc::clone(&next);
+                }
+                Token::TokenProp => {
+                    let prop_describe = FdtPropDescribe::from_bytes(&mut bytes).unwrap();
+        
+                    let name = strings_block.find(prop_describe.name_off()).unwrap();
+
+                    let raw_value = utils::take_aligned(&mut bytes, prop_describe.len(), 4).unwrap();
+        
+                    let prop = DeviceTreeProperty::from_bytes(name, raw_value);
+
+                    current.borrow_mut().insert_prop(prop);
+                }
+                Token::TokenEndNode => {
+                    debug!("End of node '{}'.", current.borrow().name());
+
+                    if !current.borrow().has_parent() {
+                        break;
+                    }
+
+                    let parent = current.borrow().parent().unwrap();
+
+                    current = parent;
+                }
+                Token::TokenEnd => {
+                    break;
+                }
+                _ => ()
+            }
+        }
+
+        debug!("End of parsing.");
+
+        Ok(DeviceTree::new(root))
+    }
+}
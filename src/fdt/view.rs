@@ -0,0 +1,312 @@
+use core::str;
+use log::debug;
+
+use crate::{
+    DeviceTreeError,
+    DeviceTreeBlob
+};
+use super::events::{
+    FdtEvents,
+    FdtEventKind,
+    read_event,
+    read_be_u32
+};
+
+/// Read-only view of a Devicetree Blob
+///
+/// Names and values borrow straight from the blob, so nothing is copied and no heap is needed.
+#[derive(Clone, Copy, Debug)]
+pub struct Fdt<'a> {
+    structure: &'a [u8],
+    strings: &'a [u8]
+}
+
+/// Node of a Fdt view
+#[derive(Clone, Copy, Debug)]
+pub struct FdtNode<'a> {
+    fdt: Fdt<'a>,
+    name: &'a str,
+    /// Offset in the structure block of the FDT_BEGIN_NODE token
+    offset: usize,
+    /// Offset in the structure block of the first token after the node name
+    body: usize
+}
+
+/// Property of a Fdt view
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FdtProperty<'a> {
+    name: &'a str,
+    value: &'a [u8]
+}
+
+impl<'a> DeviceTreeBlob<'a> {
+    /// Zero-copy view of the blob, see Fdt
+    pub fn view(&self) -> Result<Fdt<'a>, DeviceTreeError> {
+        Fdt::new(self.structure_block.0, self.strings_block.0)
+    }
+}
+
+impl<'a> Fdt<'a> {
+    /// Create a view over a structure block and a strings block
+    ///
+    /// The structure block is checked once here: every token, name and string offset must be valid,
+    /// the block must hold exactly one top-level node with an empty name, the root, and the nodes must be
+    /// balanced and terminated by FDT_END. The lookups afterwards cannot fail.
+    pub fn new(structure: &'a [u8], strings: &'a [u8]) -> Result<Self, DeviceTreeError> {
+        let fdt = Self { structure, strings };
+
+        let (mut opened, mut closed, mut terminated) = (false, false, false);
+
+        for event in fdt.events() {
+            let event = event?;
+
+            match event.kind {
+                FdtEventKind::Nop => (),
+                FdtEventKind::BeginNode("") if !opened => opened = true,
+                _ if !opened => return Err(DeviceTreeError::BadToken),
+                FdtEventKind::End if closed => terminated = true,
+                _ if closed => return Err(DeviceTreeError::BadToken),
+                FdtEventKind::EndNode if event.depth == 0 => closed = true,
+                _ => ()
+            }
+        }
+
+        if !terminated {
+            return Err(DeviceTreeError::BadToken);
+        }
+
+        debug!("Valid structure block for zero-copy view.");
+
+        Ok(fdt)
+    }
+
+    /// The root node
+    pub fn root(&self) -> FdtNode<'a> {
+        let mut offset = 0;
+
+        loop {
+            match self.item(offset).unwrap() {
+                (FdtEventKind::BeginNode(name), next) => return FdtNode { fdt: *self, name, offset, body: next },
+                (_, next) => offset = next
+            }
+        }
+    }
+
+    /// Find a node by its full path, e.g. '/cpus/cpu@0'
+    pub fn find_node(&self, path: &str) -> Option<FdtNode<'a>> {
+        let mut current = self.root();
+
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            current = current.find_child(name)?;
+        }
+
+        Some(current)
+    }
+
+    /// Iterate over the tokens of the structure block, see FdtEvents
+    pub fn events(&self) -> FdtEvents<'a> {
+        FdtEvents::new(self.structure, self.strings)
+    }
+
+    fn item(&self, offset: usize) -> Result<(FdtEventKind<'a>, usize), DeviceTreeError> {
+        read_event(self.structure, self.strings, offset)
+    }
+
+    /// Offset of the token following the subtree whose first token after the name is at `offset`
+    fn skip_node(&self, offset: usize) -> usize {
+        let mut events = FdtEvents::at(self.structure, self.strings, offset, 1);
+
+        events.skip_subtree().unwrap();
+
+        events.offset()
+    }
+}
+
+impl<'a> FdtNode<'a> {
+    /// Full name of the node, e.g. 'cpu@0'. The root node has an empty name.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Offset of the node in the structure block
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn properties(&self) -> FdtPropIter<'a> {
+        FdtPropIter { fdt: self.fdt, offset: self.body }
+    }
+
+    pub fn property(&self, name: &str) -> Option<FdtProperty<'a>> {
+        self.properties().find(|prop| prop.name == name)
+    }
+
+    pub fn children(&self) -> FdtNodeIter<'a> {
+        FdtNodeIter { fdt: self.fdt, offset: self.body }
+    }
+
+    pub fn find_child(&self, name: &str) -> Option<FdtNode<'a>> {
+        self.children().find(|child| child.name == name)
+    }
+
+    /// Whether the 'compatible' property of the node contains `compatible`
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        match self.property("compatible") {
+            Some(prop) => prop.strings().any(|s| s == compatible),
+            None => false
+        }
+    }
+}
+
+/// Iterator over the properties of a FdtNode
+pub struct FdtPropIter<'a> {
+    fdt: Fdt<'a>,
+    offset: usize
+}
+
+impl<'a> Iterator for FdtPropIter<'a> {
+    type Item = FdtProperty<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (item, next) = self.fdt.item(self.offset).ok()?;
+
+            match item {
+                FdtEventKind::Prop(name, value) => {
+                    self.offset = next;
+
+                    return Some(FdtProperty { name, value });
+                },
+                FdtEventKind::Nop => self.offset = next,
+                _ => return None
+            }
+        }
+    }
+}
+
+/// Iterator over the children of a FdtNode
+pub struct FdtNodeIter<'a> {
+    fdt: Fdt<'a>,
+    offset: usize
+}
+
+impl<'a> Iterator for FdtNodeIter<'a> {
+    type Item = FdtNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (item, next) = self.fdt.item(self.offset).ok()?;
+
+            match item {
+                FdtEventKind::BeginNode(name) => {
+                    let start = self.offset;
+
+                    self.offset = self.fdt.skip_node(next);
+
+                    return Some(FdtNode { fdt: self.fdt, name, offset: start, body: next });
+                },
+                FdtEventKind::Prop(..) | FdtEventKind::Nop => self.offset = next,
+                FdtEventKind::EndNode | FdtEventKind::End => return None
+            }
+        }
+    }
+}
+
+impl<'a> FdtProperty<'a> {
+    pub fn new(name: &'a str, value: &'a [u8]) -> Self {
+        Self { name, value }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn value(&self) -> &'a [u8] {
+        self.value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// The value as a single u32 cell
+    pub fn u32(&self) -> Result<u32, DeviceTreeError> {
+        let bytes: [u8; 4] = self.value.try_into().map_err(|_| DeviceTreeError::BadPropValue)?;
+
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    /// The value as a u64 made of two cells
+    pub fn u64(&self) -> Result<u64, DeviceTreeError> {
+        let bytes: [u8; 8] = self.value.try_into().map_err(|_| DeviceTreeError::BadPropValue)?;
+
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    /// The value as a phandle
+    pub fn phandle(&self) -> Result<u32, DeviceTreeError> {
+        self.u32()
+    }
+
+    /// The value as a single NUL-terminated string
+    pub fn str(&self) -> Result<&'a str, DeviceTreeError> {
+        match self.value.split_last() {
+            Some((0, s)) if !s.contains(&0) => str::from_utf8(s).map_err(|_| DeviceTreeError::BadPropValue),
+            _ => Err(DeviceTreeError::BadPropValue)
+        }
+    }
+
+    /// Iterate over the value as a list of NUL-terminated strings
+    ///
+    /// The iterator is empty if the value is not a valid string list.
+    pub fn strings(&self) -> FdtStrings<'a> {
+        match self.value.split_last() {
+            Some((0, s)) if str::from_utf8(s).is_ok() => FdtStrings(Some(s)),
+            _ => FdtStrings(None)
+        }
+    }
+
+    /// Iterate over the value as big endian u32 cells
+    pub fn cells(&self) -> Result<FdtCells<'a>, DeviceTreeError> {
+        if !self.value.len().is_multiple_of(4) {
+            return Err(DeviceTreeError::BadPropValue);
+        }
+
+        Ok(FdtCells(self.value))
+    }
+}
+
+/// Iterator over the strings of a string list property
+pub struct FdtStrings<'a>(Option<&'a [u8]>);
+
+impl<'a> Iterator for FdtStrings<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.0?;
+
+        let (s, rest) = match bytes.iter().position(|&b| b == 0) {
+            Some(nul) => (&bytes[..nul], Some(&bytes[nul + 1..])),
+            None => (bytes, None)
+        };
+
+        self.0 = rest;
+
+        str::from_utf8(s).ok()
+    }
+}
+
+/// Iterator over the cells of a property
+pub struct FdtCells<'a>(&'a [u8]);
+
+impl<'a> Iterator for FdtCells<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = read_be_u32(self.0, 0)?;
+
+        self.0 = &self.0[4..];
+
+        Some(value)
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::vec::Vec;
+use log::debug;
+
+use crate::{
+    DeviceTree,
+    DeviceTreeError,
+    tree::{
+        node::DeviceTreeNode,
+        overlay::WriteOptions
+    }
+};
+use super::{
+    header::{
+        FdtHeader,
+        FDT_HEADER_SIZE
+    },
+    blob::{
+        FDT_BEGIN_NODE,
+        FDT_END_NODE,
+        FDT_PROP,
+        FDT_END
+    }
+};
+
+/// Size in bytes of an entry of the memory reservation block
+const RESERVE_ENTRY_SIZE: usize = 16;
+
+impl DeviceTree {
+    /// Serialize the tree into a Devicetree Blob
+    pub fn to_dtb(&self, options: &WriteOptions) -> Result<Vec<u8>, DeviceTreeError> {
+        debug!("Converting tree structure to dtb.");
+
+        let tree = self.resolve(options)?;
+
+        let mut structure_block = Vec::new();
+        let mut strings_block = Vec::new();
+
+        write_node(&tree.root().borrow(), true, &mut structure_block, &mut strings_block);
+
+        push_be_u32(&mut structure_block, FDT_END);
+
+        let off_mem_rsvmap = FDT_HEADER_SIZE;
+        let off_dt_struct = off_mem_rsvmap + (tree.memory_reservations().len() + 1) * RESERVE_ENTRY_SIZE;
+        let off_dt_strings = off_dt_struct + structure_block.len();
+        let totalsize = off_dt_strings + strings_block.len();
+
+        let header = FdtHeader::new(
+            totalsize as u32,
+            off_dt_struct as u32,
+            off_dt_strings as u32,
+            off_mem_rsvmap as u32,
+            tree.boot_cpuid_phys(),
+            strings_block.len() as u32,
+            structure_block.len() as u32
+        );
+
+        let mut dtb = Vec::with_capacity(totalsize);
+
+        dtb.extend_from_slice(&header.to_bytes());
+
+        for entry in tree.memory_reservations() {
+            dtb.extend_from_slice(&entry.address().to_be_bytes());
+            dtb.extend_from_slice(&entry.size().to_be_bytes());
+        }
+
+        dtb.extend_from_slice(&[0u8; RESERVE_ENTRY_SIZE]);
+        dtb.extend_from_slice(&structure_block);
+        dtb.extend_from_slice(&strings_block);
+
+        debug!("End of writing, totalsize: {}.", totalsize);
+
+        Ok(dtb)
+    }
+}
+
+fn write_node(node: &DeviceTreeNode, is_root: bool, structure_block: &mut Vec<u8>, strings_block: &mut Vec<u8>) {
+    push_be_u32(structure_block, FDT_BEGIN_NODE);
+
+    // The root node has an empty name in the blob
+    if !is_root {
+        structure_block.extend_from_slice(node.name().as_bytes());
+    }
+
+    structure_block.push(0);
+    pad_to_4(structure_block);
+
+    for (name, prop) in node.prop_iter() {
+        let value = prop.raw_value();
+
+        push_be_u32(structure_block, FDT_PROP);
+        push_be_u32(structure_block, value.len() as u32);
+        push_be_u32(structure_block, string_offset(strings_block, name) as u32);
+
+        structure_block.extend_from_slice(value);
+        pad_to_4(structure_block);
+    }
+
+    for (_, child) in node.children_iter() {
+        write_node(&child.borrow(), false, structure_block, strings_block);
+    }
+
+    push_be_u32(structure_block, FDT_END_NODE);
+}
+
+/// Offset of `name` in the strings block, appending it if it is not there yet
+///
+/// Like dtc, a name that is the suffix of a string already in the block shares its bytes.
+fn string_offset(strings_block: &mut Vec<u8>, name: &str) -> usize {
+    let name = name.as_bytes();
+
+    let found = strings_block
+        .windows(name.len() + 1)
+        .position(|s| &s[..name.len()] == name && s[name.len()] == 0);
+
+    if let Some(offset) = found {
+        return offset;
+    }
+
+    let offset = strings_block.len();
+
+    strings_block.extend_from_slice(name);
+    strings_block.push(0);
+
+    offset
+}
+
+fn push_be_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_be_bytes());
+}
+
+fn pad_to_4(bytes: &mut Vec<u8>) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(0);
+    }
+}
@@ -1,8 +1,6 @@
 #![no_std]
-#![feature(slice_take)]
-#![feature(cstr_from_bytes_until_nul)]
-#![feature(format_args_nl)]
 
+#[cfg(feature = "alloc")]
 #[cfg_attr(test, macro_use)]
 pub mod tree;
 pub mod fdt;
@@ -11,43 +9,110 @@ pub mod utils;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "alloc")]
 #[macro_use]
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
+#[cfg(feature = "alloc")]
 use crate::tree::node::DeviceTreeNodeWrap;
 use crate::fdt::{
 	header::FdtHeader,
 	blob::{
-		FdtReserveEntry, 
+		FdtReserveBlock, 
 		FdtStructBlock, 
 		FdtStringsBlock
 	},
 };
+#[cfg(feature = "alloc")]
+use crate::fdt::blob::FdtReserveEntry;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum DeviceTreeError {
 	/* Device Tree parsing error */
 	BadMagic(u32),
     BadVersion(u32),
+	BadTotalSize,
+	BadPointer,
 	BadToken,
 	BadStringsBlockOffset,
 	NotAToken,
 	BadPropValue,
 	BadPropType,
 	PropAlreadyParsed,
+	/* Device Tree writing error */
+	DuplicateLabel,
+	UnresolvedReference,
+	/* Device Tree in-place editing error */
+	NoSpace,
+	NotFound,
+	NodeExists,
+	BadLayout,
+	BadPath,
+	BadNodeName,
+	BadPropName,
+	/* Binding schema error */
+	BadSchema,
     /* Device Tree processing error */
 	CpuNumInvalid,
+	BadPhandle,
+	BadTopology,
+	/* File I/O error */
+	#[cfg(feature = "std")]
+	Io(std::io::ErrorKind),
+}
+
+impl core::fmt::Display for DeviceTreeError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			Self::BadMagic(magic) => write!(f, "bad magic number {:#x}", magic),
+			Self::BadVersion(version) => write!(f, "unsupported version {}", version),
+			Self::BadTotalSize => write!(f, "bad total size in header"),
+			Self::BadPointer => write!(f, "null or misaligned blob pointer"),
+			Self::BadToken => write!(f, "malformed structure block"),
+			Self::BadStringsBlockOffset => write!(f, "offset out of the strings block"),
+			Self::NotAToken => write!(f, "unknown token in structure block"),
+			Self::BadPropValue => write!(f, "bad property value"),
+			Self::BadPropType => write!(f, "bad property type"),
+			Self::PropAlreadyParsed => write!(f, "property already parsed"),
+			Self::DuplicateLabel => write!(f, "duplicate label"),
+			Self::UnresolvedReference => write!(f, "reference to unknown label"),
+			Self::NoSpace => write!(f, "not enough space in the buffer"),
+			Self::NotFound => write!(f, "node or property not found"),
+			Self::NodeExists => write!(f, "node already exists"),
+			Self::BadLayout => write!(f, "blocks of the blob are not in the expected order"),
+			Self::BadPath => write!(f, "bad node path"),
+			Self::BadNodeName => write!(f, "bad node name"),
+			Self::BadPropName => write!(f, "bad property name"),
+			Self::BadSchema => write!(f, "malformed binding schema"),
+			Self::CpuNumInvalid => write!(f, "invalid number of cpus"),
+			Self::BadPhandle => write!(f, "reference to unknown phandle"),
+			Self::BadTopology => write!(f, "malformed cpu topology"),
+			#[cfg(feature = "std")]
+			Self::Io(kind) => write!(f, "I/O error: {}", kind)
+		}
+	}
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for DeviceTreeError {}
+
+/// Owned and mutable devicetree, requires the feature 'alloc'
+#[cfg(feature = "alloc")]
 pub struct DeviceTree {
-	root: DeviceTreeNodeWrap
+	root: DeviceTreeNodeWrap,
+	memory_reservations: Vec<FdtReserveEntry>,
+	boot_cpuid_phys: u32
 }
 
 pub struct DeviceTreeBlob<'a> {
 	header: FdtHeader,
-    memory_reservation_block: Vec<FdtReserveEntry>,
+    memory_reservation_block: FdtReserveBlock<'a>,
     structure_block: FdtStructBlock<'a>,
     strings_block: FdtStringsBlock<'a>
 }
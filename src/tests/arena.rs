@@ -0,0 +1,67 @@
+use crate::{
+	DeviceTree,
+	DeviceTreeError,
+	tree::{
+		arena::ArenaTree,
+		node::{
+			DeviceTreeNode,
+			insert_child
+		},
+		prop::DeviceTreeProperty,
+		overlay::WriteOptions
+	}
+};
+
+#[test]
+fn navigation() {
+	let mut tree = ArenaTree::new();
+
+	let root = tree.root();
+	let cpus = tree.add_child(root, "cpus").unwrap();
+	let cpu_0 = tree.add_child(cpus, "cpu@0").unwrap();
+	let cpu_1 = tree.add_child(cpus, "cpu@1").unwrap();
+
+	tree[cpu_1].add_prop(DeviceTreeProperty::from_bytes("reg", &1_u32.to_be_bytes())).unwrap();
+
+	assert_eq!(tree.add_child(cpus, "cpu@0"), Ok(cpu_0));
+	assert_eq!(tree.len(), 4);
+	assert_eq!(tree.parent(cpu_0), Some(cpus));
+	assert_eq!(tree.first_child(cpus), Some(cpu_0));
+	assert_eq!(tree.next_sibling(cpu_0), Some(cpu_1));
+	assert_eq!(tree.prev_sibling(cpu_0), None);
+	assert_eq!(tree.find_node("/cpus/cpu@1"), Some(cpu_1));
+	assert_eq!(tree.path(cpu_1), "/cpus/cpu@1");
+	assert_eq!(tree[cpu_1].prop_value("reg").unwrap().raw_value(), &[0, 0, 0, 1]);
+
+	assert_eq!(tree.add_child(cpus, "cpu@"), Err(DeviceTreeError::BadNodeName));
+	assert_eq!(tree.add_child(cpus, "/"), Err(DeviceTreeError::BadNodeName));
+	assert_eq!(tree[cpu_1].add_prop(DeviceTreeProperty::from_bytes("re g", &[])), Err(DeviceTreeError::BadPropName));
+	assert_eq!(tree.len(), 4);
+}
+
+#[test]
+fn conversion() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let tree = DeviceTree::from_bytes(&mut dtb).unwrap();
+
+	let arena = ArenaTree::from(&tree);
+
+	assert_eq!(arena.len(), tree.nodes().len());
+	assert_eq!(arena.children(arena.find_node("/cpus").unwrap()).len(), 4);
+
+	let back = DeviceTree::from(&arena);
+
+	assert_eq!(back.to_dtb(&WriteOptions::new()).unwrap(), include_bytes!("./dtb/test1.dtb"));
+
+	// Names a blob may carry even though they are not valid survive the conversions
+	let long = "a-node-name-longer-than-31-characters";
+
+	insert_child(tree.root(), long, DeviceTreeNode::new_wrap());
+	tree.find_node("/chosen").unwrap().borrow_mut().insert_prop(DeviceTreeProperty::from_bytes(long, &[]));
+
+	let back = DeviceTree::from(&ArenaTree::from(&tree));
+
+	assert!(back.find_node(&format!("/{}", long)).is_some());
+	assert!(back.find_node("/chosen").unwrap().borrow().prop_exists(long));
+}
@@ -1,5 +1,6 @@
 use crate::{
 	DeviceTreeBlob,
+	DeviceTreeError,
 	fdt::blob::Token
 };
 
@@ -42,6 +43,7 @@ fn strings_block() {
 	assert_eq!(strings_block.find(27), Ok("compatible"));
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn blob_to_tree() {
     let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
@@ -52,3 +54,121 @@ fn blob_to_tree() {
 
     assert_eq!(tree.num_cpus(), 4);
 }
+
+#[test]
+fn memory_reservation_block() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let blob = DeviceTreeBlob::from_bytes(&mut dtb).unwrap();
+
+	assert_eq!(blob.memory_reservation_block().bytes().len(), 16);
+	assert_eq!(blob.memory_reservation_block().iter().count(), 0);
+}
+
+/// Copy of test1.dtb in a buffer aligned to 8 bytes
+#[repr(align(8))]
+struct AlignedDtb([u8; include_bytes!("./dtb/test1.dtb").len()]);
+
+fn aligned_dtb() -> AlignedDtb {
+	AlignedDtb(*include_bytes!("./dtb/test1.dtb"))
+}
+
+#[test]
+fn from_ptr() {
+	let buffer = aligned_dtb();
+
+	let ptr = buffer.0.as_ptr();
+
+	let blob = unsafe { DeviceTreeBlob::from_ptr_checked(ptr) }.unwrap();
+
+	assert_eq!(blob.header().totalsize(), include_bytes!("./dtb/test1.dtb").len());
+
+	#[cfg(feature = "alloc")]
+	assert_eq!(unsafe { crate::DeviceTree::from_ptr(ptr) }.unwrap().num_cpus(), 4);
+}
+
+#[test]
+fn from_aligned_bytes() {
+	let buffer = aligned_dtb();
+
+	let blob = DeviceTreeBlob::from_aligned_bytes(&buffer.0).unwrap();
+
+	assert_eq!(blob.header().totalsize(), buffer.0.len());
+	assert_eq!(DeviceTreeBlob::from_aligned_bytes(&buffer.0[4..]).err(), Some(DeviceTreeError::BadPointer));
+
+	#[cfg(feature = "alloc")]
+	assert_eq!(crate::DeviceTree::from_aligned_bytes(&buffer.0).unwrap().num_cpus(), 4);
+}
+
+#[test]
+fn from_ptr_checked() {
+	let mut buffer = aligned_dtb();
+
+	let ptr = buffer.0.as_ptr();
+
+	assert_eq!(unsafe { DeviceTreeBlob::from_ptr_checked(ptr.wrapping_add(4)) }.err(), Some(DeviceTreeError::BadPointer));
+	assert_eq!(unsafe { DeviceTreeBlob::from_ptr_checked(core::ptr::null()) }.err(), Some(DeviceTreeError::BadPointer));
+
+	buffer.0[..4].fill(0);
+
+	let ptr = buffer.0.as_ptr();
+
+	assert_eq!(unsafe { DeviceTreeBlob::from_ptr_checked(ptr) }.err(), Some(DeviceTreeError::BadMagic(0)));
+}
+
+#[test]
+fn corrupt_header() {
+	let dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let with_field = |offset: usize, value: u32| {
+		let mut dtb = *include_bytes!("./dtb/test1.dtb");
+		dtb[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+		dtb
+	};
+
+	// Truncated blob, then header fields pointing out of 'totalsize': totalsize, off_dt_struct, off_dt_strings,
+	// off_mem_rsvmap and size_dt_struct
+	assert_eq!(DeviceTreeBlob::from_bytes(&mut &dtb[..20]).err(), Some(DeviceTreeError::BadTotalSize));
+	assert_eq!(DeviceTreeBlob::from_bytes(&mut &dtb[..dtb.len() - 1]).err(), Some(DeviceTreeError::BadTotalSize));
+
+	for (offset, value) in [(4, 8), (8, dtb.len() as u32), (12, u32::MAX), (16, dtb.len() as u32 + 1), (36, u32::MAX)] {
+		let dtb = with_field(offset, value);
+
+		assert_eq!(DeviceTreeBlob::from_bytes(&mut &dtb[..]).err(), Some(DeviceTreeError::BadTotalSize));
+	}
+
+	// The blocks are taken at their offsets: starting the strings block a byte later shifts the names
+	let field = |offset: usize| u32::from_be_bytes(dtb[offset..offset + 4].try_into().unwrap());
+
+	let mut dtb = with_field(12, field(12) + 1);
+	dtb[32..36].copy_from_slice(&(field(32) - 1).to_be_bytes());
+
+	assert_eq!(DeviceTreeBlob::from_bytes(&mut &dtb[..]).unwrap().strings_block().find(0), Ok("address-cells"));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn strings_block_unused() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let blob = DeviceTreeBlob::from_bytes(&mut dtb).unwrap();
+
+	let strings_block = blob.strings_block();
+
+	assert_eq!(strings_block.iter().nth(2), Some((27, "compatible")));
+	assert_eq!(strings_block.iter().count(), 31);
+	assert!(strings_block.unused(blob.structure_block()).unwrap().is_empty());
+
+	let mut buffer = include_bytes!("./dtb/test1.dtb").to_vec();
+
+	let mut editable = crate::fdt::edit::FdtBlobMut::new(&mut buffer).unwrap();
+
+	editable.delprop("/soc/ethernet@10090000", "phy-mode").unwrap();
+
+	let blob = DeviceTreeBlob::from_bytes(&mut editable.bytes()).unwrap();
+
+	let unused = blob.strings_block().unused(blob.structure_block()).unwrap();
+
+	assert_eq!(unused.len(), 1);
+	assert_eq!(unused[0].1, "phy-mode");
+}
@@ -0,0 +1,123 @@
+use alloc::{
+	string::String,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	fdt::edit::FdtBlobMut,
+	tree::{
+		check::{
+			Check,
+			Checks,
+			Severity
+		}
+	}
+};
+use super::{
+	tree,
+	add_node
+};
+
+#[test]
+fn clean_tree() {
+	assert_eq!(tree().check(&Checks::new()), []);
+}
+
+/// Replace the first occurrence of `from` in `bytes` by `to` of the same length
+fn rename(bytes: &mut [u8], from: &[u8], to: &[u8]) {
+	let offset = bytes.windows(from.len()).position(|window| window == from).unwrap();
+
+	bytes[offset..offset + to.len()].copy_from_slice(to);
+}
+
+#[test]
+fn findings() {
+	// Names that add_child and add_prop reject can only come from a blob: valid names are patched in its bytes
+	let mut buffer = include_bytes!("./dtb/test1.dtb").to_vec();
+
+	buffer.resize(buffer.len() + 128, 0);
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	blob.add_subnode("/", "bad-name@1-2").unwrap();
+	blob.setprop("/bad-name@1-2", "interrupts", &[0, 0, 0, 5]).unwrap();
+	blob.setprop("/bad-name@1-2", "status-", b"okay\0").unwrap();
+
+	let size = blob.totalsize();
+
+	rename(&mut buffer[..size], b"bad-name@1-2\0", b"bad name@1@2\0");
+	rename(&mut buffer[..size], b"status-\0", b"status!\0");
+
+	let tree = DeviceTree::from_bytes(&mut &buffer[..size]).unwrap();
+
+	add_node(&tree.find_node("/soc").unwrap(), "serial@0x10012000", &[
+		("reg", &[0; 12]),
+		("interrupt-parent", &[0, 0, 0, 8]),
+		("interrupts", &[0, 0, 0, 5, 0, 0]),
+		("phandle", &[0, 0, 0, 8])
+	]);
+	add_node(&tree.find_node("/soc").unwrap(), "gpio@10010000", &[("reg", &[0; 16]), ("status", b"okay")]);
+	add_node(tree.root(), "timer", &[("reg", &[0; 16])]);
+
+	let report: Vec<String> = tree.check(&Checks::new()).iter().map(|finding| format!("{}", finding)).collect();
+
+	assert_eq!(report, [
+		"ERROR (node_name_chars): /bad name@1@2: Bad character ' ' in node name",
+		"ERROR (node_name_format): /bad name@1@2: multiple '@' in node name",
+		"ERROR (property_name_chars): /bad name@1@2: Bad character '!' in property name 'status!'",
+		"ERROR (property_types): /soc/gpio@10010000: property 'status' does not have the type of the specification",
+		"ERROR (duplicate_phandle): /soc/serial@0x10012000: duplicated phandle 0x8 (seen before at /soc/interrupt-controller@c000000)",
+		"Warning (unit_address_vs_reg): /bad name@1@2: node has a unit name, but no reg or ranges property",
+		"Warning (unit_address_vs_reg): /timer: node has a reg or ranges property, but no unit name",
+		"Warning (unit_address_format): /soc/serial@0x10012000: unit name should not have leading \"0x\"",
+		"Warning (unique_unit_address): /soc/gpio@10010000: duplicate unit-address (also used in node /soc/serial@10010000)",
+		"Warning (reg_format): /soc/serial@0x10012000: \"reg\" property has invalid length (12 bytes) (#address-cells == 2, #size-cells == 2)",
+		"Warning (interrupts_property): /soc/serial@0x10012000: interrupts size is (6), expected multiple of 4",
+		"Warning (interrupts_property): /bad name@1@2: Missing interrupt-parent"
+	]);
+}
+
+#[test]
+fn interrupts_property() {
+	let tree = tree();
+
+	// The interrupt controller is the implicit parent of its children
+	add_node(&tree.find_node("/soc/interrupt-controller@c000000").unwrap(), "msi", &[("interrupts", &[0, 0, 0, 1])]);
+	add_node(&tree.find_node("/soc").unwrap(), "timer-a", &[("interrupts-extended", &[0, 0, 0, 5, 0, 0, 0, 7, 0, 0, 0, 8, 0, 0, 0, 3])]);
+	add_node(&tree.find_node("/soc").unwrap(), "timer-b", &[
+		("interrupts-extended", &[0, 0, 0, 5, 0, 0, 0, 7, 0, 0, 0, 8]),
+		("interrupts", &[0, 0])
+	]);
+	add_node(&tree.find_node("/soc").unwrap(), "timer-c", &[("interrupts-extended", &[0, 0, 0, 0x40, 0, 0, 0, 1])]);
+
+	let mut checks = Checks::none();
+
+	checks.enable(Check::InterruptsProperty);
+
+	let report: Vec<String> = tree.check(&checks).iter().map(|finding| format!("{}", finding)).collect();
+
+	assert_eq!(report, [
+		"Warning (interrupts_property): /soc/timer-b: interrupts-extended size (12) too small for #interrupt-cells 1 of \
+		/soc/interrupt-controller@c000000",
+		"Warning (interrupts_property): /soc/timer-c: Bad phandle 0x40 in interrupts-extended (cell 0)"
+	]);
+}
+
+#[test]
+fn configure() {
+	let tree = tree();
+
+	add_node(tree.root(), "timer", &[("reg", &[0; 12])]);
+
+	let mut checks = Checks::none();
+
+	checks.enable(Check::RegFormat).enable(Check::UnitAddressVsReg).disable(Check::UnitAddressVsReg);
+
+	let findings = tree.check(&checks);
+
+	assert_eq!(findings.len(), 1);
+	assert_eq!(findings[0].check, Check::RegFormat);
+	assert_eq!(findings[0].severity, Severity::Warning);
+	assert_eq!(findings[0].path, "/timer");
+}
@@ -0,0 +1,107 @@
+use alloc::rc::Rc;
+
+use crate::{
+	DeviceTreeError,
+	tree::{
+		node::{
+			DeviceTreeNode,
+			AddChild
+		},
+		prop::DeviceTreeProperty,
+		cpu::Cpu
+	}
+};
+use super::tree;
+
+#[test]
+fn cpus() {
+	let tree = tree();
+
+	let cpus = tree.cpus().unwrap();
+
+	assert_eq!(cpus.len(), 4);
+	assert!(cpus.iter().map(|cpu| cpu.id()).eq([0, 1, 2, 3]));
+
+	let cpu = &cpus[1];
+
+	assert!(cpu.is_enabled());
+	assert_eq!(cpu.isa(), Some("rv64imafdcsu"));
+	assert_eq!(cpu.mmu_type(), Some("riscv,sv48"));
+	assert_eq!(cpu.timebase_frequency(), Some(10_000_000));
+	assert_eq!(cpu.clock_frequency(), None);
+	assert_eq!(cpu.enable_method(), [] as [&str; 0]);
+	assert_eq!(cpu.interrupt_controller().unwrap().borrow().phandle(), Some(5));
+	assert_eq!(cpus[0].mmu_type(), None);
+}
+
+#[test]
+fn cpu_properties() {
+	let tree = tree();
+
+	let cpus = tree.find_node("/cpus").unwrap();
+
+	// Neither the cpu-map nor a cache node is a CPU
+	cpus.add_child("cpu-map", DeviceTreeNode::new_wrap()).unwrap();
+	cpus.add_child("l2-cache", DeviceTreeNode::new_wrap()).unwrap();
+
+	assert_eq!(tree.num_cpus(), 4);
+
+	let cpu = tree.find_node("/cpus/cpu@3").unwrap();
+
+	cpu.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("status", b"disabled\0")).unwrap();
+	cpu.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("enable-method", b"spin-table\0")).unwrap();
+	cpu.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("cpu-release-addr", &0x8000fff8_u64.to_be_bytes())).unwrap();
+	cpu.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("clock-frequency", &1_000_000_000_u32.to_be_bytes())).unwrap();
+
+	let cpu = tree.cpus().unwrap().pop().unwrap();
+
+	assert!(!cpu.is_enabled());
+	assert_eq!(cpu.enable_method(), ["spin-table"]);
+	assert_eq!(cpu.cpu_release_addr(), Some(0x8000fff8));
+	assert_eq!(cpu.clock_frequency(), Some(1_000_000_000));
+
+	// A CPU that cannot be read is left out, the others are still there
+	let cpu = tree.find_node("/cpus/cpu@2").unwrap();
+
+	cpu.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("reg", &[0; 2])).unwrap();
+
+	assert!(tree.cpus().unwrap().iter().map(|cpu| cpu.id()).eq([0, 1, 3]));
+
+	cpu.borrow_mut().remove_prop("reg");
+
+	assert_eq!(Cpu::from_node(&cpu, &cpus.borrow()).err(), Some(DeviceTreeError::BadPropValue));
+	assert_eq!(tree.cpus().unwrap().len(), 3);
+}
+
+#[test]
+fn cpu_address_cells() {
+	let tree = tree();
+
+	let cpus = tree.find_node("/cpus").unwrap();
+
+	// Without '#address-cells', 'reg' has two cells
+	cpus.borrow_mut().remove_prop("#address-cells");
+
+	for (i, (_, cpu)) in cpus.borrow().children_iter().enumerate() {
+		cpu.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("reg", &[0, 0, 0, 1, 0, 0, 0, i as u8])).unwrap();
+	}
+
+	assert!(tree.cpus().unwrap().iter().map(|cpu| cpu.id()).eq([0x100000000, 0x100000001, 0x100000002, 0x100000003]));
+}
+
+#[test]
+fn boot_cpu() {
+	let mut tree = tree();
+
+	assert_eq!(tree.boot_cpu().unwrap().unwrap().id(), 0);
+
+	tree.set_boot_cpuid_phys(2);
+
+	let boot_cpu = tree.boot_cpu().unwrap().unwrap();
+
+	assert!(Rc::ptr_eq(boot_cpu.node(), &tree.find_node("/cpus/cpu@2").unwrap()));
+
+	tree.set_boot_cpuid_phys(7);
+
+	assert!(tree.boot_cpu().unwrap().is_none());
+}
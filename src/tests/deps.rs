@@ -0,0 +1,208 @@
+use alloc::{
+	string::String,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTreeError,
+	tree::{
+		node::DeviceTreeNodeWrap,
+		prop::DeviceTreeProperty
+	}
+};
+use super::{
+	tree,
+	add_node
+};
+
+fn cells(cells: &[u32]) -> Vec<u8> {
+	cells.iter().flat_map(|cell| cell.to_be_bytes()).collect()
+}
+
+fn paths(nodes: &[DeviceTreeNodeWrap]) -> Vec<String> {
+	nodes.iter().map(|node| node.borrow().path()).collect()
+}
+
+fn position(order: &[String], path: &str) -> usize {
+	order.iter().position(|other| other == path).unwrap()
+}
+
+#[test]
+fn probe_order() {
+	let tree = tree();
+
+	let order = tree.probe_order();
+
+	assert!(!order.has_cycles());
+
+	let order = paths(order.order());
+
+	assert_eq!(order.len(), tree.nodes().len());
+	assert_eq!(order[0], "/");
+
+	// The clock controller needs both fixed clocks, the serial port the clock and interrupt controllers
+	assert!(position(&order, "/hfclk") < position(&order, "/soc/clock-controller@10000000"));
+	assert!(position(&order, "/rtcclk") < position(&order, "/soc/clock-controller@10000000"));
+	assert!(position(&order, "/soc/clock-controller@10000000") < position(&order, "/soc/serial@10010000"));
+	assert!(position(&order, "/soc/interrupt-controller@c000000") < position(&order, "/soc/serial@10010000"));
+
+	// The PLIC needs the interrupt controllers of all CPUs
+	for cpu in 0..4 {
+		let intc = format!("/cpus/cpu@{}/interrupt-controller", cpu);
+
+		assert!(position(&order, &intc) < position(&order, "/soc/interrupt-controller@c000000"));
+	}
+
+	// Nodes that wait for nobody keep the tree order
+	assert!(position(&order, "/chosen") < position(&order, "/aliases"));
+}
+
+#[test]
+fn suppliers() {
+	let tree = tree();
+
+	let soc = tree.find_node("/soc").unwrap();
+
+	add_node(&soc, "gpio@10060000", &[
+		("compatible", b"sifive,gpio0\0"),
+		("#gpio-cells", &cells(&[2])),
+		("phandle", &cells(&[0x20]))
+	]);
+
+	let pinctrl = add_node(&soc, "pinctrl@10070000", &[("compatible", b"sifive,pinctrl\0")]);
+
+	add_node(&pinctrl, "mmc-pins", &[("phandle", &cells(&[0x21]))]);
+
+	let mmc = add_node(&soc, "mmc@10050000", &[
+		("compatible", b"sifive,mmc\0"),
+		("cd-gpios", &cells(&[0x20, 5, 0])),
+		("vmmc-supply", &cells(&[0x22])),
+		("pinctrl-0", &cells(&[0x21])),
+		("pinctrl-names", b"default\0")
+	]);
+
+	add_node(tree.root(), "regulator", &[("compatible", b"regulator-fixed\0"), ("phandle", &cells(&[0x22]))]);
+
+	let graph = tree.dependency_graph();
+
+	// The pin configuration belongs to the pin controller
+	assert_eq!(
+		paths(&graph.suppliers(&mmc)),
+		["/soc", "/soc/gpio@10060000", "/soc/pinctrl@10070000", "/regulator"]
+	);
+
+	let properties: Vec<&str> = graph.dependencies()
+		.iter()
+		.filter(|dependency| dependency.consumer().borrow().path() == "/soc/mmc@10050000")
+		.map(|dependency| dependency.property())
+		.collect();
+
+	assert_eq!(properties, ["parent", "cd-gpios", "vmmc-supply", "pinctrl-0"]);
+
+	assert_eq!(
+		paths(&graph.consumers(&tree.find_node("/soc/clock-controller@10000000").unwrap())),
+		["/soc/serial@10010000", "/soc/ethernet@10090000"]
+	);
+
+	// Disabled nodes are left out, and so are the links to them
+	tree.find_node("/regulator").unwrap()
+		.borrow_mut()
+		.add_prop(DeviceTreeProperty::from_bytes("status", b"disabled\0"))
+		.unwrap();
+
+	let graph = tree.dependency_graph();
+
+	assert_eq!(graph.nodes().len(), tree.nodes().len() - 1);
+	assert_eq!(graph.suppliers(&mmc).len(), 3);
+
+	// Broken references are skipped and listed, the rest of the graph is still built
+	mmc.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("vmmc-supply", &cells(&[0x40]))).unwrap();
+
+	let graph = tree.dependency_graph();
+
+	assert_eq!(paths(&graph.suppliers(&mmc)), ["/soc", "/soc/gpio@10060000", "/soc/pinctrl@10070000"]);
+	assert_eq!(graph.broken_links().len(), 1);
+	assert_eq!(graph.broken_links()[0].property(), "vmmc-supply");
+	assert_eq!(graph.broken_links()[0].error(), &DeviceTreeError::BadPhandle);
+
+	// A phandle to a node without '#gpio-cells', after one that can be followed
+	mmc.borrow_mut().remove_prop("vmmc-supply");
+	mmc.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("cd-gpios", &cells(&[0x20, 5, 0, 0x22, 0]))).unwrap();
+
+	let graph = tree.dependency_graph();
+
+	assert_eq!(graph.suppliers(&mmc).len(), 3);
+	assert_eq!(graph.broken_links()[0].property(), "cd-gpios");
+	assert_eq!(graph.broken_links()[0].error(), &DeviceTreeError::BadPropValue);
+	assert_eq!(tree.probe_order().order().len(), graph.nodes().len());
+}
+
+#[test]
+fn gpio_count() {
+	let tree = tree();
+
+	let soc = tree.find_node("/soc").unwrap();
+
+	// The number of GPIOs of a controller is not a reference, here to the clock controller
+	let gpio = add_node(&soc, "gpio@10060000", &[
+		("compatible", b"snps,dw-apb-gpio\0"),
+		("snps,nr-gpios", &cells(&[7])),
+		("nr-gpios", &cells(&[0x40]))
+	]);
+
+	let graph = tree.dependency_graph();
+
+	assert_eq!(paths(&graph.suppliers(&gpio)), ["/soc"]);
+	assert!(graph.broken_links().is_empty());
+}
+
+#[test]
+fn implicit_interrupt_parent() {
+	let tree = tree();
+
+	// An interrupt nexus without 'interrupt-parent' above it, and a device behind a bridge node below it
+	let nexus = add_node(&tree.find_node("/soc").unwrap(), "pcie@30000000", &[
+		("compatible", b"pci-host-ecam-generic\0"),
+		("#interrupt-cells", &cells(&[1])),
+		("interrupt-map", &[])
+	]);
+	let bridge = add_node(&nexus, "pci@0,0", &[]);
+	let device = add_node(&bridge, "ethernet@0,0", &[("compatible", b"pci8086,100e\0"), ("interrupts", &cells(&[1]))]);
+
+	let graph = tree.dependency_graph();
+
+	assert_eq!(paths(&graph.suppliers(&device)), ["/soc/pcie@30000000", "/soc/pcie@30000000/pci@0,0"]);
+	assert!(graph.broken_links().is_empty());
+
+	let interrupts: Vec<String> = graph.dependencies()
+		.iter()
+		.filter(|dependency| dependency.property() == "interrupts")
+		.filter(|dependency| dependency.consumer().borrow().path() == "/soc/pcie@30000000/pci@0,0/ethernet@0,0")
+		.map(|dependency| dependency.supplier().borrow().path())
+		.collect();
+
+	assert_eq!(interrupts, ["/soc/pcie@30000000"]);
+}
+
+#[test]
+fn cycles() {
+	let tree = tree();
+
+	// The fixed clock now needs an output of the clock controller, which needs it in turn
+	tree.find_node("/hfclk").unwrap()
+		.borrow_mut()
+		.add_prop(DeviceTreeProperty::from_bytes("clocks", &cells(&[7, 0])))
+		.unwrap();
+
+	let order = tree.probe_order();
+
+	assert_eq!(order.cycles().len(), 1);
+	assert_eq!(paths(&order.cycles()[0]), ["/hfclk", "/soc/clock-controller@10000000"]);
+
+	// The cycle is broken in tree order and everything is still probed
+	let order = paths(order.order());
+
+	assert_eq!(order.len(), tree.nodes().len());
+	assert!(position(&order, "/hfclk") < position(&order, "/soc/clock-controller@10000000"));
+	assert!(position(&order, "/soc/clock-controller@10000000") < position(&order, "/soc/serial@10010000"));
+}
@@ -0,0 +1,121 @@
+use alloc::{
+	string::String,
+	vec::Vec
+};
+use core::cell::RefCell;
+
+use crate::{
+	tree::{
+		prop::DeviceTreeProperty,
+		driver::{
+			Driver,
+			DriverRegistry
+		}
+	}
+};
+use super::tree;
+
+fn paths<'a>(nodes: impl Iterator<Item = &'a crate::tree::node::DeviceTreeNodeWrap>) -> Vec<String> {
+	nodes.map(|node| node.borrow().path()).collect()
+}
+
+#[test]
+fn bind() {
+	let tree = tree();
+
+	tree.find_node("/soc/ethernet@10090000").unwrap()
+		.borrow_mut()
+		.add_prop(DeviceTreeProperty::from_bytes("compatible", b"sifive,fu540-c000-gem\0cdns,gem\0"))
+		.unwrap();
+	tree.find_node("/rtcclk").unwrap()
+		.borrow_mut()
+		.add_prop(DeviceTreeProperty::from_bytes("status", b"disabled\0"))
+		.unwrap();
+
+	let mut probed = Vec::new();
+
+	let report = {
+		let mut registry = DriverRegistry::new();
+
+		registry
+			.register(Driver::new("macb", &["cdns,gem"], |_, _| Ok(())))
+			.register(Driver::new("fixed-clock", &["fixed-clock"], |_, _| Ok(())))
+			.register(Driver::new("sifive-uart", &["sifive,uart0"], |node, compatible| {
+				probed.push((node.borrow().path(), String::from(compatible)));
+				Ok(())
+			}))
+			.register(Driver::new("macb-fu540", &["sifive,fu540-c000-gem"], |_, _| Ok(())))
+			.register(Driver::new("clint", &["riscv,clint0", "sifive,clint0"], |_, _| Err("no timer")));
+
+		assert_eq!(registry.drivers().len(), 5);
+
+		registry.bind(&tree)
+	};
+
+	assert_eq!(probed, [(String::from("/soc/serial@10010000"), String::from("sifive,uart0"))]);
+
+	// The most specific compatible wins over the order of registration, the disabled clock is left out
+	let bound: Vec<(String, &str, &str)> = report.bound()
+		.iter()
+		.map(|binding| (binding.node().borrow().path(), binding.driver(), binding.compatible()))
+		.collect();
+
+	assert_eq!(bound, [
+		(String::from("/hfclk"), "fixed-clock", "fixed-clock"),
+		(String::from("/soc/serial@10010000"), "sifive-uart", "sifive,uart0"),
+		(String::from("/soc/ethernet@10090000"), "macb-fu540", "sifive,fu540-c000-gem")
+	]);
+	assert!(report.is_bound(&tree.find_node("/hfclk").unwrap()));
+	assert!(!report.is_bound(&tree.find_node("/rtcclk").unwrap()));
+
+	assert_eq!(report.failed().len(), 1);
+	assert_eq!(report.failed()[0].0.node().borrow().path(), "/soc/clint@2000000");
+	assert_eq!(report.failed()[0].1, "no timer");
+
+	assert_eq!(paths(report.unmatched().iter()), [
+		"/",
+		"/cpus/cpu@0",
+		"/cpus/cpu@0/interrupt-controller",
+		"/cpus/cpu@1",
+		"/cpus/cpu@1/interrupt-controller",
+		"/cpus/cpu@2",
+		"/cpus/cpu@2/interrupt-controller",
+		"/cpus/cpu@3",
+		"/cpus/cpu@3/interrupt-controller",
+		"/soc",
+		"/soc/interrupt-controller@c000000",
+		"/soc/clock-controller@10000000"
+	]);
+
+	let report = format!("{}", report);
+
+	assert!(report.contains("bound: /soc/serial@10010000: sifive-uart (sifive,uart0)\n"));
+	assert!(report.contains("failed: /soc/clint@2000000: clint (riscv,clint0): no timer\n"));
+	assert!(report.contains("unmatched: /: sifive,hifive-unleashed-a00\n"));
+}
+
+#[test]
+fn bind_in_probe_order() {
+	let tree = tree();
+
+	let order = RefCell::new(Vec::new());
+
+	{
+		let mut registry: DriverRegistry<()> = DriverRegistry::new();
+
+		registry
+			.register(Driver::new("sifive-uart", &["sifive,uart0"], |node, _| {
+				order.borrow_mut().push(node.borrow().path());
+				Ok(())
+			}))
+			.register(Driver::new("fu540-prci", &["sifive,fu540-c000-prci"], |node, _| {
+				order.borrow_mut().push(node.borrow().path());
+				Ok(())
+			}));
+
+		// The serial port comes first in the tree but needs the clock controller
+		registry.bind_nodes(tree.probe_order().order());
+	}
+
+	assert_eq!(order.into_inner(), ["/soc/clock-controller@10000000", "/soc/serial@10010000"]);
+}
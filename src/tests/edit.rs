@@ -0,0 +1,200 @@
+use alloc::{
+	vec,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeBlob,
+	DeviceTreeError,
+	fdt::{
+		edit::FdtBlobMut,
+		header::FDT_HEADER_SIZE
+	}
+};
+
+/// Copy of test1.dtb with `spare` bytes of free space behind it
+fn buffer(spare: usize) -> Vec<u8> {
+	let mut buffer = include_bytes!("./dtb/test1.dtb").to_vec();
+
+	buffer.resize(buffer.len() + spare, 0);
+
+	buffer
+}
+
+#[test]
+fn setprop() {
+	let mut buffer = buffer(256);
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	blob.setprop("/chosen", "bootargs", b"console=ttySIF0\0").unwrap();
+	blob.setprop("/chosen", "linux,initrd-start", &0x84000000_u32.to_be_bytes()).unwrap();
+	blob.setprop("/cpus/cpu@0", "status", b"disabled\0").unwrap();
+
+	let fdt = blob.view().unwrap();
+
+	let chosen = fdt.find_node("/chosen").unwrap();
+
+	assert_eq!(chosen.property("bootargs").unwrap().str(), Ok("console=ttySIF0"));
+	assert_eq!(chosen.property("linux,initrd-start").unwrap().u32(), Ok(0x84000000));
+	assert_eq!(chosen.properties().last().unwrap().name(), "linux,initrd-start");
+	assert_eq!(fdt.find_node("/cpus/cpu@0").unwrap().property("status").unwrap().str(), Ok("disabled"));
+
+	let tree = DeviceTree::from_bytes(&mut blob.bytes()).unwrap();
+
+	assert_eq!(tree.num_cpus(), 4);
+}
+
+#[test]
+fn appendprop_delprop() {
+	let mut buffer = buffer(64);
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	blob.appendprop("/soc/ethernet@10090000", "clock-names", b"tx\0").unwrap();
+	blob.delprop("/soc/ethernet@10090000", "phy-mode").unwrap();
+
+	assert_eq!(blob.delprop("/soc/ethernet@10090000", "phy-mode"), Err(DeviceTreeError::NotFound));
+
+	let fdt = blob.view().unwrap();
+
+	let ethernet = fdt.find_node("/soc/ethernet@10090000").unwrap();
+
+	assert!(ethernet.property("clock-names").unwrap().strings().eq(["pclk", "hclk", "tx"]));
+	assert!(ethernet.property("phy-mode").is_none());
+	assert_eq!(ethernet.property("reg-names").unwrap().str(), Ok("control"));
+}
+
+#[test]
+fn nodes() {
+	let mut buffer = buffer(128);
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	blob.add_subnode("/", "reserved-memory").unwrap();
+	blob.add_subnode("/reserved-memory", "mmode_resv0@80000000").unwrap();
+	blob.setprop("/reserved-memory/mmode_resv0@80000000", "no-map", &[]).unwrap();
+
+	assert_eq!(blob.add_subnode("/", "reserved-memory"), Err(DeviceTreeError::NodeExists));
+
+	blob.del_node("/cpus").unwrap();
+
+	assert_eq!(blob.del_node("/"), Err(DeviceTreeError::BadPath));
+
+	let fdt = blob.view().unwrap();
+
+	assert!(fdt.find_node("/cpus").is_none());
+	assert!(fdt.find_node("/reserved-memory/mmode_resv0@80000000").unwrap().property("no-map").unwrap().is_empty());
+	assert_eq!(fdt.root().children().last().unwrap().name(), "reserved-memory");
+}
+
+#[test]
+fn bad_names() {
+	let mut buffer = buffer(128);
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	let children = blob.view().unwrap().root().children().count();
+
+	for name in ["", "/", "soc/serial", "serial\0", "serial@", "this-base-name-is-longer-than-31"] {
+		assert_eq!(blob.add_subnode("/", name), Err(DeviceTreeError::BadNodeName), "{:?}", name);
+	}
+
+	for name in ["", "a/b", "model\0", "status!"] {
+		assert_eq!(blob.setprop("/", name, &[]), Err(DeviceTreeError::BadPropName), "{:?}", name);
+	}
+
+	assert_eq!(blob.view().unwrap().root().children().count(), children);
+}
+
+#[test]
+fn rootless() {
+	let mut buffer = buffer(128);
+
+	// The structure block starts with FDT_END
+	let off_dt_struct = u32::from_be_bytes(buffer[8..12].try_into().unwrap()) as usize;
+
+	buffer[off_dt_struct..off_dt_struct + 4].copy_from_slice(&9_u32.to_be_bytes());
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	assert_eq!(blob.setprop("/chosen", "bootargs", b"\0"), Err(DeviceTreeError::BadToken));
+	assert_eq!(blob.delprop("/", "model"), Err(DeviceTreeError::BadToken));
+	assert_eq!(blob.add_subnode("/", "chosen"), Err(DeviceTreeError::BadToken));
+	assert_eq!(blob.del_node("/cpus"), Err(DeviceTreeError::BadToken));
+}
+
+#[test]
+fn mem_rsv() {
+	let mut buffer = buffer(32);
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	blob.add_mem_rsv(0x80000000, 0x200000).unwrap();
+	blob.add_mem_rsv(0x90000000, 0x1000).unwrap();
+	blob.del_mem_rsv(0).unwrap();
+
+	assert_eq!(blob.num_mem_rsv(), 1);
+
+	let blob = DeviceTreeBlob::from_bytes(&mut blob.bytes()).unwrap();
+
+	let entry = blob.memory_reservation_block().iter().next().unwrap();
+
+	assert_eq!((entry.address(), entry.size()), (0x90000000, 0x1000));
+	assert_eq!(blob.view().unwrap().find_node("/cpus").unwrap().children().count(), 4);
+}
+
+#[test]
+fn no_space() {
+	let mut buffer = buffer(8);
+
+	let original = buffer.clone();
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	assert_eq!(blob.setprop("/chosen", "a-new-property", &[0; 4]), Err(DeviceTreeError::NoSpace));
+	assert_eq!(blob.add_mem_rsv(0x80000000, 0x1000), Err(DeviceTreeError::NoSpace));
+	assert_eq!(blob.setprop("/missing", "status", b"okay\0"), Err(DeviceTreeError::NotFound));
+
+	assert_eq!(buffer, original);
+}
+
+#[test]
+fn pack() {
+	let mut buffer = buffer(64);
+
+	let mut blob = FdtBlobMut::new(&mut buffer).unwrap();
+
+	let strings_size = blob.strings().len();
+
+	blob.delprop("/soc/ethernet@10090000", "phy-mode").unwrap();
+	blob.pack().unwrap();
+
+	assert_eq!(blob.strings().len(), strings_size - "phy-mode".len() - 1);
+	assert_eq!(blob.totalsize(), FDT_HEADER_SIZE + 16 * (blob.num_mem_rsv() + 1) + blob.structure().len() + blob.strings().len());
+
+	let tree = DeviceTree::from_bytes(&mut blob.bytes()).unwrap();
+
+	assert_eq!(tree.num_cpus(), 4);
+	assert_eq!(blob.view().unwrap().find_node("/soc/ethernet@10090000").unwrap().property("reg-names").unwrap().str(), Ok("control"));
+}
+
+#[test]
+fn open_into() {
+	let original = buffer(0);
+
+	let blob = DeviceTreeBlob::from_bytes(&mut &original[..]).unwrap();
+
+	let mut buffer = vec![0; original.len() + 128];
+
+	let mut blob = blob.open_into(&mut buffer).unwrap();
+
+	blob.setprop("/chosen", "bootargs", b"console=ttySIF0\0").unwrap();
+
+	assert_eq!(blob.view().unwrap().find_node("/chosen").unwrap().property("bootargs").unwrap().str(), Ok("console=ttySIF0"));
+
+	let mut small = vec![0; 64];
+
+	assert_eq!(blob.open_into(&mut small).err(), Some(DeviceTreeError::NoSpace));
+}
@@ -0,0 +1,66 @@
+use alloc::vec::Vec;
+
+use crate::{
+	DeviceTreeBlob,
+	fdt::events::{
+		FdtEventKind,
+		WalkAction
+	}
+};
+
+#[test]
+fn events() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let blob = DeviceTreeBlob::from_bytes(&mut dtb).unwrap();
+
+	let events: Vec<_> = blob.events().map(|event| event.unwrap()).collect();
+
+	assert_eq!(events[0].kind, FdtEventKind::BeginNode(""));
+	assert_eq!(events[0].offset, 0);
+	assert_eq!(events[1].kind, FdtEventKind::Prop("#address-cells", &[0, 0, 0, 2]));
+	assert_eq!(events[1].offset, 8);
+	assert_eq!(events[1].depth, 0);
+
+	let last = events.last().unwrap();
+
+	assert_eq!(last.kind, FdtEventKind::End);
+	assert_eq!(last.depth, 0);
+	assert_eq!(last.offset + 4, blob.structure_block().bytes().len());
+
+	let phy = events.iter().find(|event| event.kind == FdtEventKind::BeginNode("ethernet-phy@0")).unwrap();
+
+	assert_eq!(phy.depth, 3);
+}
+
+#[test]
+fn walk() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let blob = DeviceTreeBlob::from_bytes(&mut dtb).unwrap();
+
+	let mut current = "";
+	let mut found = None;
+	let mut visited = Vec::new();
+
+	blob.walk(|event| match event.kind {
+		FdtEventKind::BeginNode("cpus") => WalkAction::SkipSubtree,
+		FdtEventKind::BeginNode(name) => {
+			current = name;
+			visited.push(name);
+
+			WalkAction::Continue
+		},
+		FdtEventKind::Prop("compatible", value) if value == b"sifive,uart0\0" => {
+			found = Some(current);
+
+			WalkAction::Stop
+		},
+		_ => WalkAction::Continue
+	}).unwrap();
+
+	assert_eq!(found, Some("serial@10010000"));
+	assert!(!visited.contains(&"cpu@0"));
+	assert!(visited.contains(&"memory@80000000"));
+	assert!(!visited.contains(&"clint@2000000"));
+}
@@ -0,0 +1,130 @@
+use alloc::rc::Rc;
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError,
+	tree::{
+		prop::DeviceTreeProperty,
+		check::{
+			Check,
+			Checks
+		}
+	}
+};
+use super::add_node;
+
+/// A single cell
+fn cell(value: u32) -> [u8; 4] {
+	value.to_be_bytes()
+}
+
+/// A display controller linked to an HDMI encoder, itself linked to a connector that does not link back
+fn tree() -> DeviceTree {
+	let tree = super::tree();
+	let soc = tree.find_node("/soc").unwrap();
+
+	let display = add_node(&soc, "display@20000000", &[]);
+	let port = add_node(&display, "port", &[]);
+
+	add_node(&port, "endpoint", &[("phandle", &cell(0x30)), ("remote-endpoint", &cell(0x31))]);
+
+	let hdmi = add_node(&soc, "hdmi@20100000", &[]);
+	let ports = add_node(&hdmi, "ports", &[("#address-cells", &cell(1)), ("#size-cells", &cell(0))]);
+	let port = add_node(&ports, "port@0", &[("reg", &cell(0))]);
+
+	add_node(&port, "endpoint", &[("phandle", &cell(0x31)), ("remote-endpoint", &cell(0x30))]);
+
+	let port = add_node(&ports, "port@1", &[("reg", &cell(1)), ("#address-cells", &cell(1)), ("#size-cells", &cell(0))]);
+
+	add_node(&port, "endpoint@0", &[("reg", &cell(0)), ("phandle", &cell(0x32)), ("remote-endpoint", &cell(0x33))]);
+	add_node(&port, "endpoint@1", &[("reg", &cell(1))]);
+
+	let connector = add_node(tree.root(), "connector", &[]);
+	let port = add_node(&connector, "port", &[]);
+
+	add_node(&port, "endpoint", &[("phandle", &cell(0x33)), ("remote-endpoint", &cell(0x31))]);
+
+	tree
+}
+
+#[test]
+fn ports() {
+	let tree = tree();
+
+	let hdmi = tree.find_node("/soc/hdmi@20100000").unwrap();
+	let ports = hdmi.borrow().ports().unwrap();
+
+	assert!(ports.iter().map(|port| port.id()).eq([0, 1]));
+	assert!(Rc::ptr_eq(&ports[1].device().unwrap(), &hdmi));
+
+	let endpoints = ports[1].endpoints().unwrap();
+
+	assert!(endpoints.iter().map(|endpoint| endpoint.id()).eq([0, 1]));
+	assert_eq!(endpoints[1].port().unwrap().unwrap().id(), 1);
+	assert_eq!(hdmi.borrow().endpoints().unwrap().len(), 3);
+	assert!(hdmi.borrow().port(1).unwrap().is_some());
+	assert!(hdmi.borrow().port(2).unwrap().is_none());
+
+	// A device with a single port without 'ports'
+	let display = tree.find_node("/soc/display@20000000").unwrap();
+	let ports = display.borrow().ports().unwrap();
+
+	assert_eq!(ports.len(), 1);
+	assert_eq!(ports[0].id(), 0);
+	assert!(Rc::ptr_eq(&ports[0].device().unwrap(), &display));
+
+	assert!(tree.find_node("/soc").unwrap().borrow().ports().unwrap().is_empty());
+}
+
+#[test]
+fn remote_endpoint() {
+	let tree = tree();
+
+	let display = tree.find_node("/soc/display@20000000").unwrap();
+	let endpoint = &display.borrow().endpoints().unwrap()[0];
+
+	let remote = endpoint.remote(&tree).unwrap().unwrap();
+
+	assert_eq!(remote.node().borrow().path(), "/soc/hdmi@20100000/ports/port@0/endpoint");
+	assert_eq!(endpoint.remote_port(&tree).unwrap().unwrap().id(), 0);
+	assert_eq!(endpoint.remote_device(&tree).unwrap().unwrap().borrow().path(), "/soc/hdmi@20100000");
+	assert!(endpoint.is_mutual(&tree).unwrap());
+
+	let hdmi = tree.find_node("/soc/hdmi@20100000").unwrap();
+	let endpoints = hdmi.borrow().endpoints().unwrap();
+
+	// The connector links back to the other port of the encoder
+	assert_eq!(endpoints[1].remote_device(&tree).unwrap().unwrap().borrow().path(), "/connector");
+	assert!(!endpoints[1].is_mutual(&tree).unwrap());
+	assert!(endpoints[2].remote(&tree).unwrap().is_none());
+
+	endpoints[2].node().borrow_mut().add_prop(DeviceTreeProperty::from_bytes("remote-endpoint", &0x40_u32.to_be_bytes())).unwrap();
+
+	assert_eq!(endpoints[2].remote(&tree).err(), Some(DeviceTreeError::BadPhandle));
+}
+
+#[test]
+fn graph_endpoint_check() {
+	let tree = tree();
+
+	let mut checks = Checks::none();
+
+	checks.enable(Check::GraphEndpoint);
+
+	let findings = tree.check(&checks);
+
+	assert_eq!(findings.len(), 2);
+	assert_eq!(
+		format!("{}", findings[0]),
+		"Warning (graph_endpoint): /soc/hdmi@20100000/ports/port@1/endpoint@0: \
+		graph connection to node '/connector/port/endpoint' is not bidirectional"
+	);
+	assert_eq!(findings[1].path, "/connector/port/endpoint");
+
+	tree.find_node("/connector/port/endpoint").unwrap()
+		.borrow_mut()
+		.add_prop(DeviceTreeProperty::from_bytes("remote-endpoint", &0x32_u32.to_be_bytes()))
+		.unwrap();
+
+	assert!(tree.check(&checks).is_empty());
+}
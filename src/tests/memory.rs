@@ -0,0 +1,130 @@
+use alloc::{
+	string::String,
+	vec::Vec
+};
+
+use crate::{
+	fdt::blob::FdtReserveEntry,
+	tree::{
+		memory::{
+			MemoryMap,
+			MemoryRange,
+			ReservedRange,
+			ReservedReason
+		}
+	}
+};
+use super::{
+	tree,
+	add_node
+};
+
+/// 'reg' with 2 address cells and 2 size cells
+fn reg(start: u64, size: u64) -> [u8; 16] {
+	let mut reg = [0; 16];
+
+	reg[..8].copy_from_slice(&start.to_be_bytes());
+	reg[8..].copy_from_slice(&size.to_be_bytes());
+
+	reg
+}
+
+fn ranges(map: &MemoryMap) -> Vec<(u64, u64)> {
+	map.usable().iter().map(|range| (range.start(), range.end())).collect()
+}
+
+#[test]
+fn memory() {
+	let tree = tree();
+
+	assert_eq!(tree.memory().unwrap(), [MemoryRange::new(0x80000000, 0x20000000)]);
+
+	// A second bank that overlaps the first one, and a disabled one
+	add_node(tree.root(), "memory@90000000", &[("device_type", b"memory\0"), ("reg", &reg(0x90000000, 0x20000000))]);
+	add_node(tree.root(), "memory@100000000", &[
+		("device_type", b"memory\0"),
+		("reg", &reg(0x100000000, 0x1000)),
+		("status", b"disabled\0")
+	]);
+
+	assert_eq!(tree.memory().unwrap(), [MemoryRange::new(0x80000000, 0x30000000)]);
+}
+
+#[test]
+fn memory_map() {
+	let mut tree = tree();
+
+	tree.add_memory_reservation(FdtReserveEntry::new(0x80000000, 0x1000));
+	// Overlaps the no-map region and sticks out of RAM
+	tree.add_memory_reservation(FdtReserveEntry::new(0x9ffff000, 0x2000));
+
+	let reserved_memory = add_node(tree.root(), "reserved-memory", &[
+		("#address-cells", &2_u32.to_be_bytes()),
+		("#size-cells", &2_u32.to_be_bytes()),
+		("ranges", &[])
+	]);
+
+	add_node(&reserved_memory, "mmode_resv0@9fff0000", &[("reg", &reg(0x9fff0000, 0x10000)), ("no-map", &[])]);
+
+	let map = tree.memory_map().unwrap();
+
+	assert_eq!(ranges(&map), [(0x80001000, 0x9fff0000)]);
+	assert_eq!(map.usable_size(), 0x1ffef000);
+	assert!(map.is_usable(0x90000000));
+	assert!(!map.is_usable(0x80000fff));
+
+	assert_eq!(map.reserved(), [
+		ReservedRange::new(MemoryRange::new(0x80000000, 0x1000), ReservedReason::MemoryReservation),
+		ReservedRange::new(
+			MemoryRange::new(0x9fff0000, 0x10000),
+			ReservedReason::NoMap(String::from("/reserved-memory/mmode_resv0@9fff0000"))
+		),
+		ReservedRange::new(MemoryRange::new(0x9ffff000, 0x2000), ReservedReason::MemoryReservation)
+	]);
+
+	// Regions without 'no-map' are reported, but stay usable
+	add_node(&reserved_memory, "linux,cma@90000000", &[("reg", &reg(0x90000000, 0x1000000)), ("reusable", &[])]);
+	add_node(&reserved_memory, "ramoops@98000000", &[("reg", &reg(0x98000000, 0x10000))]);
+
+	let map = tree.memory_map().unwrap();
+
+	assert_eq!(ranges(&map), [(0x80001000, 0x9fff0000)]);
+	assert_eq!(map.reserved()[1..3], [
+		ReservedRange::new(
+			MemoryRange::new(0x90000000, 0x1000000),
+			ReservedReason::Reserved(String::from("/reserved-memory/linux,cma@90000000"))
+		),
+		ReservedRange::new(
+			MemoryRange::new(0x98000000, 0x10000),
+			ReservedReason::Reserved(String::from("/reserved-memory/ramoops@98000000"))
+		)
+	]);
+
+	// A region that cannot be read is left out of the map
+	add_node(&reserved_memory, "bad@0", &[("reg", &[0; 12]), ("no-map", &[])]);
+
+	assert_eq!(tree.memory_map().unwrap(), map);
+}
+
+#[test]
+fn memory_map_edges() {
+	let ram = [
+		MemoryRange::new(0x3000, 0x1000),
+		MemoryRange::new(0x1000, 0x1000),
+		MemoryRange::new(0x2000, 0x1000),
+		MemoryRange::new(0x8000, 0),
+		MemoryRange::new(u64::MAX - 0xfff, 0x2000)
+	];
+
+	let reserved = |start, size| ReservedRange::new(MemoryRange::new(start, size), ReservedReason::MemoryReservation);
+
+	// Touching banks merge, holes at the edges and in the middle split them
+	let map = MemoryMap::new(&ram, vec![reserved(0x1000, 0x800), reserved(0x2800, 0x100), reserved(0x3f00, 0x1000)]);
+
+	assert_eq!(ranges(&map), [(0x1800, 0x2800), (0x2900, 0x3f00), (u64::MAX - 0xfff, u64::MAX)]);
+
+	// A reservation covering all RAM
+	let map = MemoryMap::new(&ram, vec![reserved(0, u64::MAX)]);
+
+	assert!(map.usable().is_empty());
+}
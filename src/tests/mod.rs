@@ -1,5 +1,74 @@
+#[cfg(feature = "alloc")]
 mod node;
+#[cfg(feature = "alloc")]
 mod prop;
+#[cfg(feature = "alloc")]
 mod tree;
 mod utils;
 mod blob;
+#[cfg(feature = "alloc")]
+mod writer;
+#[cfg(feature = "alloc")]
+mod arena;
+mod view;
+#[cfg(feature = "alloc")]
+mod events;
+#[cfg(feature = "alloc")]
+mod edit;
+#[cfg(feature = "alloc")]
+mod schema;
+#[cfg(feature = "alloc")]
+mod check;
+#[cfg(feature = "alloc")]
+mod cpu;
+#[cfg(feature = "alloc")]
+mod topology;
+#[cfg(feature = "alloc")]
+mod memory;
+#[cfg(feature = "alloc")]
+mod reserved;
+#[cfg(feature = "alloc")]
+mod numa;
+#[cfg(feature = "alloc")]
+mod pci;
+#[cfg(feature = "alloc")]
+mod graph;
+#[cfg(feature = "alloc")]
+mod deps;
+#[cfg(feature = "driver")]
+mod driver;
+
+#[cfg(feature = "alloc")]
+use crate::{
+	DeviceTree,
+	tree::{
+		node::{
+			DeviceTreeNode,
+			DeviceTreeNodeWrap,
+			AddChild
+		},
+		prop::DeviceTreeProperty
+	}
+};
+
+/// The tree of 'dtb/test1.dtb'
+#[cfg(feature = "alloc")]
+fn tree() -> DeviceTree {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	DeviceTree::from_bytes(&mut dtb).unwrap()
+}
+
+/// Add a child to `parent` with the given properties
+#[cfg(feature = "alloc")]
+fn add_node(parent: &DeviceTreeNodeWrap, name: &str, props: &[(&str, &[u8])]) -> DeviceTreeNodeWrap {
+	let node = DeviceTreeNode::new_wrap();
+
+	for (name, value) in props {
+		node.borrow_mut().add_prop(DeviceTreeProperty::from_bytes(name, value)).unwrap();
+	}
+
+	parent.add_child(name, node.clone()).unwrap();
+
+	node
+}
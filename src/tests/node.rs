@@ -1,15 +1,18 @@
 use alloc::{
-	string::ToString,
+	string::{String, ToString},
+	vec::Vec,
 	rc::Rc
 };
 
 use crate::{
+	DeviceTreeError,
 	tree::{
 		node::{
 			DeviceTreeNode,
 			AddChild,
 		},
-		prop::DeviceTreeProperty
+		prop::DeviceTreeProperty,
+		name::NodeName
 	},
 };
 
@@ -20,7 +23,7 @@ fn set_parent() {
 
 	child.borrow_mut().set_parent(Rc::clone(&parent));
 
-	assert_eq!(child.borrow().parent(), Some(&Rc::clone(&parent)));
+	assert_eq!(child.borrow().parent(), Some(Rc::clone(&parent)));
 }
 
 #[test]
@@ -33,9 +36,9 @@ fn add_child() {
 
 	child.borrow_mut().set_label("child");
 
-	parent.add_child("child", Rc::clone(&child));
+	parent.add_child("child", Rc::clone(&child)).unwrap();
 
-	let parent_of_child = Rc::clone(&child.borrow().parent().as_ref().unwrap());
+	let parent_of_child = child.borrow().parent().unwrap();
 
 	assert_eq!(parent_of_child.borrow().label(), Some(&"parent".to_string()));
 	assert_eq!(parent.borrow().find_child("child").unwrap().borrow().label(), Some(&"child".to_string()));
@@ -45,19 +48,84 @@ fn add_child() {
 fn add_prop() {
 	let mut node = DeviceTreeNode::new();
 
-	assert_eq!(node.add_prop(DeviceTreeProperty::from_bytes("name", "value".as_bytes())), None);
-	assert_eq!(node.prop_exists("name"), true);
+	assert_eq!(node.add_prop(DeviceTreeProperty::from_bytes("name", "value".as_bytes())), Ok(None));
+	assert!(node.prop_exists("name"));
 }
 
 #[test]
 fn delete_prop() {
 	let mut node = DeviceTreeNode::new();
 
-	node.add_prop(DeviceTreeProperty::from_bytes("name", "value".as_bytes()));
+	node.add_prop(DeviceTreeProperty::from_bytes("name", "value".as_bytes())).unwrap();
 
-	assert_eq!(node.prop_exists("name"), true);
+	assert!(node.prop_exists("name"));
 
 	node.remove_prop("name");
 
-	assert_eq!(node.prop_exists("name"), false);
+	assert!(!node.prop_exists("name"));
+}
+
+#[test]
+fn insertion_order() {
+	let parent = DeviceTreeNode::new_wrap();
+
+	parent.add_child("cpu@1", DeviceTreeNode::new_wrap()).unwrap();
+	parent.add_child("cpu@0", DeviceTreeNode::new_wrap()).unwrap();
+	parent.add_child("cpu@2", DeviceTreeNode::new_wrap()).unwrap();
+
+	parent.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("reg", &[0; 4])).unwrap();
+	parent.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("compatible", b"riscv\0")).unwrap();
+	parent.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("reg", &[1; 4])).unwrap();
+
+	let children: Vec<String> = parent.borrow().children_iter().map(|(name, _)| name.clone()).collect();
+
+	assert_eq!(children, ["cpu@1", "cpu@0", "cpu@2"]);
+	assert_eq!(parent.borrow().children_iter().len(), 3);
+	assert_eq!(parent.borrow().prop_iter().rev().len(), 2);
+
+	parent.borrow_mut().remove_prop("reg");
+	parent.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("reg", &[2; 4])).unwrap();
+
+	let props: Vec<String> = parent.borrow().prop_iter().map(|(name, _)| name.clone()).collect();
+
+	assert_eq!(props, ["compatible", "reg"]);
+	assert_eq!(parent.borrow().find_child("cpu@0").unwrap().borrow().name(), "cpu@0");
+}
+
+#[test]
+fn node_name() {
+	let name = NodeName::parse("memory@80000000").unwrap();
+
+	assert_eq!((name.base(), name.unit_address()), ("memory", Some("80000000")));
+	assert_eq!(name.unit_address_fields(), Ok(vec![0x80000000]));
+	assert_eq!(NodeName::parse("pci@1,8000").unwrap().unit_address_fields(), Ok(vec![1, 0x8000]));
+	assert_eq!(NodeName::parse("cpus").unwrap().unit_address_fields(), Ok(vec![]));
+	assert_eq!(NodeName::parse("partition@boot").unwrap().unit_address_fields(), Err(DeviceTreeError::BadNodeName));
+	assert!(NodeName::parse("/").unwrap().is_root());
+	assert_eq!(format!("{}", NodeName::parse("cpu@0").unwrap()), "cpu@0");
+
+	for bad in ["", "@0", "cpu@", "cpu 0", "cpu@0@1", "this-base-name-is-longer-than-31"] {
+		assert_eq!(NodeName::parse(bad), Err(DeviceTreeError::BadNodeName), "{}", bad);
+	}
+
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let tree = crate::DeviceTree::from_bytes(&mut dtb).unwrap();
+
+	let cpu = tree.find_node("/cpus/cpu@2").unwrap();
+
+	assert_eq!(cpu.borrow().node_name().unwrap().unit_address_fields(), Ok(vec![2]));
+	assert!(tree.root().borrow().node_name().unwrap().is_root());
+}
+
+#[test]
+fn reject_bad_names() {
+	let parent = DeviceTreeNode::new_wrap();
+
+	assert_eq!(parent.add_child("cpu 0", DeviceTreeNode::new_wrap()).err(), Some(DeviceTreeError::BadNodeName));
+	assert_eq!(parent.add_child("/", DeviceTreeNode::new_wrap()).err(), Some(DeviceTreeError::BadNodeName));
+	assert_eq!(parent.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("bad=name", &[])), Err(DeviceTreeError::BadPropName));
+	assert_eq!(parent.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("", &[])), Err(DeviceTreeError::BadPropName));
+	assert_eq!(parent.borrow().num_children(), 0);
+	assert!(parent.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("#address-cells", &[0, 0, 0, 1])).is_ok());
 }
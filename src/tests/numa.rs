@@ -0,0 +1,106 @@
+use crate::{
+	DeviceTree,
+	DeviceTreeError,
+	tree::{
+		node::DeviceTreeNodeWrap,
+		prop::DeviceTreeProperty,
+		memory::MemoryRange,
+		numa::DistanceMap
+	}
+};
+use super::{
+	tree,
+	add_node
+};
+
+fn set_numa_node_id(node: &DeviceTreeNodeWrap, id: u32) {
+	node.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("numa-node-id", &id.to_be_bytes())).unwrap();
+}
+
+/// Two sockets of two CPUs, each with a bank of memory
+fn numa_tree() -> DeviceTree {
+	let tree = tree();
+
+	for cpu in 0..4 {
+		set_numa_node_id(&tree.find_node(&format!("/cpus/cpu@{}", cpu)).unwrap(), cpu / 2);
+	}
+
+	set_numa_node_id(&tree.find_node("/memory@80000000").unwrap(), 0);
+
+	let memory = add_node(tree.root(), "memory@100000000", &[
+		("device_type", b"memory\0"),
+		("reg", &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0x20, 0, 0, 0])
+	]);
+
+	set_numa_node_id(&memory, 1);
+
+	tree
+}
+
+fn matrix(entries: &[(u32, u32, u32)]) -> alloc::vec::Vec<u8> {
+	entries.iter().flat_map(|&(from, to, distance)| [from, to, distance]).flat_map(u32::to_be_bytes).collect()
+}
+
+#[test]
+fn numa_nodes() {
+	let tree = tree();
+
+	// Without numa-node-id everything is in node 0
+	let nodes = tree.numa_nodes().unwrap();
+
+	assert_eq!(nodes.len(), 1);
+	assert_eq!(nodes[0].cpus().len(), 4);
+	assert_eq!(nodes[0].memory(), [MemoryRange::new(0x80000000, 0x20000000)]);
+
+	let tree = numa_tree();
+
+	let nodes = tree.numa_nodes().unwrap();
+
+	assert_eq!(nodes.len(), 2);
+	assert_eq!(nodes[1].id(), 1);
+	assert!(nodes[1].cpus().iter().map(|cpu| cpu.id()).eq([2, 3]));
+	assert_eq!(nodes[1].memory(), [MemoryRange::new(0x100000000, 0x20000000)]);
+	assert_eq!(tree.cpus().unwrap()[3].numa_node_id().unwrap(), Some(1));
+}
+
+#[test]
+fn distance_map() {
+	let tree = numa_tree();
+
+	// Default distances
+	let map = tree.numa_distance_map().unwrap();
+
+	assert_eq!(map.nodes(), [0, 1]);
+	assert_eq!(map.distance(0, 0), Some(10));
+	assert_eq!(map.distance(1, 0), Some(20));
+	assert_eq!(map.distance(0, 2), None);
+
+	let distance_map = add_node(tree.root(), "distance-map", &[
+		("compatible", b"numa-distance-map-v1\0"),
+		("distance-matrix", &matrix(&[(0, 0, 10), (0, 1, 30), (1, 1, 10), (0, 2, 40), (2, 0, 45)]))
+	]);
+
+	// Node 2 has neither CPUs nor memory, (1, 0) mirrors (0, 1) and (2, 0) is given in both directions
+	let map = tree.numa_distance_map().unwrap();
+
+	assert_eq!(map.nodes(), [0, 1, 2]);
+	assert_eq!(map.distance(1, 0), Some(30));
+	assert_eq!(map.distance(0, 2), Some(40));
+	assert_eq!(map.distance(2, 0), Some(45));
+	assert_eq!(map.distance(1, 2), Some(20));
+
+	distance_map.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("distance-matrix", &matrix(&[(0, 1, 10)]))).unwrap();
+
+	assert_eq!(tree.numa_distance_map().err(), Some(DeviceTreeError::BadPropValue));
+
+	// Only compatible distance maps count
+	distance_map.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("compatible", b"numa-distance-map-v2\0")).unwrap();
+
+	assert_eq!(tree.numa_distance_map().unwrap().distance(0, 1), Some(20));
+}
+
+#[test]
+fn distance_map_new() {
+	assert_eq!(DistanceMap::new(&[0], &[(0, 0, 20)]).err(), Some(DeviceTreeError::BadPropValue));
+	assert!(DistanceMap::new(&[], &[]).unwrap().nodes().is_empty());
+}
@@ -0,0 +1,245 @@
+use alloc::{
+	rc::Rc,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError,
+	tree::{
+		node::{
+			DeviceTreeNode,
+			DeviceTreeNodeWrap,
+			AddChild
+		},
+		prop::DeviceTreeProperty,
+		pci::{
+			PciAddress,
+			PciHostBridge,
+			PciSpace
+		}
+	}
+};
+
+const PLIC: u32 = 8;
+
+fn cells(cells: &[u32]) -> Vec<u8> {
+	cells.iter().flat_map(|cell| cell.to_be_bytes()).collect()
+}
+
+fn set_prop(node: &DeviceTreeNodeWrap, name: &str, value: &[u8]) {
+	node.borrow_mut().add_prop(DeviceTreeProperty::from_bytes(name, value)).unwrap();
+}
+
+/// Tree with a 'pci-host-ecam-generic' bridge under '/soc'
+fn tree() -> (DeviceTree, DeviceTreeNodeWrap) {
+	let tree = super::tree();
+
+	let pcie = DeviceTreeNode::new_wrap();
+
+	tree.find_node("/soc").unwrap().add_child("pcie@30000000", pcie.clone()).unwrap();
+
+	set_prop(&pcie, "compatible", b"pci-host-ecam-generic\0");
+	set_prop(&pcie, "device_type", b"pci\0");
+	set_prop(&pcie, "#address-cells", &cells(&[3]));
+	set_prop(&pcie, "#size-cells", &cells(&[2]));
+	set_prop(&pcie, "#interrupt-cells", &cells(&[1]));
+	set_prop(&pcie, "reg", &cells(&[0, 0x30000000, 0, 0x1000000]));
+	set_prop(&pcie, "bus-range", &cells(&[0x10, 0x1f]));
+	set_prop(&pcie, "ranges", &cells(&[
+		0x01000000, 0, 0, 0, 0x3eff0000, 0, 0x10000,
+		0x02000000, 0, 0x40000000, 0, 0x40000000, 0, 0x40000000,
+		0xc3000000, 0x4, 0, 0x4, 0, 0x4, 0
+	]));
+	set_prop(&pcie, "interrupt-map-mask", &cells(&[0x1800, 0, 0, 7]));
+	set_prop(&pcie, "interrupt-map", &cells(&[
+		0x0000, 0, 0, 1, PLIC, 32,
+		0x0000, 0, 0, 2, PLIC, 33,
+		0x0800, 0, 0, 1, PLIC, 33,
+		0x0800, 0, 0, 2, PLIC, 34
+	]));
+
+	(tree, pcie)
+}
+
+/// The only host bridge of the tree, which must decode
+fn only_bridge(tree: &DeviceTree) -> PciHostBridge {
+	let bridges = tree.pci_host_bridges();
+
+	assert_eq!(bridges.invalid().len(), 0);
+
+	bridges.bridges().last().unwrap().clone()
+}
+
+/// Why the host bridge of the tree is left out
+fn error(tree: &DeviceTree) -> Option<DeviceTreeError> {
+	tree.pci_host_bridges().invalid().first().map(|(_, error)| error.clone())
+}
+
+#[test]
+fn pci_address() {
+	let address = PciAddress::new(0xc3110a10, 0x4, 0x1000);
+
+	assert_eq!(address.space(), PciSpace::Memory64);
+	assert!(address.is_prefetchable());
+	assert!(!address.is_relocatable());
+	assert!(!address.is_aliased());
+	assert_eq!((address.bus(), address.device(), address.function(), address.register()), (0x11, 1, 2, 0x10));
+	assert_eq!(address.address(), 0x4_0000_1000);
+
+	assert_eq!(PciAddress::config(0x11, 1, 2).hi(), 0x00110a00);
+	assert_eq!(PciAddress::config(0, 0, 0).space(), PciSpace::Config);
+}
+
+#[test]
+fn host_bridge() {
+	let (tree, pcie) = tree();
+
+	let bridges = tree.pci_host_bridges();
+
+	assert_eq!(bridges.bridges().len(), 1);
+	assert_eq!(bridges.invalid().len(), 0);
+
+	let bridge = &bridges.bridges()[0];
+
+	assert!(Rc::ptr_eq(bridge.node(), &pcie));
+	assert_eq!(bridge.reg(), [(0x30000000, 0x1000000)]);
+	assert_eq!(bridge.bus_range(), (0x10, 0x1f));
+
+	let windows = bridge.windows();
+
+	assert!(windows.iter().map(|window| window.space()).eq([PciSpace::Io, PciSpace::Memory32, PciSpace::Memory64]));
+	assert!(windows[2].pci_address().is_prefetchable());
+	assert_eq!(windows[2].cpu_address(), 0x4_0000_0000);
+	assert_eq!(windows[2].size(), 0x4_0000_0000);
+
+	assert_eq!(bridge.translate(PciSpace::Io, 0x1000), Some(0x3eff1000));
+	assert_eq!(bridge.translate(PciSpace::Memory32, 0x40001000), Some(0x40001000));
+	assert_eq!(bridge.translate(PciSpace::Memory32, 0x1000), None);
+	assert_eq!(bridge.translate(PciSpace::Io, 0x10000), None);
+
+	// ECAM starts at the first bus of bus-range
+	assert_eq!(bridge.ecam_address(0x10, 0, 0), Some(0x30000000));
+	assert_eq!(bridge.ecam_address(0x11, 2, 1), Some(0x30100000 | 2 << 15 | 1 << 12));
+	assert_eq!(bridge.ecam_address(0x0f, 0, 0), None);
+	assert_eq!(bridge.ecam_address(0x20, 0, 0), None);
+}
+
+#[test]
+fn interrupt_map() {
+	let (tree, _) = tree();
+
+	let bridge = only_bridge(&tree);
+
+	assert_eq!(bridge.interrupt_map().len(), 4);
+
+	// The mask keeps the device number only, so the bus and function do not matter
+	let entry = bridge.route_intx(0x12, 1, 3, 2).unwrap();
+
+	assert_eq!(entry.child_pin(), 2);
+	assert_eq!(entry.parent().borrow().phandle(), Some(PLIC));
+	assert_eq!(entry.parent_address(), [] as [u32; 0]);
+	assert_eq!(entry.parent_interrupt(), [34]);
+
+	assert_eq!(bridge.route_intx(0x10, 0, 0, 1).unwrap().parent_interrupt(), [32]);
+	assert!(bridge.route_intx(0x10, 2, 0, 1).is_none());
+	assert!(bridge.route_intx(0x10, 0, 0, 3).is_none());
+}
+
+#[test]
+fn msi() {
+	let (tree, pcie) = tree();
+
+	let bridge = only_bridge(&tree);
+
+	assert!(bridge.map_rid(0x1000).is_none());
+
+	set_prop(&pcie, "msi-parent", &cells(&[PLIC]));
+
+	let bridge = only_bridge(&tree);
+	let (controller, msi) = bridge.map_rid(0x1008).unwrap();
+
+	assert_eq!(controller.borrow().phandle(), Some(PLIC));
+	assert_eq!(msi, 0x1008);
+
+	// msi-map wins over msi-parent
+	set_prop(&pcie, "msi-map", &cells(&[0x1000, PLIC, 0x20, 0x100]));
+	set_prop(&pcie, "msi-map-mask", &cells(&[0xff08]));
+
+	let bridge = only_bridge(&tree);
+
+	assert_eq!(bridge.msi_map()[0].length(), 0x100);
+	assert_eq!(bridge.map_rid(0x1008).unwrap().1, 0x28);
+	// Bits outside of the mask are ignored
+	assert_eq!(bridge.map_rid(0x1007).unwrap().1, 0x20);
+	assert!(bridge.map_rid(0x2000).is_none());
+
+	// The MSI specifier has the '#msi-cells' of the controller
+	set_prop(&tree.find_node("/soc/interrupt-controller@c000000").unwrap(), "#msi-cells", &cells(&[0]));
+	set_prop(&pcie, "msi-map", &cells(&[0x1000, PLIC, 0x100, 0x1100, PLIC, 0x100]));
+
+	let bridge = only_bridge(&tree);
+
+	assert_eq!(bridge.msi_map().len(), 2);
+	assert_eq!(bridge.msi_map()[1].rid_base(), 0x1100);
+	assert_eq!(bridge.msi_map()[1].msi_base(), 0);
+	assert_eq!(bridge.map_rid(0x1008).unwrap().1, 8);
+
+	set_prop(&tree.find_node("/soc/interrupt-controller@c000000").unwrap(), "#msi-cells", &cells(&[2]));
+
+	assert_eq!(error(&tree), Some(DeviceTreeError::BadPropValue));
+
+	set_prop(&pcie, "msi-map", &cells(&[0x1000, 0x40, 0x20, 0x100]));
+
+	assert_eq!(error(&tree), Some(DeviceTreeError::BadPhandle));
+}
+
+#[test]
+fn bad_host_bridge() {
+	let (tree, pcie) = tree();
+
+	set_prop(&pcie, "interrupt-map", &cells(&[0x0000, 0, 0, 1, PLIC]));
+
+	assert_eq!(error(&tree), Some(DeviceTreeError::BadPropValue));
+
+	set_prop(&pcie, "interrupt-map", &[]);
+	set_prop(&pcie, "bus-range", &cells(&[0x10, 0x100]));
+
+	assert_eq!(error(&tree), Some(DeviceTreeError::BadPropValue));
+
+	set_prop(&pcie, "bus-range", &cells(&[0x10, 0x1f]));
+	set_prop(&pcie, "ranges", &cells(&[0x01000000, 0, 0, 0, 0x3eff0000, 0]));
+
+	assert_eq!(error(&tree), Some(DeviceTreeError::BadPropValue));
+
+	// A PCI-PCI bridge below the host bridge is not a host bridge
+	set_prop(&pcie, "ranges", &[]);
+
+	let bridge = DeviceTreeNode::new_wrap();
+
+	set_prop(&bridge, "device_type", b"pci\0");
+	pcie.add_child("pci@0,0", bridge).unwrap();
+
+	assert_eq!(tree.pci_host_bridges().bridges().len(), 1);
+}
+
+#[test]
+fn bad_bridge_skipped() {
+	let (tree, _) = tree();
+
+	// A second host bridge that cannot be decoded does not hide the first one
+	let broken = DeviceTreeNode::new_wrap();
+
+	tree.find_node("/soc").unwrap().add_child("pcie@50000000", broken.clone()).unwrap();
+
+	set_prop(&broken, "device_type", b"pci\0");
+	set_prop(&broken, "#address-cells", &cells(&[2]));
+
+	let bridges = tree.pci_host_bridges();
+
+	assert_eq!(bridges.bridges().len(), 1);
+	assert_eq!(bridges.bridges()[0].node().borrow().path(), "/soc/pcie@30000000");
+	assert_eq!(bridges.invalid().len(), 1);
+	assert!(Rc::ptr_eq(&bridges.invalid()[0].0, &broken));
+	assert_eq!(bridges.invalid()[0].1, DeviceTreeError::BadPropValue);
+}
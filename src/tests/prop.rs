@@ -1,5 +1,12 @@
-use crate::tree::prop::{
-	DeviceTreeProperty, 
+use alloc::vec::Vec;
+
+use crate::{
+	DeviceTreeError,
+	tree::prop::{
+		DeviceTreeProperty,
+		DeviceTreePropertyType,
+		InferOptions
+	}
 };
 
 #[test]
@@ -8,7 +15,7 @@ fn prop_value() {
 	let mut prop_stringlist = DeviceTreeProperty::from_bytes("compatible", string_list.as_bytes());
 	prop_stringlist.update_type();
 
-	assert_eq!(format!("{};", prop_stringlist), "compatible = \"string1\0string2\0\";");
+	assert_eq!(format!("{};", prop_stringlist), "compatible = \"string1\", \"string2\";");
 
 	let string = "string";
 	let mut prop_string = DeviceTreeProperty::from_bytes("model", string.as_bytes());
@@ -28,3 +35,77 @@ fn prop_value() {
 
 	assert_eq!(format!("{};", prop_empty), "dma-coherent;");
 }
+
+#[test]
+fn prop_read() {
+	// Untyped properties can be read as anything their value encodes
+	let prop = DeviceTreeProperty::from_bytes("vendor,id", &0x10_u32.to_be_bytes());
+
+	assert_eq!(prop.u32(), Ok(0x10));
+	assert_eq!(prop.phandle(), Ok(0x10));
+	assert_eq!(prop.cells(), Ok(vec![0x10]));
+	assert_eq!(prop.u64(), Err(DeviceTreeError::BadPropValue));
+	assert_eq!(prop.string(), Err(DeviceTreeError::BadPropValue));
+
+	let prop = DeviceTreeProperty::from_bytes("compatible", b"sifive,uart0\0ns16550a\0");
+
+	assert_eq!(prop.stringlist(), Ok(vec!["sifive,uart0", "ns16550a"]));
+	assert_eq!(prop.string(), Err(DeviceTreeError::BadPropValue));
+	assert_eq!(prop.bytes().len(), 22);
+
+	let prop = DeviceTreeProperty::from_bytes("model", b"SiFive HiFive Unleashed A00\0");
+
+	assert_eq!(prop.string(), Ok("SiFive HiFive Unleashed A00"));
+	assert_eq!(prop.stringlist(), Ok(vec!["SiFive HiFive Unleashed A00"]));
+
+	let reg = [0_u32, 0x10010000, 0x1000, 0, 0x10011000, 0x1000];
+	let reg: Vec<u8> = reg.iter().flat_map(|cell| cell.to_be_bytes()).collect();
+
+	let prop = DeviceTreeProperty::from_bytes("reg", &reg);
+
+	assert_eq!(prop.encoded_array(&[2, 1]), Ok(vec![vec![0x10010000, 0x1000], vec![0x10011000, 0x1000]]));
+	assert_eq!(prop.encoded_array(&[2, 2]), Err(DeviceTreeError::BadPropValue));
+	assert_eq!(prop.encoded_array(&[3]), Err(DeviceTreeError::BadPropValue));
+
+	let prop = DeviceTreeProperty::from_bytes("phandle", &0xffffffff_u32.to_be_bytes());
+
+	assert_eq!(prop.phandle(), Err(DeviceTreeError::BadPropValue));
+}
+
+#[test]
+fn prop_infer() {
+	let options = InferOptions::new();
+
+	let mut prop = DeviceTreeProperty::from_bytes("clock-output-names", b"tlclk\0hfclk\0");
+	prop.update_type();
+
+	assert_eq!(prop.guess_type(&options), DeviceTreePropertyType::StringList);
+	assert_eq!(format!("{};", prop), "clock-output-names = \"tlclk\", \"hfclk\";");
+
+	let prop = DeviceTreeProperty::from_bytes("reg", &[0, 0, 0, 1, 0, 0, 0x10, 0]);
+
+	assert_eq!(format!("{};", prop), "reg = <0x1 0x1000>;");
+	assert_eq!(prop.guess_type(&InferOptions { cells: false, ..options }), DeviceTreePropertyType::Bytes);
+
+	let mut prop = DeviceTreeProperty::from_bytes("local-mac-address", &[0x70, 0xb3, 0xd5, 0x92, 0xf2, 0xf3]);
+
+	prop.infer_type(&options);
+
+	assert_eq!(prop.value_type(), &DeviceTreePropertyType::Bytes);
+
+	// Only properties without a type are inferred
+	let mut prop = DeviceTreeProperty::from_bytes("clock-frequency", &[0x41, 0x42, 0x43, 0]);
+	prop.update_type();
+	prop.infer_type(&options);
+
+	assert_eq!(prop.value_type(), &DeviceTreePropertyType::U32);
+	assert_eq!(format!("{}", prop), "clock-frequency = <0x41424300>");
+
+	// A value too short for its type is shown as bytes
+	let mut prop = DeviceTreeProperty::from_bytes("phandle", &[0, 1]);
+	prop.update_type();
+
+	assert_eq!(format!("{}", prop), "phandle = [00 01]");
+	assert_eq!(DeviceTreeProperty::from_bytes("label", &[0x41, 0x42, 0x43, 0]).guess_type(&options), DeviceTreePropertyType::String);
+	assert_eq!(DeviceTreeProperty::from_bytes("label", &[0x41, 0, 0, 0]).guess_type(&options), DeviceTreePropertyType::Cells);
+}
@@ -0,0 +1,148 @@
+use alloc::{
+	rc::Rc,
+	string::String
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError,
+	tree::{
+		prop::DeviceTreeProperty,
+		memory::{
+			MemoryRange,
+			ReservedReason
+		},
+		reserved::Placement
+	}
+};
+use super::add_node;
+
+fn tree() -> DeviceTree {
+	let tree = super::tree();
+
+	let reserved_memory = add_node(tree.root(), "reserved-memory", &[
+		("#address-cells", &2_u32.to_be_bytes()),
+		("#size-cells", &2_u32.to_be_bytes()),
+		("ranges", &[])
+	]);
+
+	add_node(&reserved_memory, "mmode@80000000", &[("reg", &pair(0x80000000, 0x20000)), ("no-map", &[])]);
+	add_node(&reserved_memory, "linux,cma", &[
+		("compatible", b"shared-dma-pool\0"),
+		("reusable", &[]),
+		("size", &0x1000000_u64.to_be_bytes()),
+		("alignment", &0x400000_u64.to_be_bytes()),
+		("alloc-ranges", &pair(0x80000000, 0x10000000)),
+		("phandle", &0x20_u32.to_be_bytes())
+	]);
+	add_node(&reserved_memory, "framebuffer", &[
+		("size", &0x800000_u64.to_be_bytes()),
+		("no-map", &[]),
+		("phandle", &0x21_u32.to_be_bytes())
+	]);
+	add_node(&reserved_memory, "huge", &[("size", &0x40000000_u64.to_be_bytes())]);
+	add_node(&reserved_memory, "disabled@90000000", &[("reg", &pair(0x90000000, 0x1000)), ("status", b"disabled\0")]);
+
+	tree
+}
+
+/// Address and size with 2 cells each
+fn pair(address: u64, size: u64) -> [u8; 16] {
+	let mut pair = [0; 16];
+
+	pair[..8].copy_from_slice(&address.to_be_bytes());
+	pair[8..].copy_from_slice(&size.to_be_bytes());
+
+	pair
+}
+
+#[test]
+fn reserved_memory() {
+	let tree = tree();
+
+	let reserved = tree.reserved_memory().unwrap();
+
+	assert_eq!(reserved.regions().len(), 4);
+
+	let mmode = reserved.find("/reserved-memory/mmode@80000000").unwrap();
+
+	assert!(!mmode.is_dynamic());
+	assert!(mmode.is_no_map());
+	assert_eq!(mmode.ranges(), [MemoryRange::new(0x80000000, 0x20000)]);
+
+	let cma = reserved.find("/reserved-memory/linux,cma").unwrap();
+
+	assert!(cma.is_shared_dma_pool());
+	assert!(cma.is_reusable());
+	assert_eq!(cma.placement(), &Placement::Dynamic {
+		size: 0x1000000,
+		alignment: 0x400000,
+		alloc_ranges: vec![MemoryRange::new(0x80000000, 0x10000000)]
+	});
+	// Top of the allocation range, aligned down
+	assert_eq!(cma.ranges(), [MemoryRange::new(0x8f000000, 0x1000000)]);
+
+	// Top of RAM
+	let framebuffer = reserved.find("/reserved-memory/framebuffer").unwrap();
+
+	assert_eq!(framebuffer.ranges(), [MemoryRange::new(0x9f800000, 0x800000)]);
+
+	// Larger than RAM
+	assert!(!reserved.find("/reserved-memory/huge").unwrap().is_allocated());
+	assert!(reserved.find("/reserved-memory/disabled@90000000").is_none());
+
+	// The no-map regions, static or allocated, are not usable RAM, the CMA pool is
+	let map = tree.memory_map().unwrap();
+
+	assert_eq!(map.usable(), [MemoryRange::new(0x80020000, 0x1f7e0000)]);
+	assert_eq!(map.reserved().len(), 3);
+	assert_eq!(map.reserved()[1].reason(), &ReservedReason::Reserved(String::from("/reserved-memory/linux,cma")));
+}
+
+#[test]
+fn reserved_memory_allocation_order() {
+	let tree = tree();
+
+	// Without alloc-ranges the CMA pool, allocated first, takes the top of RAM and the framebuffer goes below it
+	tree.find_node("/reserved-memory/linux,cma").unwrap().borrow_mut().remove_prop("alloc-ranges");
+
+	let reserved = tree.reserved_memory().unwrap();
+
+	assert_eq!(reserved.find("/reserved-memory/linux,cma").unwrap().ranges(), [MemoryRange::new(0x9f000000, 0x1000000)]);
+	assert_eq!(reserved.find("/reserved-memory/framebuffer").unwrap().ranges(), [MemoryRange::new(0x9e800000, 0x800000)]);
+}
+
+#[test]
+fn memory_region() {
+	let tree = tree();
+
+	let serial = tree.find_node("/soc/serial@10010000").unwrap();
+
+	serial.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("memory-region", &[0, 0, 0, 0x21, 0, 0, 0, 0x20])).unwrap();
+
+	let reserved = tree.reserved_memory().unwrap();
+	let regions = reserved.regions_of(&serial.borrow()).unwrap();
+
+	assert!(regions.iter().map(|region| region.path()).eq(["/reserved-memory/framebuffer", "/reserved-memory/linux,cma"]));
+	assert!(reserved.regions_of(&tree.find_node("/soc").unwrap().borrow()).unwrap().is_empty());
+
+	let consumers = tree.memory_region_consumers(regions[1]);
+
+	assert_eq!(consumers.len(), 1);
+	assert!(Rc::ptr_eq(&consumers[0], &serial));
+
+	// The phandle of a node that is not a region
+	serial.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("memory-region", &8_u32.to_be_bytes())).unwrap();
+
+	assert_eq!(reserved.regions_of(&serial.borrow()).err(), Some(DeviceTreeError::BadPhandle));
+
+	// A region with neither 'reg' nor 'size' is left out, the others are still read
+	let empty = add_node(&tree.find_node("/reserved-memory").unwrap(), "empty", &[]);
+
+	let reserved = tree.reserved_memory().unwrap();
+
+	assert_eq!(reserved.regions().len(), 4);
+	assert_eq!(reserved.invalid().len(), 1);
+	assert!(Rc::ptr_eq(&reserved.invalid()[0].0, &empty));
+	assert_eq!(reserved.invalid()[0].1, DeviceTreeError::BadPropValue);
+}
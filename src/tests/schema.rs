@@ -0,0 +1,166 @@
+use alloc::vec::Vec;
+
+use crate::{
+	DeviceTreeError,
+	tree::{
+		prop::{
+			DeviceTreeProperty,
+			DeviceTreePropertyType
+		},
+		schema::{
+			BindingSchema,
+			PropertySchema,
+			SchemaRegistry,
+			SchemaViolation,
+			ViolationKind
+		}
+	}
+};
+use super::tree;
+
+const UART_SCHEMA: &str = r#"
+# SiFive UART
+%YAML 1.2
+---
+$id: http://devicetree.org/schemas/serial/sifive-serial.yaml#
+title: SiFive asynchronous serial interface (UART)
+
+description: |
+  UART contained in the SiFive FU540 and FU740 SoCs.
+  current-speed: is not a key here.
+
+properties:
+  compatible:
+    items:
+      - enum:
+          - sifive,fu540-c000-uart
+          - sifive,fu740-c000-uart
+      - const: sifive,uart0
+
+  reg:
+    maxItems: 1
+
+  interrupts:
+    maxItems: 1
+
+  clocks:
+    maxItems: 1
+
+  current-speed:
+    $ref: /schemas/types.yaml#/definitions/uint32
+    enum: [ 9600, 115200 ]  # baud rates
+
+required:
+- compatible
+- reg
+- interrupts
+- clocks
+"#;
+
+#[test]
+fn yaml() {
+	let schema = BindingSchema::from_yaml(UART_SCHEMA).unwrap();
+
+	assert_eq!(schema.compatible(), ["sifive,fu540-c000-uart", "sifive,fu740-c000-uart", "sifive,uart0"]);
+	assert!(schema.find_property("clocks").unwrap().is_required());
+	assert_eq!(schema.find_property("current-speed").unwrap().value_type(), &DeviceTreePropertyType::U32);
+	assert!(!schema.find_property("current-speed").unwrap().is_required());
+
+	assert_eq!(BindingSchema::from_yaml("properties:\n  reg:\n    maxItems: one\n"), Err(DeviceTreeError::BadSchema));
+	assert_eq!(BindingSchema::from_yaml("required:\n  - reg\n    - reg\n"), Err(DeviceTreeError::BadSchema));
+}
+
+#[test]
+fn validate() {
+	let tree = tree();
+
+	let mut registry = SchemaRegistry::new();
+
+	registry.add_yaml(UART_SCHEMA).unwrap();
+
+	assert_eq!(registry.validate(&tree), []);
+
+	let serial = tree.find_node("/soc/serial@10010000").unwrap();
+
+	serial.borrow_mut().remove_prop("clocks");
+	serial.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("current-speed", &57600_u32.to_be_bytes())).unwrap();
+	serial.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("reg", &[0; 32])).unwrap();
+	serial.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("status", b"okay")).unwrap();
+
+	let violation = |property: &str, kind| SchemaViolation { path: "/soc/serial@10010000".into(), property: property.into(), kind };
+
+	assert_eq!(registry.validate(&tree), [
+		violation("status", ViolationKind::BadValue),
+		violation("reg", ViolationKind::BadLength(2)),
+		violation("clocks", ViolationKind::Missing),
+		violation("current-speed", ViolationKind::BadEnum)
+	]);
+
+	assert_eq!(format!("{}", violation("clocks", ViolationKind::Missing)), "/soc/serial@10010000: missing required property 'clocks'");
+}
+
+#[test]
+fn frequency_width() {
+	let tree = tree();
+	let registry = SchemaRegistry::new();
+
+	let cpus = tree.find_node("/cpus").unwrap();
+
+	// The frequencies may be a u32 or a u64, but nothing else
+	cpus.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("timebase-frequency", &1_000_000_u64.to_be_bytes())).unwrap();
+
+	assert_eq!(cpus.borrow().prop_value("timebase-frequency").unwrap().value_type(), &DeviceTreePropertyType::U64);
+	assert_eq!(registry.validate_node(&cpus.borrow()), []);
+
+	cpus.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("timebase-frequency", &[0; 6])).unwrap();
+
+	assert_eq!(cpus.borrow().prop_value("timebase-frequency").unwrap().value_type(), &DeviceTreePropertyType::U32);
+	assert_eq!(registry.validate_node(&cpus.borrow()), [SchemaViolation {
+		path: "/cpus".into(),
+		property: "timebase-frequency".into(),
+		kind: ViolationKind::BadValue
+	}]);
+
+	let schema = BindingSchema::from_yaml(concat!(
+		"properties:\n",
+		"  clock-frequency: true\n",
+		"  max-frequency:\n",
+		"    oneOf:\n",
+		"      - $ref: /schemas/types.yaml#/definitions/uint32\n",
+		"      - $ref: /schemas/types.yaml#/definitions/uint64\n"
+	)).unwrap();
+
+	for name in ["clock-frequency", "max-frequency"] {
+		let types: Vec<_> = schema.find_property(name).unwrap().value_types().cloned().collect();
+
+		assert_eq!(types, [DeviceTreePropertyType::U32, DeviceTreePropertyType::U64]);
+	}
+
+	let core: Vec<_> = BindingSchema::core().find_property("clock-frequency").unwrap().value_types().cloned().collect();
+
+	assert_eq!(core, [DeviceTreePropertyType::U32, DeviceTreePropertyType::U64]);
+}
+
+#[test]
+fn apply_types() {
+	let tree = tree();
+
+	let mut registry = SchemaRegistry::new();
+
+	registry.add(BindingSchema::new(&["riscv,plic0"])
+		.property(PropertySchema::new("riscv,ndev", DeviceTreePropertyType::U32).required().enum_values(&["0x35"]))
+		.property(PropertySchema::new("interrupts-extended", DeviceTreePropertyType::Cells)));
+
+	registry.apply_types(&tree);
+
+	let plic = tree.find_node("/soc/interrupt-controller@c000000").unwrap();
+
+	assert_eq!(plic.borrow().prop_value("riscv,ndev").unwrap().value_type(), &DeviceTreePropertyType::U32);
+	assert_eq!(plic.borrow().prop_value("compatible").unwrap().value_type(), &DeviceTreePropertyType::StringList);
+
+	// Nodes that are not compatible keep their types
+	let ethernet = tree.find_node("/soc/ethernet@10090000").unwrap();
+
+	assert_eq!(ethernet.borrow().prop_value("interrupts").unwrap().value_type(), &DeviceTreePropertyType::Raw);
+	assert_eq!(registry.validate(&tree), []);
+}
@@ -0,0 +1,146 @@
+use crate::{
+	DeviceTree,
+	DeviceTreeError,
+	tree::{
+		node::DeviceTreeNodeWrap,
+		prop::DeviceTreeProperty,
+		topology::TopologyLevel
+	}
+};
+use super::add_node;
+
+fn tree() -> DeviceTree {
+	let tree = super::tree();
+
+	for cpu in 0..4 {
+		set_u32(&tree.find_node(&format!("/cpus/cpu@{}", cpu)).unwrap(), "phandle", 0x10 + cpu);
+	}
+
+	tree
+}
+
+fn set_u32(node: &DeviceTreeNodeWrap, name: &str, value: u32) {
+	node.borrow_mut().add_prop(DeviceTreeProperty::from_bytes(name, &value.to_be_bytes())).unwrap();
+}
+
+#[test]
+fn cpu_topology() {
+	let tree = tree();
+
+	assert!(tree.cpu_topology().unwrap().is_empty());
+
+	let cpu_map = add_node(&tree.find_node("/cpus").unwrap(), "cpu-map", &[]);
+	let socket = add_node(&cpu_map, "socket0", &[]);
+
+	// Two clusters of two cores, the second core having two threads
+	for (cluster, cpus) in [(0, [0x10, 0x11]), (1, [0x12, 0x13])] {
+		let cluster = add_node(&socket, &format!("cluster{}", cluster), &[]);
+
+		set_u32(&add_node(&cluster, "core0", &[]), "cpu", cpus[0]);
+
+		let core = add_node(&cluster, "core1", &[]);
+
+		set_u32(&add_node(&core, "thread0", &[]), "cpu", cpus[1]);
+	}
+
+	let topology = tree.cpu_topology().unwrap();
+
+	assert_eq!(topology.len(), 1);
+	assert_eq!(topology[0].level(), TopologyLevel::Socket);
+	assert!(topology[0].cpus().iter().map(|cpu| cpu.id()).eq([0, 1, 2, 3]));
+
+	let cluster = &topology[0].children()[1];
+
+	assert_eq!(cluster.level(), TopologyLevel::Cluster);
+	assert_eq!(cluster.index(), 1);
+	assert_eq!(cluster.children()[0].cpu().unwrap().id(), 2);
+	assert!(cluster.children()[1].cpu().is_none());
+	assert_eq!(cluster.children()[1].children()[0].level(), TopologyLevel::Thread);
+	assert_eq!(cluster.children()[1].children()[0].cpu().unwrap().id(), 3);
+
+	// A core directly under a socket
+	set_u32(&add_node(&socket, "core2", &[]), "cpu", 0x10);
+
+	assert_eq!(tree.cpu_topology().err(), Some(DeviceTreeError::BadTopology));
+}
+
+#[test]
+fn cpu_topology_errors() {
+	let tree = tree();
+
+	let cpu_map = add_node(&tree.find_node("/cpus").unwrap(), "cpu-map", &[]);
+	let cluster = add_node(&cpu_map, "cluster0", &[]);
+	let core = add_node(&cluster, "core0", &[]);
+
+	// A leaf without a CPU
+	assert_eq!(tree.cpu_topology().err(), Some(DeviceTreeError::BadTopology));
+
+	set_u32(&core, "cpu", 0x20);
+
+	assert_eq!(tree.cpu_topology().err(), Some(DeviceTreeError::BadPhandle));
+
+	// The interrupt controller of cpu@0 is not a CPU
+	set_u32(&core, "cpu", 0x06);
+
+	assert_eq!(tree.cpu_topology().err(), Some(DeviceTreeError::BadTopology));
+
+	set_u32(&core, "cpu", 0x10);
+
+	assert_eq!(tree.cpu_topology().unwrap()[0].cpus().len(), 1);
+
+	add_node(&cpu_map, "die0", &[]);
+
+	assert_eq!(tree.cpu_topology().err(), Some(DeviceTreeError::BadTopology));
+}
+
+#[test]
+fn caches() {
+	let tree = tree();
+
+	let cpus = tree.find_node("/cpus").unwrap();
+
+	let l2 = add_node(&cpus, "l2-cache0", &[]);
+	set_u32(&l2, "phandle", 0x20);
+	set_u32(&l2, "cache-size", 0x40000);
+	set_u32(&l2, "cache-sets", 256);
+	set_u32(&l2, "cache-block-size", 64);
+	set_u32(&l2, "next-level-cache", 0x21);
+	l2.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("cache-unified", &[])).unwrap();
+
+	let l3 = add_node(&cpus, "l3-cache", &[]);
+	set_u32(&l3, "phandle", 0x21);
+	set_u32(&l3, "cache-level", 3);
+	set_u32(&l3, "cache-line-size", 128);
+
+	for cpu in 0..2 {
+		set_u32(&tree.find_node(&format!("/cpus/cpu@{}", cpu)).unwrap(), "next-level-cache", 0x20);
+	}
+
+	set_u32(&tree.find_node("/cpus/cpu@2").unwrap(), "next-level-cache", 0x21);
+
+	let cpus = tree.cpus().unwrap();
+	let caches = cpus[0].caches(&tree).unwrap();
+
+	assert_eq!(caches.len(), 2);
+	assert_eq!(caches[0].level(), 2);
+	assert!(caches[0].is_unified());
+	assert_eq!(caches[0].size(), Some(0x40000));
+	assert_eq!(caches[0].sets(), Some(256));
+	assert_eq!(caches[0].line_size(), Some(64));
+	assert_eq!(caches[1].level(), 3);
+	assert!(!caches[1].is_unified());
+	assert_eq!(caches[1].line_size(), Some(128));
+
+	assert!(cpus[3].caches(&tree).unwrap().is_empty());
+	assert!(tree.cpus_sharing(&caches[0]).unwrap().iter().map(|cpu| cpu.id()).eq([0, 1]));
+	assert!(tree.cpus_sharing(&caches[1]).unwrap().iter().map(|cpu| cpu.id()).eq([0, 1, 2]));
+
+	// A chain that loops back
+	set_u32(&l3, "next-level-cache", 0x20);
+
+	assert_eq!(cpus[0].caches(&tree).err(), Some(DeviceTreeError::BadTopology));
+
+	set_u32(&l3, "next-level-cache", 0x30);
+
+	assert_eq!(cpus[0].caches(&tree).err(), Some(DeviceTreeError::BadPhandle));
+}
@@ -1,10 +1,16 @@
-use alloc::rc::Rc;
+use alloc::{
+	vec::Vec,
+	rc::{Rc, Weak}
+};
 
 use crate::{
 	DeviceTree,
-	tree::node::{
-		DeviceTreeNode,
-		AddChild,
+	tree::{
+		node::{
+			DeviceTreeNode,
+			AddChild,
+		},
+		prop::DeviceTreeProperty
 	}
 };
 
@@ -18,7 +24,7 @@ fn tree() {
 
 	let cpus = DeviceTreeNode::new_wrap();
 
-	current.add_child("cpus", Rc::clone(&cpus));
+	current.add_child("cpus", Rc::clone(&cpus)).unwrap();
 
 	current = Rc::clone(&cpus);
 
@@ -26,7 +32,10 @@ fn tree() {
 
 	let cpu_0 = DeviceTreeNode::new_wrap();
 
-	current.add_child("cpu@0", Rc::clone(&cpu_0));
+	cpu_0.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("device_type", b"cpu\0")).unwrap();
+
+	current.add_child("cpu@0", Rc::clone(&cpu_0)).unwrap();
+	current.add_child("cpu-map", DeviceTreeNode::new_wrap()).unwrap();
 
 	assert_eq!(tree.num_cpus(), 1);
 }
@@ -39,3 +48,51 @@ fn from_bytes() {
 
     assert_eq!(tree.num_cpus(), 4);
 }
+
+#[test]
+fn drop_releases_nodes() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let tree = DeviceTree::from_bytes(&mut dtb).unwrap();
+
+	let nodes: Vec<Weak<_>> = tree.nodes().iter().map(Rc::downgrade).collect();
+
+	assert!(nodes.len() > 1);
+	assert_eq!(Rc::strong_count(tree.root()), 1);
+
+	let phy = tree.find_node("/soc/ethernet@10090000/ethernet-phy@0").unwrap();
+
+	assert_eq!(phy.borrow().path(), "/soc/ethernet@10090000/ethernet-phy@0");
+
+	drop(phy);
+	drop(tree);
+
+	assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+}
+
+#[test]
+fn detached_subtree() {
+	let tree = DeviceTree::new_empty_root();
+
+	let cpus = DeviceTreeNode::new_wrap();
+
+	tree.root().add_child("cpus", Rc::clone(&cpus)).unwrap();
+
+	drop(tree);
+
+	assert_eq!(Rc::strong_count(&cpus), 1);
+	assert!(!cpus.borrow().has_parent());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_file() {
+	let tree = DeviceTree::from_file("src/tests/dtb/test1.dtb").unwrap();
+
+	assert_eq!(tree.num_cpus(), 4);
+
+	let error = DeviceTree::from_file("src/tests/dtb/missing.dtb").err().unwrap();
+
+	assert_eq!(error, crate::DeviceTreeError::Io(std::io::ErrorKind::NotFound));
+	assert_eq!(format!("{}", error), "I/O error: entity not found");
+}
@@ -0,0 +1,91 @@
+use crate::{
+	DeviceTreeBlob,
+	DeviceTreeError,
+	fdt::view::{
+		Fdt,
+		FdtProperty
+	}
+};
+
+#[test]
+fn find_node() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let blob = DeviceTreeBlob::from_bytes(&mut dtb).unwrap();
+
+	let fdt = blob.view().unwrap();
+
+	assert_eq!(fdt.root().name(), "");
+	assert_eq!(fdt.root().property("model").unwrap().str(), Ok("SiFive HiFive Unleashed A00"));
+
+	let cpus = fdt.find_node("/cpus").unwrap();
+
+	assert_eq!(cpus.children().count(), 4);
+	assert_eq!(cpus.property("timebase-frequency").unwrap().u32(), Ok(0x989680));
+
+	let cpu_2 = fdt.find_node("/cpus/cpu@2").unwrap();
+
+	assert_eq!(cpu_2.property("reg").unwrap().u32(), Ok(2));
+	assert_eq!(cpu_2.find_child("interrupt-controller").unwrap().property("phandle").unwrap().phandle(), Ok(4));
+
+	let ethernet = fdt.find_node("/soc/ethernet@10090000").unwrap();
+
+	assert!(ethernet.is_compatible("sifive,fu540-c000-gem"));
+	assert!(ethernet.property("clock-names").unwrap().strings().eq(["pclk", "hclk"]));
+	assert!(ethernet.property("reg").unwrap().cells().unwrap().eq([0x00, 0x10090000, 0x00, 0x2000, 0x00, 0x100a0000, 0x00, 0x1000]));
+	assert_eq!(ethernet.properties().count(), 12);
+
+	assert!(fdt.find_node("/soc/serial@0").is_none());
+}
+
+#[test]
+fn typed_getters() {
+	let prop = FdtProperty::new("reg", &[0, 0, 0, 1, 0, 0, 0, 2]);
+
+	assert_eq!(prop.u32(), Err(DeviceTreeError::BadPropValue));
+	assert_eq!(prop.u64(), Ok(0x1_0000_0002));
+	assert_eq!(prop.str(), Err(DeviceTreeError::BadPropValue));
+
+	let prop = FdtProperty::new("model", b"a\0b\0");
+
+	assert_eq!(prop.str(), Err(DeviceTreeError::BadPropValue));
+	assert!(prop.strings().eq(["a", "b"]));
+	assert!(prop.cells().unwrap().eq([0x61006200]));
+}
+
+#[test]
+fn malformed() {
+	let structure = [0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 9];
+
+	assert_eq!(Fdt::new(&structure, &[]).err(), Some(DeviceTreeError::BadToken));
+}
+
+#[test]
+fn root_required() {
+	const BEGIN_ROOT: [u8; 8] = [0, 0, 0, 1, 0, 0, 0, 0];
+	const BEGIN_CPUS: [u8; 12] = [0, 0, 0, 1, b'c', b'p', b'u', b's', 0, 0, 0, 0];
+	const END_NODE: [u8; 4] = [0, 0, 0, 2];
+	const NOP: [u8; 4] = [0, 0, 0, 4];
+	const END: [u8; 4] = [0, 0, 0, 9];
+
+	let view = |tokens: &[&[u8]]| {
+		let mut structure = [0; 64];
+		let mut len = 0;
+
+		for token in tokens {
+			structure[len..len + token.len()].copy_from_slice(token);
+			len += token.len();
+		}
+
+		Fdt::new(&structure[..len], &[]).map(|fdt| fdt.find_node("/chosen").is_none())
+	};
+
+	// No root, a named top-level node, two top-level nodes
+	assert_eq!(view(&[&END]), Err(DeviceTreeError::BadToken));
+	assert_eq!(view(&[&NOP, &END]), Err(DeviceTreeError::BadToken));
+	assert_eq!(view(&[&BEGIN_CPUS, &END_NODE, &END]), Err(DeviceTreeError::BadToken));
+	assert_eq!(view(&[&BEGIN_ROOT, &END_NODE, &BEGIN_ROOT, &END_NODE, &END]), Err(DeviceTreeError::BadToken));
+	assert_eq!(view(&[&BEGIN_ROOT, &END_NODE, &NOP]), Err(DeviceTreeError::BadToken));
+
+	assert_eq!(view(&[&NOP, &BEGIN_ROOT, &BEGIN_CPUS, &END_NODE, &END_NODE, &NOP, &END]), Ok(true));
+}
@@ -0,0 +1,239 @@
+use alloc::{
+	rc::Rc,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	tree::{
+		node::{
+			DeviceTreeNode,
+			AddChild,
+			insert_child
+		},
+		prop::DeviceTreeProperty,
+		overlay::WriteOptions
+	}
+};
+
+/// A tree with a labeled interrupt controller referenced by a serial node, and a reference to an external label
+fn labeled_tree() -> DeviceTree {
+	let tree = DeviceTree::new_empty_root();
+
+	let intc = DeviceTreeNode::new_wrap();
+	intc.borrow_mut().set_label("intc");
+	tree.root().add_child("interrupt-controller@c000000", Rc::clone(&intc)).unwrap();
+
+	let serial = DeviceTreeNode::new_wrap();
+
+	let mut parent = DeviceTreeProperty::from_bytes("interrupt-parent", &[0; 4]);
+	parent.add_ref(0, "intc").unwrap();
+	serial.borrow_mut().add_prop(parent).unwrap();
+
+	let mut clocks = DeviceTreeProperty::from_bytes("clocks", &[0; 8]);
+	clocks.add_ref(0, "prci").unwrap();
+	serial.borrow_mut().add_prop(clocks).unwrap();
+
+	tree.root().add_child("serial@10010000", serial).unwrap();
+
+	tree
+}
+
+#[test]
+fn round_trip() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let tree = DeviceTree::from_bytes(&mut dtb).unwrap();
+
+	let written = tree.to_dtb(&WriteOptions::new()).unwrap();
+
+	let reparsed = DeviceTree::from_bytes(&mut written.as_slice()).unwrap();
+
+	assert_eq!(reparsed.num_cpus(), 4);
+	assert_eq!(written, include_bytes!("./dtb/test1.dtb"));
+}
+
+#[test]
+fn symbols() {
+	let mut tree = labeled_tree();
+
+	tree.find_node("/serial@10010000").unwrap().borrow_mut().remove_prop("clocks");
+
+	let options = WriteOptions { symbols: true, ..WriteOptions::new() };
+
+	let written = tree.to_dtb(&options).unwrap();
+
+	let reparsed = DeviceTree::from_bytes(&mut written.as_slice()).unwrap();
+
+	let symbols = reparsed.find_node("/__symbols__").unwrap();
+
+	assert_eq!(symbols.borrow().prop_value("intc").unwrap().raw_value(), b"/interrupt-controller@c000000\0");
+
+	let intc = reparsed.find_node("/interrupt-controller@c000000").unwrap();
+	let serial = reparsed.find_node("/serial@10010000").unwrap();
+
+	assert_eq!(intc.borrow().phandle(), Some(1));
+	assert_eq!(serial.borrow().prop_value("interrupt-parent").unwrap().raw_value(), &1_u32.to_be_bytes());
+
+	// Entries of an existing '__symbols__' are kept
+	let existing = DeviceTreeNode::new_wrap();
+	existing.borrow_mut().add_prop(DeviceTreeProperty::from_bytes("uart0", b"/serial@10010000\0")).unwrap();
+	tree.root().add_child("__symbols__", existing).unwrap();
+
+	let reparsed = DeviceTree::from_bytes(&mut tree.to_dtb(&options).unwrap().as_slice()).unwrap();
+	let symbols = reparsed.find_node("/__symbols__").unwrap();
+
+	assert!(symbols.borrow().prop_iter().map(|(name, _)| name.as_str()).eq(["uart0", "intc"]));
+
+	tree.add_memory_reservation(crate::fdt::blob::FdtReserveEntry::new(0x80000000, 0x1000));
+
+	assert!(tree.to_dts(&options).unwrap().contains("/memreserve/ 0x0000000080000000 0x0000000000001000;"));
+}
+
+#[test]
+fn unresolved_reference() {
+	let tree = labeled_tree();
+
+	assert_eq!(tree.to_dtb(&WriteOptions::new()), Err(crate::DeviceTreeError::UnresolvedReference));
+}
+
+#[test]
+fn plugin() {
+	let tree = labeled_tree();
+
+	let options = WriteOptions { plugin: true, ..WriteOptions::new() };
+
+	let written = tree.to_dtb(&options).unwrap();
+
+	let reparsed = DeviceTree::from_bytes(&mut written.as_slice()).unwrap();
+
+	let fixups = reparsed.find_node("/__fixups__").unwrap();
+
+	assert_eq!(fixups.borrow().prop_value("prci").unwrap().raw_value(), b"/serial@10010000:clocks:0\0");
+
+	let local = reparsed.find_node("/__local_fixups__/serial@10010000").unwrap();
+
+	assert_eq!(local.borrow().prop_value("interrupt-parent").unwrap().raw_value(), &0_u32.to_be_bytes());
+
+	let serial = reparsed.find_node("/serial@10010000").unwrap();
+
+	assert_eq!(&serial.borrow().prop_value("clocks").unwrap().raw_value()[..4], &[0xff; 4]);
+
+	let dts = tree.to_dts(&options).unwrap();
+
+	assert!(dts.starts_with("/dts-v1/;\n/plugin/;\n"));
+	assert!(dts.contains("intc: interrupt-controller@c000000 {"));
+	assert!(dts.contains("prci = \"/serial@10010000:clocks:0\";"));
+}
+
+#[test]
+fn long_names() {
+	let tree = labeled_tree();
+
+	let label = "interrupt_controller_of_the_platform";
+	let external = "power_reset_clock_interrupt_controller";
+
+	tree.find_node("/interrupt-controller@c000000").unwrap().borrow_mut().set_label(label);
+
+	// A node name too long for add_child, like one read from a blob
+	let serial = DeviceTreeNode::new_wrap();
+	let mut parent = DeviceTreeProperty::from_bytes("interrupt-parent", &[0; 4]);
+	let mut clocks = DeviceTreeProperty::from_bytes("clocks", &[0; 4]);
+
+	parent.add_ref(0, label).unwrap();
+	clocks.add_ref(0, external).unwrap();
+	serial.borrow_mut().add_prop(parent).unwrap();
+	serial.borrow_mut().add_prop(clocks).unwrap();
+
+	insert_child(tree.root(), "serial-port-of-the-debug-console@10010000", serial);
+
+	let options = WriteOptions { symbols: true, plugin: true, ..WriteOptions::new() };
+
+	let written = tree.to_dtb(&options).unwrap();
+
+	let reparsed = DeviceTree::from_bytes(&mut written.as_slice()).unwrap();
+
+	assert!(reparsed.find_node("/__symbols__").unwrap().borrow().prop_exists(label));
+	assert!(reparsed.find_node("/__fixups__").unwrap().borrow().prop_exists(external));
+	assert!(reparsed.find_node("/__local_fixups__/serial-port-of-the-debug-console@10010000").is_some());
+	assert!(tree.to_dts(&options).unwrap().contains(label));
+}
+
+#[test]
+fn shared_strings() {
+	let tree = DeviceTree::new_empty_root();
+
+	tree.root().borrow_mut().add_prop(DeviceTreeProperty::from_bytes("#address-cells", &2_u32.to_be_bytes())).unwrap();
+	tree.root().borrow_mut().add_prop(DeviceTreeProperty::from_bytes("cells", &[])).unwrap();
+	tree.root().borrow_mut().add_prop(DeviceTreeProperty::from_bytes("address-cells", &[])).unwrap();
+	tree.root().borrow_mut().add_prop(DeviceTreeProperty::from_bytes("cells-size", &[])).unwrap();
+
+	let written = tree.to_dtb(&WriteOptions::new()).unwrap();
+
+	let blob = crate::DeviceTreeBlob::from_bytes(&mut written.as_slice()).unwrap();
+
+	let strings_block = blob.strings_block();
+
+	assert!(strings_block.iter().map(|(_, s)| s).eq(["#address-cells", "cells-size"]));
+	assert!(strings_block.unused(blob.structure_block()).unwrap().is_empty());
+
+	let root = blob.view().unwrap().root();
+
+	assert!(root.properties().map(|prop| prop.name()).eq(["#address-cells", "cells", "address-cells", "cells-size"]));
+}
+
+#[test]
+fn dts_inferred_types() {
+	let mut dtb: &[u8] = include_bytes!("./dtb/test1.dtb");
+
+	let tree = DeviceTree::from_bytes(&mut dtb).unwrap();
+
+	let dts = tree.to_dts(&WriteOptions::new()).unwrap();
+
+	assert!(dts.contains("riscv,isa = \"rv64imacu\";"));
+	assert!(dts.contains("reg = <0x0 0x10010000 0x0 0x1000>;"));
+	assert!(!dts.contains("(raw)"));
+}
+
+/// Decode the escapes of a DTS string literal like dtc
+fn unescape(literal: &str) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	let mut rest = literal.as_bytes();
+
+	while let Some((&b, tail)) = rest.split_first() {
+		rest = tail;
+
+		match (b, rest.first()) {
+			(b'\\', Some(b'x')) => {
+				let hex = core::str::from_utf8(&rest[1..3]).unwrap();
+				bytes.push(u8::from_str_radix(hex, 16).unwrap());
+				rest = &rest[3..];
+			},
+			(b'\\', Some(&c)) => {
+				bytes.push(c);
+				rest = &rest[1..];
+			},
+			_ => bytes.push(b)
+		}
+	}
+
+	bytes
+}
+
+#[test]
+fn dts_strings() {
+	let tree = DeviceTree::new_empty_root();
+
+	let value = b"say \"h\xc3\xa9\" \\ \xe2\x82\xac\t\xff\0";
+
+	tree.root().borrow_mut().add_prop(DeviceTreeProperty::from_bytes("model", value)).unwrap();
+
+	let dts = tree.to_dts(&WriteOptions::new()).unwrap();
+	let line = dts.lines().find(|line| line.contains("model")).unwrap();
+
+	assert_eq!(line.trim(), "model = \"say \\\"h\u{e9}\\\" \\\\ \u{20ac}\\x09\\xff\";");
+
+	let literal = line.trim().strip_prefix("model = \"").unwrap().strip_suffix("\";").unwrap();
+
+	assert_eq!(unescape(literal), &value[..value.len() - 1]);
+}
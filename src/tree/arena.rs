@@ -0,0 +1,349 @@
+use alloc::{
+	string::{String, ToString},
+	vec::Vec,
+	rc::Rc
+};
+use core::ops::{
+	Index,
+	IndexMut
+};
+use log::debug;
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError,
+	fdt::blob::FdtReserveEntry
+};
+use super::{
+	node::{
+		DeviceTreeNode,
+		DeviceTreeNodeWrap,
+		insert_child
+	},
+	prop::DeviceTreeProperty,
+	name::{
+		NodeName,
+		validate_prop_name
+	},
+	map::{
+		OrderedMap,
+		Iter
+	}
+};
+
+/// Index of a node in an ArenaTree
+///
+/// An id stays valid for the whole life of the tree it was handed out by.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct NodeId(usize);
+
+impl NodeId {
+	pub fn index(self) -> usize {
+		self.0
+	}
+}
+
+/// Node of an ArenaTree: links to other nodes are NodeIds instead of shared references
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArenaNode {
+	name: String,
+	label: Option<String>,
+	parent: Option<NodeId>,
+	children: Vec<NodeId>,
+	properties: OrderedMap<DeviceTreeProperty>
+}
+
+impl ArenaNode {
+	fn new(name: &str, parent: Option<NodeId>) -> Self {
+		Self {
+			name: name.to_string(),
+			label: None,
+			parent,
+			children: Vec::new(),
+			properties: OrderedMap::new()
+		}
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn label(&self) -> Option<&String> {
+		self.label.as_ref()
+	}
+
+	pub fn set_label(&mut self, label: &str) {
+		self.label = Some(label.to_string());
+	}
+
+	pub fn parent(&self) -> Option<NodeId> {
+		self.parent
+	}
+
+	/// Children in the order they were added
+	pub fn children(&self) -> &[NodeId] {
+		&self.children
+	}
+
+	pub fn prop_value(&self, name: &str) -> Option<&DeviceTreeProperty> {
+		self.properties.get(name)
+	}
+
+	pub fn prop_value_mut(&mut self, name: &str) -> Option<&mut DeviceTreeProperty> {
+		self.properties.get_mut(name)
+	}
+
+	pub fn prop_iter(&self) -> Iter<'_, DeviceTreeProperty> {
+		self.properties.iter()
+	}
+
+	pub fn prop_exists(&self, name: &str) -> bool {
+		self.properties.contains_key(name)
+	}
+
+	/// Add a property, see DeviceTreeNode::add_prop
+	pub fn add_prop(&mut self, prop: DeviceTreeProperty) -> Result<Option<DeviceTreeProperty>, DeviceTreeError> {
+		validate_prop_name(prop.name())?;
+
+		Ok(self.insert_prop(prop))
+	}
+
+	/// Add a property without validating its name, for properties copied from another tree
+	pub(crate) fn insert_prop(&mut self, mut prop: DeviceTreeProperty) -> Option<DeviceTreeProperty> {
+		prop.update_type();
+
+		self.properties.insert(prop.name().to_string(), prop)
+	}
+
+	pub fn remove_prop(&mut self, name: &str) -> Option<(String, DeviceTreeProperty)> {
+		self.properties.remove_entry(name)
+	}
+}
+
+/// Devicetree whose nodes live in a single Vec and are addressed by NodeId
+///
+/// Nodes are reached with `&tree[id]` and `&mut tree[id]`, so there is no RefCell to borrow.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArenaTree {
+	nodes: Vec<ArenaNode>,
+	memory_reservations: Vec<FdtReserveEntry>,
+	boot_cpuid_phys: u32
+}
+
+impl Default for ArenaTree {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ArenaTree {
+	/// A tree with an empty root node
+	pub fn new() -> Self {
+		Self {
+			nodes: vec![ArenaNode::new("/", None)],
+			memory_reservations: Vec::new(),
+			boot_cpuid_phys: 0
+		}
+	}
+
+	pub fn root(&self) -> NodeId {
+		NodeId(0)
+	}
+
+	/// Number of nodes in the tree
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	pub fn get(&self, id: NodeId) -> Option<&ArenaNode> {
+		self.nodes.get(id.0)
+	}
+
+	pub fn get_mut(&mut self, id: NodeId) -> Option<&mut ArenaNode> {
+		self.nodes.get_mut(id.0)
+	}
+
+	/// Add a child named `name` to `parent`
+	///
+	/// If `parent` already has a child with this name, its id is returned and no node is added.
+	/// Returns DeviceTreeError::BadNodeName if the name is not valid, see NodeName::parse.
+	pub fn add_child(&mut self, parent: NodeId, name: &str) -> Result<NodeId, DeviceTreeError> {
+		if NodeName::parse(name)?.is_root() {
+			return Err(DeviceTreeError::BadNodeName);
+		}
+
+		Ok(self.insert_child(parent, name))
+	}
+
+	/// Add a child without validating its name, for nodes copied from another tree
+	pub(crate) fn insert_child(&mut self, parent: NodeId, name: &str) -> NodeId {
+		if let Some(id) = self.find_child(parent, name) {
+			return id;
+		}
+
+		debug!("Adding subnode '{}' to node '{}'.", name, self[parent].name());
+
+		let id = NodeId(self.nodes.len());
+
+		self.nodes.push(ArenaNode::new(name, Some(parent)));
+		self[parent].children.push(id);
+
+		id
+	}
+
+	pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+		self[id].parent
+	}
+
+	pub fn children(&self, id: NodeId) -> &[NodeId] {
+		&self[id].children
+	}
+
+	pub fn find_child(&self, parent: NodeId, name: &str) -> Option<NodeId> {
+		self[parent].children.iter().copied().find(|&child| self[child].name == name)
+	}
+
+	pub fn first_child(&self, id: NodeId) -> Option<NodeId> {
+		self[id].children.first().copied()
+	}
+
+	pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+		let siblings = self.children(self.parent(id)?);
+		let position = siblings.iter().position(|&sibling| sibling == id)?;
+
+		siblings.get(position + 1).copied()
+	}
+
+	pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+		let siblings = self.children(self.parent(id)?);
+		let position = siblings.iter().position(|&sibling| sibling == id)?;
+
+		siblings.get(position.checked_sub(1)?).copied()
+	}
+
+	/// Find a node by its full path, e.g. '/cpus/cpu@0'
+	pub fn find_node(&self, path: &str) -> Option<NodeId> {
+		let mut current = self.root();
+
+		for name in path.split('/').filter(|name| !name.is_empty()) {
+			current = self.find_child(current, name)?;
+		}
+
+		Some(current)
+	}
+
+	/// Full path of a node from the root
+	pub fn path(&self, id: NodeId) -> String {
+		let mut names = Vec::new();
+		let mut current = id;
+
+		while let Some(parent) = self.parent(current) {
+			names.push(self[current].name());
+			current = parent;
+		}
+
+		names.reverse();
+
+		format!("/{}", names.join("/"))
+	}
+
+	/// Ids of all nodes in depth-first pre-order, starting with the root
+	pub fn ids(&self) -> Vec<NodeId> {
+		let mut ids = Vec::with_capacity(self.nodes.len());
+		let mut stack = vec![self.root()];
+
+		while let Some(id) = stack.pop() {
+			stack.extend(self[id].children.iter().rev());
+
+			ids.push(id);
+		}
+
+		ids
+	}
+
+	pub fn memory_reservations(&self) -> &[FdtReserveEntry] {
+		&self.memory_reservations
+	}
+
+	pub fn boot_cpuid_phys(&self) -> u32 {
+		self.boot_cpuid_phys
+	}
+}
+
+impl Index<NodeId> for ArenaTree {
+	type Output = ArenaNode;
+
+	fn index(&self, id: NodeId) -> &ArenaNode {
+		&self.nodes[id.0]
+	}
+}
+
+impl IndexMut<NodeId> for ArenaTree {
+	fn index_mut(&mut self, id: NodeId) -> &mut ArenaNode {
+		&mut self.nodes[id.0]
+	}
+}
+
+impl From<&DeviceTree> for ArenaTree {
+	fn from(tree: &DeviceTree) -> Self {
+		let mut arena = ArenaTree::new();
+
+		arena.memory_reservations = tree.memory_reservations().to_vec();
+		arena.boot_cpuid_phys = tree.boot_cpuid_phys();
+
+		let mut stack = vec![(Rc::clone(tree.root()), arena.root())];
+
+		while let Some((node, id)) = stack.pop() {
+			let node = node.borrow();
+
+			arena[id].label = node.label().cloned();
+			arena[id].properties = node.prop_iter().map(|(name, prop)| (name.clone(), prop.clone())).collect();
+
+			for (name, child) in node.children_iter() {
+				let child_id = arena.insert_child(id, name);
+
+				stack.push((Rc::clone(child), child_id));
+			}
+		}
+
+		arena
+	}
+}
+
+impl From<&ArenaTree> for DeviceTree {
+	fn from(arena: &ArenaTree) -> Self {
+		let mut tree = DeviceTree::new_empty_root();
+
+		let mut stack: Vec<(NodeId, DeviceTreeNodeWrap)> = vec![(arena.root(), Rc::clone(tree.root()))];
+
+		while let Some((id, node)) = stack.pop() {
+			if let Some(label) = arena[id].label() {
+				node.borrow_mut().set_label(label);
+			}
+
+			for (_, prop) in arena[id].prop_iter() {
+				node.borrow_mut().insert_prop(prop.clone());
+			}
+
+			for &child_id in arena.children(id) {
+				let child = DeviceTreeNode::new_wrap();
+
+				insert_child(&node, arena[child_id].name(), Rc::clone(&child));
+
+				stack.push((child_id, child));
+			}
+		}
+
+		for entry in arena.memory_reservations() {
+			tree.add_memory_reservation(*entry);
+		}
+
+		tree.set_boot_cpuid_phys(arena.boot_cpuid_phys());
+
+		tree
+	}
+}
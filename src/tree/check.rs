@@ -0,0 +1,571 @@
+use alloc::{
+	collections::BTreeMap,
+	string::String,
+	vec::Vec
+};
+
+use crate::DeviceTree;
+use super::{
+	node::{
+		self,
+		DeviceTreeNode,
+		DeviceTreeNodeWrap,
+		InterruptParent
+	},
+	prop::DeviceTreeProperty,
+	schema::{
+		SchemaRegistry,
+		ViolationKind
+	},
+	name::{
+		NODE_NAME_CHARS,
+		PROP_NAME_CHARS
+	},
+	graph::is_graph_node
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+	Warning,
+	Error
+}
+
+/// A semantic check on the tree, named like its counterpart in dtc
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Check {
+	/// Node names use only the allowed characters
+	NodeNameChars,
+	/// Node names have a base name and at most one '@' followed by a unit address
+	NodeNameFormat,
+	/// Property names use only the allowed characters
+	PropertyNameChars,
+	/// Standard properties have the type the specification gives them
+	PropertyTypes,
+	/// No two nodes have the same phandle
+	DuplicatePhandle,
+	/// A node has a unit address if and only if it has 'reg' or 'ranges'
+	UnitAddressVsReg,
+	/// Unit addresses have no '0x' prefix and no leading zeros
+	UnitAddressFormat,
+	/// No two siblings have the same unit address
+	UniqueUnitAddress,
+	/// 'reg' is made of whole address and size pairs
+	RegFormat,
+	/// 'ranges' is made of whole child address, parent address and size triplets
+	RangesFormat,
+	/// Nodes with 'reg' or 'ranges' do not rely on the default '#address-cells' and '#size-cells' of their parent
+	AvoidDefaultAddrSize,
+	/// 'interrupts' has an interrupt parent, and its length fits the '#interrupt-cells' of that parent;
+	/// each entry of 'interrupts-extended' refers to a node and has as many cells as its '#interrupt-cells'
+	InterruptsProperty,
+	/// The 'remote-endpoint' of a graph endpoint refers to an endpoint which refers back to it
+	GraphEndpoint
+}
+
+impl Check {
+	pub const ALL: [Check; 13] = [
+		Check::NodeNameChars,
+		Check::NodeNameFormat,
+		Check::PropertyNameChars,
+		Check::PropertyTypes,
+		Check::DuplicatePhandle,
+		Check::UnitAddressVsReg,
+		Check::UnitAddressFormat,
+		Check::UniqueUnitAddress,
+		Check::RegFormat,
+		Check::RangesFormat,
+		Check::AvoidDefaultAddrSize,
+		Check::InterruptsProperty,
+		Check::GraphEndpoint
+	];
+
+	/// Name of the check as dtc prints it
+	pub fn name(&self) -> &'static str {
+		match self {
+			Check::NodeNameChars => "node_name_chars",
+			Check::NodeNameFormat => "node_name_format",
+			Check::PropertyNameChars => "property_name_chars",
+			Check::PropertyTypes => "property_types",
+			Check::DuplicatePhandle => "duplicate_phandle",
+			Check::UnitAddressVsReg => "unit_address_vs_reg",
+			Check::UnitAddressFormat => "unit_address_format",
+			Check::UniqueUnitAddress => "unique_unit_address",
+			Check::RegFormat => "reg_format",
+			Check::RangesFormat => "ranges_format",
+			Check::AvoidDefaultAddrSize => "avoid_default_addr_size",
+			Check::InterruptsProperty => "interrupts_property",
+			Check::GraphEndpoint => "graph_endpoint"
+		}
+	}
+
+	pub fn severity(&self) -> Severity {
+		match self {
+			Check::NodeNameChars
+			| Check::NodeNameFormat
+			| Check::PropertyNameChars
+			| Check::PropertyTypes
+			| Check::DuplicatePhandle => Severity::Error,
+			_ => Severity::Warning
+		}
+	}
+
+	fn run(&self, context: &Context, findings: &mut Findings) {
+		match self.pass() {
+			Pass::Tree(check) => check(context, findings),
+			Pass::Node(check) => {
+				for node in context.nodes.iter() {
+					check(context, &node.borrow(), findings);
+				}
+			}
+		}
+	}
+
+	fn pass(&self) -> Pass {
+		match self {
+			Check::NodeNameChars => Pass::Node(|_, node, findings| node_name_chars(node, findings)),
+			Check::NodeNameFormat => Pass::Node(|_, node, findings| node_name_format(node, findings)),
+			Check::PropertyNameChars => Pass::Node(|_, node, findings| property_name_chars(node, findings)),
+			Check::PropertyTypes => Pass::Node(property_types),
+			Check::DuplicatePhandle => Pass::Tree(duplicate_phandle),
+			Check::UnitAddressVsReg => Pass::Node(|_, node, findings| unit_address_vs_reg(node, findings)),
+			Check::UnitAddressFormat => Pass::Node(|_, node, findings| unit_address_format(node, findings)),
+			Check::UniqueUnitAddress => Pass::Node(|_, node, findings| unique_unit_address(node, findings)),
+			Check::RegFormat => Pass::Node(|_, node, findings| reg_format(node, findings)),
+			Check::RangesFormat => Pass::Node(|_, node, findings| ranges_format(node, findings)),
+			Check::AvoidDefaultAddrSize => Pass::Node(|_, node, findings| avoid_default_addr_size(node, findings)),
+			Check::InterruptsProperty => Pass::Node(interrupts_property),
+			Check::GraphEndpoint => Pass::Node(graph_endpoint)
+		}
+	}
+}
+
+/// How a check goes over the tree: once for the whole tree, or once per node in tree order
+enum Pass {
+	Tree(fn(&Context, &mut Findings)),
+	Node(fn(&Context, &DeviceTreeNode, &mut Findings))
+}
+
+/// What the checks look up, built once per Checks::run rather than per node or per phandle
+struct Context {
+	/// All nodes in tree order
+	nodes: Vec<DeviceTreeNodeWrap>,
+	/// The nodes by phandle, see DeviceTree::phandles
+	phandles: BTreeMap<u32, DeviceTreeNodeWrap>,
+	/// Schemas of the standard properties, for property_types
+	registry: SchemaRegistry
+}
+
+impl Context {
+	fn new(tree: &DeviceTree) -> Self {
+		Self { nodes: tree.nodes(), phandles: tree.phandles(), registry: SchemaRegistry::new() }
+	}
+
+	fn find_phandle(&self, phandle: u32) -> Option<DeviceTreeNodeWrap> {
+		self.phandles.get(&phandle).cloned()
+	}
+}
+
+/// A problem found by a check
+#[derive(Clone, PartialEq, Debug)]
+pub struct Finding {
+	pub check: Check,
+	pub severity: Severity,
+	pub path: String,
+	pub message: String
+}
+
+impl core::fmt::Display for Finding {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let severity = match self.severity {
+			Severity::Warning => "Warning",
+			Severity::Error => "ERROR"
+		};
+
+		write!(f, "{} ({}): {}: {}", severity, self.check.name(), self.path, self.message)
+	}
+}
+
+/// Set of checks to run over a tree
+#[derive(Clone, PartialEq, Debug)]
+pub struct Checks {
+	enabled: Vec<Check>
+}
+
+impl Default for Checks {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Checks {
+	/// All checks
+	pub fn new() -> Self {
+		Self { enabled: Check::ALL.to_vec() }
+	}
+
+	/// No check, to enable some of them one by one
+	pub fn none() -> Self {
+		Self { enabled: Vec::new() }
+	}
+
+	pub fn enable(&mut self, check: Check) -> &mut Self {
+		if !self.enabled.contains(&check) {
+			self.enabled.push(check);
+		}
+
+		self
+	}
+
+	pub fn disable(&mut self, check: Check) -> &mut Self {
+		self.enabled.retain(|&c| c != check);
+
+		self
+	}
+
+	pub fn is_enabled(&self, check: Check) -> bool {
+		self.enabled.contains(&check)
+	}
+
+	/// Run the enabled checks over `tree`, in the order of Check::ALL
+	pub fn run(&self, tree: &DeviceTree) -> Vec<Finding> {
+		let context = Context::new(tree);
+		let mut findings = Findings { findings: Vec::new(), check: Check::ALL[0] };
+
+		for check in Check::ALL.iter().filter(|&&check| self.is_enabled(check)) {
+			findings.check = *check;
+
+			check.run(&context, &mut findings);
+		}
+
+		findings.findings
+	}
+}
+
+impl DeviceTree {
+	/// Run `checks` over the tree, see Checks::run
+	pub fn check(&self, checks: &Checks) -> Vec<Finding> {
+		checks.run(self)
+	}
+}
+
+/// Findings of the checks run so far
+struct Findings {
+	findings: Vec<Finding>,
+	/// The check that is running
+	check: Check
+}
+
+impl Findings {
+	fn report(&mut self, node: &DeviceTreeNode, message: String) {
+		let check = self.check;
+
+		self.findings.push(Finding { check, severity: check.severity(), path: node.path(), message });
+	}
+}
+
+/// Base name and unit address of the node name, which is empty for the root
+fn split_name(node: &DeviceTreeNode) -> (&str, Option<&str>) {
+	if !node.has_parent() {
+		return ("", None);
+	}
+
+	match node.name().split_once('@') {
+		Some((base, unit_address)) => (base, Some(unit_address)),
+		None => (node.name(), None)
+	}
+}
+
+/// Value of the u32 property `name` of `node`, if it has a valid one
+fn cells(node: &DeviceTreeNode, name: &str) -> Option<u32> {
+	node.prop_value(name)?.u32().ok()
+}
+
+/// '#address-cells' and '#size-cells' of the parent of `node`, with their defaults
+fn parent_cells(node: &DeviceTreeNode) -> (u32, u32) {
+	match node.parent() {
+		Some(parent) => {
+			let parent = parent.borrow();
+
+			(cells(&parent, "#address-cells").unwrap_or(2), cells(&parent, "#size-cells").unwrap_or(1))
+		},
+		None => (2, 1)
+	}
+}
+
+fn node_name_chars(node: &DeviceTreeNode, findings: &mut Findings) {
+	let (base, unit_address) = split_name(node);
+
+	let bad = base.chars().chain(unit_address.unwrap_or("").chars()).find(|&c| !NODE_NAME_CHARS.contains(c));
+
+	if let Some(c) = bad {
+		findings.report(node, format!("Bad character '{}' in node name", c));
+	}
+}
+
+fn node_name_format(node: &DeviceTreeNode, findings: &mut Findings) {
+	let (base, unit_address) = split_name(node);
+
+	if node.has_parent() && base.is_empty() {
+		findings.report(node, String::from("node name is missing its base name"));
+	}
+
+	if unit_address.is_some_and(|unit_address| unit_address.contains('@')) {
+		findings.report(node, String::from("multiple '@' in node name"));
+	}
+}
+
+fn property_name_chars(node: &DeviceTreeNode, findings: &mut Findings) {
+	for (name, _) in node.prop_iter() {
+		if let Some(c) = name.chars().find(|&c| !PROP_NAME_CHARS.contains(c)) {
+			findings.report(node, format!("Bad character '{}' in property name '{}'", c, name));
+		}
+	}
+}
+
+fn property_types(context: &Context, node: &DeviceTreeNode, findings: &mut Findings) {
+	for violation in context.registry.validate_node(node) {
+		if violation.kind == ViolationKind::BadValue {
+			findings.report(node, format!("property '{}' does not have the type of the specification", violation.property));
+		}
+	}
+}
+
+fn duplicate_phandle(context: &Context, findings: &mut Findings) {
+	let mut seen: BTreeMap<u32, &DeviceTreeNodeWrap> = BTreeMap::new();
+
+	for node in context.nodes.iter() {
+		let phandle = match node.borrow().phandle() {
+			Some(phandle) => phandle,
+			None => continue
+		};
+
+		match seen.get(&phandle) {
+			Some(first) => {
+				let message = format!("duplicated phandle {:#x} (seen before at {})", phandle, first.borrow().path());
+
+				findings.report(&node.borrow(), message);
+			},
+			None => {
+				seen.insert(phandle, node);
+			}
+		}
+	}
+}
+
+fn unit_address_vs_reg(node: &DeviceTreeNode, findings: &mut Findings) {
+	// An empty 'ranges' maps addresses one to one, it does not give the node an address
+	let has_reg = node.prop_exists("reg") || node.prop_value("ranges").is_some_and(|ranges| !ranges.bytes().is_empty());
+
+	match split_name(node).1 {
+		Some(_) if !has_reg => findings.report(node, String::from("node has a unit name, but no reg or ranges property")),
+		None if has_reg && node.has_parent() => findings.report(node, String::from("node has a reg or ranges property, but no unit name")),
+		_ => ()
+	}
+}
+
+fn unit_address_format(node: &DeviceTreeNode, findings: &mut Findings) {
+	let unit_address = match split_name(node).1 {
+		Some(unit_address) => unit_address,
+		None => return
+	};
+
+	if unit_address.starts_with("0x") {
+		findings.report(node, String::from("unit name should not have leading \"0x\""));
+	} else if unit_address.len() > 1 && unit_address.starts_with('0') {
+		findings.report(node, String::from("unit name should not have leading 0s"));
+	}
+}
+
+fn unique_unit_address(node: &DeviceTreeNode, findings: &mut Findings) {
+	let mut seen: BTreeMap<String, String> = BTreeMap::new();
+
+	for (name, child) in node.children_iter() {
+		let unit_address = match name.split_once('@') {
+			Some((_, unit_address)) => unit_address,
+			None => continue
+		};
+
+		match seen.get(unit_address) {
+			Some(first) => {
+				let message = format!("duplicate unit-address (also used in node {})", first);
+
+				findings.report(&child.borrow(), message);
+			},
+			None => {
+				seen.insert(String::from(unit_address), child.borrow().path());
+			}
+		}
+	}
+}
+
+fn reg_format(node: &DeviceTreeNode, findings: &mut Findings) {
+	let reg = match node.prop_value("reg") {
+		Some(reg) => reg,
+		None => return
+	};
+
+	let (address_cells, size_cells) = parent_cells(node);
+	let entry_size = (address_cells + size_cells) as usize * 4;
+
+	let len = reg.bytes().len();
+
+	if len == 0 || entry_size == 0 || len % entry_size != 0 {
+		let message = format!(
+			"\"reg\" property has invalid length ({} bytes) (#address-cells == {}, #size-cells == {})",
+			len, address_cells, size_cells
+		);
+
+		findings.report(node, message);
+	}
+}
+
+fn ranges_format(node: &DeviceTreeNode, findings: &mut Findings) {
+	let ranges = match node.prop_value("ranges") {
+		Some(ranges) => ranges,
+		None => return
+	};
+
+	// An empty 'ranges' maps the child address space one to one
+	let len = ranges.bytes().len();
+
+	if len == 0 {
+		return;
+	}
+
+	let parent_address_cells = parent_cells(node).0;
+	let child_address_cells = cells(node, "#address-cells").unwrap_or(2);
+	let child_size_cells = cells(node, "#size-cells").unwrap_or(1);
+
+	let entry_size = (child_address_cells + parent_address_cells + child_size_cells) as usize * 4;
+
+	if len % entry_size != 0 {
+		let message = format!(
+			"\"ranges\" property has invalid length ({} bytes) (parent #address-cells == {}, child #address-cells == {}, #size-cells == {})",
+			len, parent_address_cells, child_address_cells, child_size_cells
+		);
+
+		findings.report(node, message);
+	}
+}
+
+fn avoid_default_addr_size(node: &DeviceTreeNode, findings: &mut Findings) {
+	if !node.prop_exists("reg") && !node.prop_exists("ranges") {
+		return;
+	}
+
+	let parent = match node.parent() {
+		Some(parent) => parent,
+		None => return
+	};
+
+	let parent = parent.borrow();
+
+	if !parent.prop_exists("#address-cells") {
+		findings.report(node, String::from("Relying on default #address-cells value"));
+	}
+
+	if !parent.prop_exists("#size-cells") {
+		findings.report(node, String::from("Relying on default #size-cells value"));
+	}
+}
+
+fn interrupts_property(context: &Context, node: &DeviceTreeNode, findings: &mut Findings) {
+	// 'interrupts-extended' takes precedence over 'interrupts' and names the parent of each interrupt
+	if let Some(interrupts) = node.prop_value("interrupts-extended") {
+		return interrupts_extended(context, node, interrupts, findings);
+	}
+
+	let interrupts = match node.prop_value("interrupts") {
+		Some(interrupts) => interrupts,
+		None => return
+	};
+
+	let controller = match interrupt_parent(context, node) {
+		Ok(controller) => controller,
+		Err(message) => return findings.report(node, message)
+	};
+
+	let controller = controller.borrow();
+
+	let interrupt_cells = match cells(&controller, "#interrupt-cells") {
+		Some(interrupt_cells) => interrupt_cells as usize,
+		None => return findings.report(node, format!("Missing #interrupt-cells in interrupt-parent {}", controller.path()))
+	};
+
+	let len = interrupts.bytes().len();
+
+	if interrupt_cells == 0 || len % (interrupt_cells * 4) != 0 {
+		findings.report(node, format!("interrupts size is ({}), expected multiple of {}", len, interrupt_cells * 4));
+	}
+}
+
+/// Check that each entry of 'interrupts-extended' is a phandle followed by the '#interrupt-cells' of its node
+fn interrupts_extended(context: &Context, node: &DeviceTreeNode, prop: &DeviceTreeProperty, findings: &mut Findings) {
+	let len = prop.bytes().len();
+
+	let list = match prop.cells() {
+		Ok(list) => list,
+		Err(_) => return findings.report(node, format!("interrupts-extended size ({}) is invalid, expected multiple of 4", len))
+	};
+
+	let mut i = 0;
+
+	while i < list.len() {
+		let controller = match context.find_phandle(list[i]) {
+			Some(controller) => controller,
+			None => return findings.report(node, format!("Bad phandle {:#x} in interrupts-extended (cell {})", list[i], i))
+		};
+
+		let controller = controller.borrow();
+
+		let interrupt_cells = match cells(&controller, "#interrupt-cells") {
+			Some(interrupt_cells) => interrupt_cells as usize,
+			None => return findings.report(node, format!("Missing #interrupt-cells in interrupt-parent {}", controller.path()))
+		};
+
+		if i + 1 + interrupt_cells > list.len() {
+			return findings.report(node, format!(
+				"interrupts-extended size ({}) too small for #interrupt-cells {} of {}",
+				len, interrupt_cells, controller.path()
+			));
+		}
+
+		i += 1 + interrupt_cells;
+	}
+}
+
+/// The interrupt parent of `node`, see node::interrupt_parent, or the message to report
+fn interrupt_parent(context: &Context, node: &DeviceTreeNode) -> Result<DeviceTreeNodeWrap, String> {
+	match node::interrupt_parent(node) {
+		Some(InterruptParent::Provider(provider)) => Ok(provider),
+		Some(InterruptParent::Phandle(Ok(phandle))) => context.find_phandle(phandle)
+			.ok_or_else(|| format!("Bad phandle {:#x} for interrupt-parent", phandle)),
+		Some(InterruptParent::Phandle(Err(_))) => Err(String::from("Bad interrupt-parent property")),
+		None => Err(String::from("Missing interrupt-parent"))
+	}
+}
+
+fn graph_endpoint(context: &Context, node: &DeviceTreeNode, findings: &mut Findings) {
+	let is_endpoint = is_graph_node(node.name(), "endpoint")
+		&& node.parent().is_some_and(|port| is_graph_node(port.borrow().name(), "port"));
+
+	let phandle = match (is_endpoint, node.prop_value("remote-endpoint")) {
+		(true, Some(prop)) => match prop.phandle() {
+			Ok(phandle) => phandle,
+			Err(_) => return findings.report(node, String::from("Bad remote-endpoint property"))
+		},
+		_ => return
+	};
+
+	let remote = match context.find_phandle(phandle) {
+		Some(remote) => remote,
+		None => return findings.report(node, format!("graph phandle {:#x} is not valid", phandle))
+	};
+
+	let remote = remote.borrow();
+	let back = remote.prop_value("remote-endpoint").and_then(|prop| prop.phandle().ok());
+
+	if back.is_none() || back != node.phandle() {
+		findings.report(node, format!("graph connection to node '{}' is not bidirectional", remote.path()));
+	}
+}
@@ -0,0 +1,193 @@
+use alloc::{
+	rc::Rc,
+	string::{
+		String,
+		ToString
+	},
+	vec::Vec
+};
+use log::error;
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::{
+	node::{
+		DeviceTreeNode,
+		DeviceTreeNodeWrap
+	},
+	prop::DeviceTreeProperty,
+	CPU_MAX_NUM
+};
+
+/// A CPU of the system, read from a node of '/cpus' with 'device_type = "cpu"'
+#[derive(Clone, Debug)]
+pub struct Cpu {
+	node: DeviceTreeNodeWrap,
+	id: u64,
+	status: String,
+	enable_method: Vec<String>,
+	cpu_release_addr: Option<u64>,
+	isa: Option<String>,
+	mmu_type: Option<String>,
+	clock_frequency: Option<u64>,
+	timebase_frequency: Option<u64>,
+	interrupt_controller: Option<DeviceTreeNodeWrap>
+}
+
+impl Cpu {
+	/// Read the CPU described by `node`, a child of `cpus`
+	///
+	/// Returns DeviceTreeError::BadPropValue if 'reg' is missing or if a property is malformed.
+	pub fn from_node(node: &DeviceTreeNodeWrap, cpus: &DeviceTreeNode) -> Result<Self, DeviceTreeError> {
+		let cpu = node.borrow();
+
+		let address_cells = match cpus.prop_value("#address-cells") {
+			Some(prop) => prop.u32()?,
+			None => 2
+		};
+
+		// With several hardware threads 'reg' has one entry per thread, the first one identifies the CPU
+		let reg = cpu.prop_value("reg").ok_or(DeviceTreeError::BadPropValue)?;
+		let id = reg.encoded_array(&[address_cells])?.first().ok_or(DeviceTreeError::BadPropValue)?[0];
+
+		let string = |name: &str| -> Result<Option<String>, DeviceTreeError> {
+			cpu.prop_value(name).map(|prop| prop.string().map(|s| s.to_string())).transpose()
+		};
+
+		// Frequencies given for all CPUs are properties of '/cpus'
+		let frequency = |name: &str| -> Result<Option<u64>, DeviceTreeError> {
+			cpu.prop_value(name).or_else(|| cpus.prop_value(name)).map(u32_or_u64).transpose()
+		};
+
+		let enable_method = match cpu.prop_value("enable-method") {
+			Some(prop) => prop.stringlist()?.into_iter().map(|s| s.to_string()).collect(),
+			None => Vec::new()
+		};
+
+		let interrupt_controller = cpu.children_iter()
+			.map(|(_, child)| child)
+			.find(|child| child.borrow().prop_exists("interrupt-controller"))
+			.map(Rc::clone);
+
+		Ok(Self {
+			node: Rc::clone(node),
+			id,
+			status: string("status")?.unwrap_or_else(|| String::from("okay")),
+			enable_method,
+			cpu_release_addr: cpu.prop_value("cpu-release-addr").map(u32_or_u64).transpose()?,
+			isa: string("riscv,isa")?,
+			mmu_type: string("mmu-type")?,
+			clock_frequency: frequency("clock-frequency")?,
+			timebase_frequency: frequency("timebase-frequency")?,
+			interrupt_controller
+		})
+	}
+
+	pub fn node(&self) -> &DeviceTreeNodeWrap {
+		&self.node
+	}
+
+	/// Hardware ID from 'reg': the hart ID on RISC-V, the MPIDR affinity bits on Arm
+	pub fn id(&self) -> u64 {
+		self.id
+	}
+
+	/// Value of 'status', "okay" if the node has none
+	pub fn status(&self) -> &str {
+		&self.status
+	}
+
+	pub fn is_enabled(&self) -> bool {
+		self.status == "okay" || self.status == "ok"
+	}
+
+	/// Methods to bring the CPU online, e.g. "psci" or "spin-table"
+	pub fn enable_method(&self) -> &[String] {
+		&self.enable_method
+	}
+
+	/// Release address of the "spin-table" enable method
+	pub fn cpu_release_addr(&self) -> Option<u64> {
+		self.cpu_release_addr
+	}
+
+	/// Value of 'riscv,isa', e.g. "rv64imafdc"
+	pub fn isa(&self) -> Option<&str> {
+		self.isa.as_deref()
+	}
+
+	/// Value of 'mmu-type', e.g. "riscv,sv39"
+	pub fn mmu_type(&self) -> Option<&str> {
+		self.mmu_type.as_deref()
+	}
+
+	/// Clock frequency in Hz, from the CPU node or else from '/cpus'
+	pub fn clock_frequency(&self) -> Option<u64> {
+		self.clock_frequency
+	}
+
+	/// Timebase frequency in Hz, from the CPU node or else from '/cpus'
+	pub fn timebase_frequency(&self) -> Option<u64> {
+		self.timebase_frequency
+	}
+
+	/// The child node that is the local interrupt controller of the CPU, e.g. 'riscv,cpu-intc'
+	pub fn interrupt_controller(&self) -> Option<&DeviceTreeNodeWrap> {
+		self.interrupt_controller.as_ref()
+	}
+}
+
+impl DeviceTree {
+	/// The CPUs of the system, in the order of their nodes
+	///
+	/// Only children of '/cpus' with 'device_type = "cpu"' are CPUs, so 'cpu-map' and cache nodes are left out.
+	/// A CPU node that cannot be read, see Cpu::from_node, is logged and left out as well.
+	/// Returns DeviceTreeError::CpuNumInvalid if there are more than CPU_MAX_NUM of them.
+	pub fn cpus(&self) -> Result<Vec<Cpu>, DeviceTreeError> {
+		let cpus = match self.root().borrow().find_child("cpus") {
+			Some(cpus) => Rc::clone(cpus),
+			None => return Ok(Vec::new())
+		};
+
+		let cpus = cpus.borrow();
+
+		let nodes: Vec<&DeviceTreeNodeWrap> = cpus.children_iter()
+			.map(|(_, node)| node)
+			.filter(|node| is_cpu(&node.borrow()))
+			.collect();
+
+		if nodes.len() as u64 > CPU_MAX_NUM as u64 {
+			return Err(DeviceTreeError::CpuNumInvalid);
+		}
+
+		let cpus = nodes.into_iter()
+			.filter_map(|node| match Cpu::from_node(node, &cpus) {
+				Ok(cpu) => Some(cpu),
+				Err(error) => {
+					error!("Skipping CPU node '{}': {}.", node.borrow().path(), error);
+					None
+				}
+			})
+			.collect();
+
+		Ok(cpus)
+	}
+
+	/// The CPU whose ID is the 'boot_cpuid_phys' of the header
+	pub fn boot_cpu(&self) -> Result<Option<Cpu>, DeviceTreeError> {
+		let boot_cpuid_phys = self.boot_cpuid_phys() as u64;
+
+		Ok(self.cpus()?.into_iter().find(|cpu| cpu.id() == boot_cpuid_phys))
+	}
+}
+
+pub(crate) fn is_cpu(node: &DeviceTreeNode) -> bool {
+	node.prop_value("device_type").and_then(|prop| prop.string().ok()) == Some("cpu")
+}
+
+/// Value of a property that is either one or two cells
+fn u32_or_u64(prop: &DeviceTreeProperty) -> Result<u64, DeviceTreeError> {
+	prop.u64().or_else(|_| prop.u32().map(u64::from))
+}
@@ -0,0 +1,457 @@
+use alloc::{
+	collections::{
+		BTreeMap,
+		BTreeSet
+	},
+	rc::Rc,
+	string::{
+		String,
+		ToString
+	},
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::node::{
+	self,
+	DeviceTreeNode,
+	DeviceTreeNodeWrap,
+	InterruptParent
+};
+
+/// Properties made of phandles each followed by as many arguments as the '#*-cells' of the node it refers to
+const PHANDLE_ARGS: [(&str, &str); 7] = [
+	("clocks", "#clock-cells"),
+	("resets", "#reset-cells"),
+	("interrupts-extended", "#interrupt-cells"),
+	("power-domains", "#power-domain-cells"),
+	("iommus", "#iommu-cells"),
+	("dmas", "#dma-cells"),
+	("phys", "#phy-cells")
+];
+
+/// "Supplier before consumer": `consumer` refers to `supplier` through `property`
+#[derive(Clone, Debug)]
+pub struct Dependency {
+	consumer: DeviceTreeNodeWrap,
+	supplier: DeviceTreeNodeWrap,
+	property: String
+}
+
+impl Dependency {
+	pub fn consumer(&self) -> &DeviceTreeNodeWrap {
+		&self.consumer
+	}
+
+	pub fn supplier(&self) -> &DeviceTreeNodeWrap {
+		&self.supplier
+	}
+
+	/// The property of the consumer the dependency comes from, or 'parent' for the one on the parent node
+	pub fn property(&self) -> &str {
+		&self.property
+	}
+}
+
+/// A property of `consumer` with a reference that could not be followed, e.g. to an unknown phandle
+///
+/// The references of the property before the broken one are still in the graph.
+#[derive(Clone, Debug)]
+pub struct BrokenLink {
+	consumer: DeviceTreeNodeWrap,
+	property: String,
+	error: DeviceTreeError
+}
+
+impl BrokenLink {
+	pub fn consumer(&self) -> &DeviceTreeNodeWrap {
+		&self.consumer
+	}
+
+	pub fn property(&self) -> &str {
+		&self.property
+	}
+
+	/// DeviceTreeError::BadPhandle for a phandle without node, DeviceTreeError::BadPropValue for a malformed
+	/// property or a missing '#*-cells'
+	pub fn error(&self) -> &DeviceTreeError {
+		&self.error
+	}
+}
+
+/// Order in which to probe the nodes, see DependencyGraph::probe_order
+#[derive(Clone, Debug)]
+pub struct ProbeOrder {
+	order: Vec<DeviceTreeNodeWrap>,
+	cycles: Vec<Vec<DeviceTreeNodeWrap>>
+}
+
+impl ProbeOrder {
+	/// Every node of the graph, each after its suppliers unless they are in a cycle together
+	pub fn order(&self) -> &[DeviceTreeNodeWrap] {
+		&self.order
+	}
+
+	/// The cycles of dependencies, each with its nodes in tree order
+	pub fn cycles(&self) -> &[Vec<DeviceTreeNodeWrap>] {
+		&self.cycles
+	}
+
+	pub fn has_cycles(&self) -> bool {
+		!self.cycles.is_empty()
+	}
+}
+
+/// Dependencies between the enabled nodes of a tree, like fw_devlink of Linux
+///
+/// A phandle in clocks, resets, interrupts, regulators ('*-supply'), gpios, pinctrl-N, power-domains, iommus,
+/// dmas or phys makes the node a consumer of the device the phandle points into, i.e. the closest node with
+/// 'compatible' from the target up. Every node also depends on its parent, which populates it.
+/// Links to disabled nodes and to descendants of the consumer are dropped. Like fw_devlink, references that
+/// cannot be followed are skipped rather than failing the whole graph, and are listed by broken_links.
+#[derive(Clone, Debug)]
+pub struct DependencyGraph {
+	nodes: Vec<DeviceTreeNodeWrap>,
+	dependencies: Vec<Dependency>,
+	broken_links: Vec<BrokenLink>,
+	/// Indices in `nodes` of the supplier of each dependency, by consumer
+	suppliers: Vec<BTreeSet<usize>>
+}
+
+impl DependencyGraph {
+	/// Build the graph of the enabled nodes of `tree`
+	pub fn new(tree: &DeviceTree) -> Self {
+		let nodes: Vec<DeviceTreeNodeWrap> = tree.nodes().into_iter().filter(|node| node.borrow().is_enabled()).collect();
+		let index: BTreeMap<*const _, usize> = nodes.iter().enumerate().map(|(i, node)| (Rc::as_ptr(node), i)).collect();
+
+		let mut dependencies = Vec::new();
+		let mut broken_links = Vec::new();
+		let mut suppliers = vec![BTreeSet::new(); nodes.len()];
+
+		for (consumer_index, consumer) in nodes.iter().enumerate() {
+			let parent = consumer.borrow().parent().map(|parent| (String::from("parent"), parent));
+			let (references, broken) = references(tree, consumer);
+
+			broken_links.extend(broken);
+
+			let references = references.into_iter().map(|(property, target)| (property, device_of(&target)));
+
+			for (property, supplier) in parent.into_iter().chain(references) {
+				let supplier_index = match index.get(&Rc::as_ptr(&supplier)) {
+					Some(&supplier_index) => supplier_index,
+					None => continue
+				};
+
+				if supplier_index == consumer_index || is_ancestor(consumer, &supplier) {
+					continue;
+				}
+
+				suppliers[consumer_index].insert(supplier_index);
+				dependencies.push(Dependency { consumer: Rc::clone(consumer), supplier, property });
+			}
+		}
+
+		Self { nodes, dependencies, broken_links, suppliers }
+	}
+
+	/// The enabled nodes, in tree order
+	pub fn nodes(&self) -> &[DeviceTreeNodeWrap] {
+		&self.nodes
+	}
+
+	pub fn dependencies(&self) -> &[Dependency] {
+		&self.dependencies
+	}
+
+	/// The properties whose references could not all be followed, in tree order
+	pub fn broken_links(&self) -> &[BrokenLink] {
+		&self.broken_links
+	}
+
+	/// The nodes `node` depends on, in tree order
+	pub fn suppliers(&self, node: &DeviceTreeNodeWrap) -> Vec<DeviceTreeNodeWrap> {
+		match self.index(node) {
+			Some(i) => self.suppliers[i].iter().map(|&supplier| Rc::clone(&self.nodes[supplier])).collect(),
+			None => Vec::new()
+		}
+	}
+
+	/// The nodes that depend on `node`, in tree order
+	pub fn consumers(&self, node: &DeviceTreeNodeWrap) -> Vec<DeviceTreeNodeWrap> {
+		match self.index(node) {
+			Some(i) => (0..self.nodes.len())
+				.filter(|&consumer| self.suppliers[consumer].contains(&i))
+				.map(|consumer| Rc::clone(&self.nodes[consumer]))
+				.collect(),
+			None => Vec::new()
+		}
+	}
+
+	/// A topological order of the nodes, ties broken by tree order so that it is deterministic
+	///
+	/// When every remaining node waits for another one, the first of them in tree order goes next, which breaks
+	/// the cycles the way fw_devlink relaxes them. The cycles themselves are reported.
+	pub fn probe_order(&self) -> ProbeOrder {
+		let n = self.nodes.len();
+		let mut pending: Vec<usize> = self.suppliers.iter().map(|suppliers| suppliers.len()).collect();
+		let mut consumers = vec![Vec::new(); n];
+
+		for (consumer, suppliers) in self.suppliers.iter().enumerate() {
+			for &supplier in suppliers {
+				consumers[supplier].push(consumer);
+			}
+		}
+
+		let mut ready: BTreeSet<usize> = (0..n).filter(|&i| pending[i] == 0).collect();
+		let mut done = vec![false; n];
+		let mut order = Vec::with_capacity(n);
+
+		while order.len() < n {
+			let next = match ready.pop_first() {
+				Some(next) => next,
+				None => (0..n).find(|&i| !done[i]).unwrap()
+			};
+
+			if done[next] {
+				continue;
+			}
+
+			done[next] = true;
+			order.push(Rc::clone(&self.nodes[next]));
+
+			for &consumer in &consumers[next] {
+				pending[consumer] -= 1;
+
+				if pending[consumer] == 0 && !done[consumer] {
+					ready.insert(consumer);
+				}
+			}
+		}
+
+		ProbeOrder { order, cycles: self.cycles() }
+	}
+
+	/// The strongly connected components of more than one node, each in tree order
+	fn cycles(&self) -> Vec<Vec<DeviceTreeNodeWrap>> {
+		let mut tarjan = Tarjan {
+			suppliers: &self.suppliers,
+			index: vec![None; self.nodes.len()],
+			low: vec![0; self.nodes.len()],
+			stack: Vec::new(),
+			on_stack: vec![false; self.nodes.len()],
+			next: 0,
+			components: Vec::new()
+		};
+
+		for i in 0..self.nodes.len() {
+			if tarjan.index[i].is_none() {
+				tarjan.visit(i);
+			}
+		}
+
+		let mut cycles: Vec<Vec<usize>> = tarjan.components.into_iter().filter(|component| component.len() > 1).collect();
+
+		for cycle in cycles.iter_mut() {
+			cycle.sort();
+		}
+
+		cycles.sort();
+
+		cycles.into_iter()
+			.map(|cycle| cycle.into_iter().map(|i| Rc::clone(&self.nodes[i])).collect())
+			.collect()
+	}
+
+	fn index(&self, node: &DeviceTreeNodeWrap) -> Option<usize> {
+		self.nodes.iter().position(|other| Rc::ptr_eq(other, node))
+	}
+}
+
+impl DeviceTree {
+	/// The dependency graph of the enabled nodes, see DependencyGraph
+	pub fn dependency_graph(&self) -> DependencyGraph {
+		DependencyGraph::new(self)
+	}
+
+	/// The enabled nodes in an order where suppliers come before their consumers, see DependencyGraph::probe_order
+	pub fn probe_order(&self) -> ProbeOrder {
+		self.dependency_graph().probe_order()
+	}
+}
+
+/// Tarjan's strongly connected components over the supplier edges
+struct Tarjan<'a> {
+	suppliers: &'a [BTreeSet<usize>],
+	index: Vec<Option<usize>>,
+	low: Vec<usize>,
+	stack: Vec<usize>,
+	on_stack: Vec<bool>,
+	next: usize,
+	components: Vec<Vec<usize>>
+}
+
+impl Tarjan<'_> {
+	fn visit(&mut self, v: usize) {
+		self.index[v] = Some(self.next);
+		self.low[v] = self.next;
+		self.next += 1;
+		self.stack.push(v);
+		self.on_stack[v] = true;
+
+		for &w in self.suppliers[v].iter() {
+			match self.index[w] {
+				None => {
+					self.visit(w);
+					self.low[v] = self.low[v].min(self.low[w]);
+				},
+				Some(index) if self.on_stack[w] => self.low[v] = self.low[v].min(index),
+				Some(_) => {}
+			}
+		}
+
+		if Some(self.low[v]) == self.index[v] {
+			let mut component = Vec::new();
+
+			while let Some(w) = self.stack.pop() {
+				self.on_stack[w] = false;
+				component.push(w);
+
+				if w == v {
+					break;
+				}
+			}
+
+			self.components.push(component);
+		}
+	}
+}
+
+/// The nodes `node` refers to, with the property of each reference, and the properties that could not be followed
+fn references(tree: &DeviceTree, consumer: &DeviceTreeNodeWrap) -> (Vec<(String, DeviceTreeNodeWrap)>, Vec<BrokenLink>) {
+	let node = consumer.borrow();
+
+	let mut references = Vec::new();
+	let mut broken = Vec::new();
+
+	for (name, prop) in node.prop_iter() {
+		let cells_name = PHANDLE_ARGS.iter()
+			.find(|(property, _)| *property == name)
+			.map(|(_, cells_name)| *cells_name)
+			.or_else(|| is_gpio_property(name).then_some("#gpio-cells"));
+
+		let mut targets = Vec::new();
+
+		let result = match cells_name {
+			Some(cells_name) => prop.cells().and_then(|cells| phandle_args(tree, &cells, cells_name, &mut targets)),
+			None if name.ends_with("-supply") => prop.phandle()
+				.and_then(|phandle| resolve(tree, phandle))
+				.map(|target| targets.push(target)),
+			None if is_pinctrl_property(name) => prop.cells().and_then(|phandles| {
+				phandles.into_iter().try_for_each(|phandle| resolve(tree, phandle).map(|target| targets.push(target)))
+			}),
+			None if name == "interrupts" => interrupt_parent(tree, &node).map(|parent| targets.extend(parent)),
+			None => continue
+		};
+
+		references.extend(targets.into_iter().map(|target| (name.to_string(), target)));
+
+		if let Err(error) = result {
+			broken.push(BrokenLink { consumer: Rc::clone(consumer), property: name.to_string(), error });
+		}
+	}
+
+	(references, broken)
+}
+
+/// The nodes of a list of phandles with arguments, where a phandle of 0 is an empty entry without arguments
+///
+/// The nodes are added to `targets` up to the first entry that cannot be followed.
+fn phandle_args(
+	tree: &DeviceTree,
+	cells: &[u32],
+	cells_name: &str,
+	targets: &mut Vec<DeviceTreeNodeWrap>
+) -> Result<(), DeviceTreeError> {
+	let mut cells = cells;
+
+	while let Some((&phandle, rest)) = cells.split_first() {
+		cells = rest;
+
+		if phandle == 0 {
+			continue;
+		}
+
+		let target = resolve(tree, phandle)?;
+		let args = target.borrow().prop_value(cells_name).ok_or(DeviceTreeError::BadPropValue)?.u32()? as usize;
+
+		cells = cells.get(args..).ok_or(DeviceTreeError::BadPropValue)?;
+		targets.push(target);
+	}
+
+	Ok(())
+}
+
+fn resolve(tree: &DeviceTree, phandle: u32) -> Result<DeviceTreeNodeWrap, DeviceTreeError> {
+	tree.find_phandle(phandle).ok_or(DeviceTreeError::BadPhandle)
+}
+
+/// The interrupt parent of the node, see node::interrupt_parent, which may be an interrupt controller ancestor
+fn interrupt_parent(tree: &DeviceTree, node: &DeviceTreeNode) -> Result<Option<DeviceTreeNodeWrap>, DeviceTreeError> {
+	match node::interrupt_parent(node) {
+		Some(InterruptParent::Provider(provider)) => Ok(Some(provider)),
+		Some(InterruptParent::Phandle(phandle)) => resolve(tree, phandle?).map(Some),
+		None => Ok(None)
+	}
+}
+
+/// Whether `name` is 'gpios', '*-gpios' or the older '*-gpio', but not 'gpio-*' properties of controllers
+///
+/// 'nr-gpios' and '<vendor>,nr-gpios' are the number of GPIOs of a controller, not references.
+fn is_gpio_property(name: &str) -> bool {
+	if name == "nr-gpios" || name.ends_with(",nr-gpios") {
+		return false;
+	}
+
+	name == "gpios" || name.ends_with("-gpios") || name.ends_with("-gpio")
+}
+
+/// Whether `name` is 'pinctrl-N'
+fn is_pinctrl_property(name: &str) -> bool {
+	name.strip_prefix("pinctrl-").is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The device `node` belongs to: the closest node with 'compatible' from `node` up, or else `node` itself
+fn device_of(node: &DeviceTreeNodeWrap) -> DeviceTreeNodeWrap {
+	let mut current = Rc::clone(node);
+
+	loop {
+		if current.borrow().prop_exists("compatible") {
+			return current;
+		}
+
+		let parent = match current.borrow().parent() {
+			Some(parent) => parent,
+			None => return Rc::clone(node)
+		};
+
+		current = parent;
+	}
+}
+
+/// Whether `ancestor` is a strict ancestor of `node`
+fn is_ancestor(ancestor: &DeviceTreeNodeWrap, node: &DeviceTreeNodeWrap) -> bool {
+	let mut current = node.borrow().parent();
+
+	while let Some(parent) = current {
+		if Rc::ptr_eq(&parent, ancestor) {
+			return true;
+		}
+
+		current = parent.borrow().parent();
+	}
+
+	false
+}
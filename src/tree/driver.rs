@@ -0,0 +1,200 @@
+use alloc::{
+	boxed::Box,
+	rc::Rc,
+	string::{
+		String,
+		ToString
+	},
+	vec::Vec
+};
+
+use crate::DeviceTree;
+use super::node::DeviceTreeNodeWrap;
+
+/// Callback of a driver, given the node and the compatible string it matched
+pub type ProbeFn<'a, E> = Box<dyn FnMut(&DeviceTreeNodeWrap, &str) -> Result<(), E> + 'a>;
+
+/// A driver: a name, the compatible strings it handles and its probe callback
+pub struct Driver<'a, E> {
+	name: String,
+	compatible: Vec<String>,
+	probe: ProbeFn<'a, E>
+}
+
+impl<'a, E> Driver<'a, E> {
+	pub fn new(
+		name: &str,
+		compatible: &[&str],
+		probe: impl FnMut(&DeviceTreeNodeWrap, &str) -> Result<(), E> + 'a
+	) -> Self {
+		Self {
+			name: name.to_string(),
+			compatible: compatible.iter().map(|s| s.to_string()).collect(),
+			probe: Box::new(probe)
+		}
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn compatible(&self) -> &[String] {
+		&self.compatible
+	}
+}
+
+/// A node handed to a driver, through one of its compatible strings
+#[derive(Clone, Debug)]
+pub struct Binding {
+	node: DeviceTreeNodeWrap,
+	driver: String,
+	compatible: String
+}
+
+impl Binding {
+	pub fn node(&self) -> &DeviceTreeNodeWrap {
+		&self.node
+	}
+
+	/// Name of the driver
+	pub fn driver(&self) -> &str {
+		&self.driver
+	}
+
+	/// The compatible string of the node that matched the driver
+	pub fn compatible(&self) -> &str {
+		&self.compatible
+	}
+}
+
+/// Outcome of DriverRegistry::bind
+#[derive(Debug)]
+pub struct BindReport<E> {
+	bound: Vec<Binding>,
+	failed: Vec<(Binding, E)>,
+	unmatched: Vec<DeviceTreeNodeWrap>
+}
+
+impl<E> BindReport<E> {
+	/// Nodes whose driver probed successfully, in the order they were probed
+	pub fn bound(&self) -> &[Binding] {
+		&self.bound
+	}
+
+	/// Nodes whose driver failed to probe, with the error of the driver
+	pub fn failed(&self) -> &[(Binding, E)] {
+		&self.failed
+	}
+
+	/// Enabled nodes with 'compatible' that no driver handles
+	pub fn unmatched(&self) -> &[DeviceTreeNodeWrap] {
+		&self.unmatched
+	}
+
+	pub fn is_bound(&self, node: &DeviceTreeNodeWrap) -> bool {
+		self.bound.iter().any(|binding| Rc::ptr_eq(&binding.node, node))
+	}
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for BindReport<E> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		for binding in &self.bound {
+			writeln!(f, "bound: {}: {} ({})", binding.node.borrow().path(), binding.driver, binding.compatible)?;
+		}
+
+		for (binding, error) in &self.failed {
+			writeln!(f, "failed: {}: {} ({}): {}", binding.node.borrow().path(), binding.driver, binding.compatible, error)?;
+		}
+
+		for node in &self.unmatched {
+			let node = node.borrow();
+
+			let compatible = node.prop_value("compatible")
+				.and_then(|prop| prop.stringlist().ok())
+				.unwrap_or_default();
+
+			writeln!(f, "unmatched: {}: {}", node.path(), compatible.join(", "))?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Drivers matched against the nodes of a tree by 'compatible'
+pub struct DriverRegistry<'a, E> {
+	drivers: Vec<Driver<'a, E>>
+}
+
+impl<E> Default for DriverRegistry<'_, E> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<'a, E> DriverRegistry<'a, E> {
+	pub fn new() -> Self {
+		Self { drivers: Vec::new() }
+	}
+
+	/// Add a driver, after those already registered
+	pub fn register(&mut self, driver: Driver<'a, E>) -> &mut Self {
+		self.drivers.push(driver);
+
+		self
+	}
+
+	pub fn drivers(&self) -> &[Driver<'a, E>] {
+		&self.drivers
+	}
+
+	/// Bind the enabled nodes of `tree` in tree order, see DriverRegistry::bind_nodes
+	pub fn bind(&mut self, tree: &DeviceTree) -> BindReport<E> {
+		self.bind_nodes(&tree.nodes())
+	}
+
+	/// Hand each enabled node with 'compatible' to the driver of its most specific compatible string
+	///
+	/// The compatible strings of a node are tried in order, and for each of them the drivers in the order they
+	/// were registered. The node goes to the first match only, whether its probe succeeds or not.
+	/// Pass e.g. the order of DeviceTree::probe_order to probe suppliers before their consumers.
+	pub fn bind_nodes(&mut self, nodes: &[DeviceTreeNodeWrap]) -> BindReport<E> {
+		let mut report = BindReport { bound: Vec::new(), failed: Vec::new(), unmatched: Vec::new() };
+
+		for node in nodes {
+			let compatible: Vec<String> = {
+				let node = node.borrow();
+
+				match (node.is_enabled(), node.prop_value("compatible")) {
+					(true, Some(prop)) => match prop.stringlist() {
+						Ok(compatible) => compatible.into_iter().map(|s| s.to_string()).collect(),
+						Err(_) => Vec::new()
+					},
+					_ => continue
+				}
+			};
+
+			let found = compatible.iter().find_map(|compatible| {
+				self.drivers.iter()
+					.position(|driver| driver.compatible.contains(compatible))
+					.map(|i| (i, compatible))
+			});
+
+			let (driver, compatible) = match found {
+				Some((i, compatible)) => (&mut self.drivers[i], compatible),
+				None => {
+					report.unmatched.push(Rc::clone(node));
+					continue;
+				}
+			};
+
+			let binding = Binding { node: Rc::clone(node), driver: driver.name.clone(), compatible: compatible.clone() };
+
+			match (driver.probe)(node, compatible) {
+				Ok(()) => report.bound.push(binding),
+				Err(error) => report.failed.push((binding, error))
+			}
+		}
+
+		report
+	}
+}
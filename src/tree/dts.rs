@@ -0,0 +1,129 @@
+use alloc::{
+	string::String,
+	vec::Vec
+};
+use core::fmt::Write;
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::{
+	node::DeviceTreeNode,
+	prop::{
+		DeviceTreeProperty,
+		DeviceTreePropertyType,
+		InferOptions
+	},
+	overlay::WriteOptions
+};
+
+impl DeviceTree {
+	/// Emit the tree as devicetree source
+	pub fn to_dts(&self, options: &WriteOptions) -> Result<String, DeviceTreeError> {
+		let tree = self.resolve(options)?;
+
+		let mut dts = String::from("/dts-v1/;\n");
+
+		if options.plugin {
+			dts.push_str("/plugin/;\n");
+		}
+
+		dts.push('\n');
+
+		for entry in tree.memory_reservations() {
+			writeln!(dts, "/memreserve/ {:#018x} {:#018x};", entry.address(), entry.size()).unwrap();
+		}
+
+		if !tree.memory_reservations().is_empty() {
+			dts.push('\n');
+		}
+
+		node_fmt(&mut dts, &tree.root().borrow(), 0, &options.infer);
+
+		Ok(dts)
+	}
+}
+
+fn node_fmt(dts: &mut String, node: &DeviceTreeNode, depth: usize, infer: &InferOptions) {
+	let indent = "\t".repeat(depth);
+
+	match node.label() {
+		Some(label) => writeln!(dts, "{}{}: {} {{", indent, label, node.name()).unwrap(),
+		None => writeln!(dts, "{}{} {{", indent, node.name()).unwrap()
+	}
+
+	for (_, prop) in node.prop_iter() {
+		writeln!(dts, "{}\t{};", indent, prop_fmt(prop, infer)).unwrap();
+	}
+
+	for (_, child) in node.children_iter() {
+		dts.push('\n');
+
+		node_fmt(dts, &child.borrow(), depth + 1, infer);
+	}
+
+	writeln!(dts, "{}}};", indent).unwrap();
+}
+
+/// Format a property in source syntax, e.g. 'compatible = "riscv,plic0"'
+///
+/// Properties without a type are formatted by the type guessed with `infer`.
+pub(crate) fn prop_fmt(prop: &DeviceTreeProperty, infer: &InferOptions) -> String {
+	let value = prop.raw_value();
+
+	if value.is_empty() {
+		return String::from(prop.name());
+	}
+
+	let value_fmt = match prop.display_type(infer) {
+		DeviceTreePropertyType::String | DeviceTreePropertyType::StringList => {
+			let strings: Vec<String> = value.strip_suffix(&[0]).unwrap_or(value)
+				.split(|&b| b == 0)
+				.map(|s| format!("\"{}\"", escape(s)))
+				.collect();
+
+			strings.join(", ")
+		},
+		DeviceTreePropertyType::U32 | DeviceTreePropertyType::U64 | DeviceTreePropertyType::Cells if value.len().is_multiple_of(4) => {
+			let cells: Vec<String> = value.chunks_exact(4)
+				.map(|c| format!("{:#x}", u32::from_be_bytes(c.try_into().unwrap())))
+				.collect();
+
+			format!("<{}>", cells.join(" "))
+		},
+		_ => {
+			let bytes: Vec<String> = value.iter().map(|b| format!("{:02x}", b)).collect();
+
+			format!("[{}]", bytes.join(" "))
+		}
+	};
+
+	format!("{} = {}", prop.name(), value_fmt)
+}
+
+/// Escape a string for a DTS string literal the way dtc reads it back
+///
+/// Quotes and backslashes are escaped, control characters and bytes that are not UTF-8 become '\xNN'.
+fn escape(bytes: &[u8]) -> String {
+	let mut escaped = String::new();
+
+	for chunk in bytes.utf8_chunks() {
+		for c in chunk.valid().chars() {
+			match c {
+				'"' | '\\' => {
+					escaped.push('\\');
+					escaped.push(c);
+				},
+				c if c.is_ascii_control() => write!(escaped, "\\x{:02x}", c as u8).unwrap(),
+				c => escaped.push(c)
+			}
+		}
+
+		for b in chunk.invalid() {
+			write!(escaped, "\\x{:02x}", b).unwrap();
+		}
+	}
+
+	escaped
+}
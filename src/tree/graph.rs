@@ -0,0 +1,165 @@
+use alloc::{
+	rc::Rc,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::node::{
+	DeviceTreeNode,
+	DeviceTreeNodeWrap
+};
+
+/// A 'port' or 'port@N' node of a device, see the OF graph binding
+#[derive(Clone, Debug)]
+pub struct Port {
+	node: DeviceTreeNodeWrap,
+	id: u32
+}
+
+impl Port {
+	/// Read the port `node`, whose ID is its 'reg' or else 0
+	pub fn from_node(node: &DeviceTreeNodeWrap) -> Result<Self, DeviceTreeError> {
+		Ok(Self { node: Rc::clone(node), id: graph_reg(&node.borrow())? })
+	}
+
+	pub fn node(&self) -> &DeviceTreeNodeWrap {
+		&self.node
+	}
+
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	/// The device the port belongs to, i.e. its parent, or the parent of 'ports'
+	pub fn device(&self) -> Option<DeviceTreeNodeWrap> {
+		let parent = self.node.borrow().parent()?;
+
+		if parent.borrow().name() == "ports" {
+			return parent.borrow().parent();
+		}
+
+		Some(parent)
+	}
+
+	/// The 'endpoint' and 'endpoint@M' children, in the order of their nodes
+	pub fn endpoints(&self) -> Result<Vec<Endpoint>, DeviceTreeError> {
+		self.node.borrow()
+			.children_iter()
+			.filter(|(name, _)| is_graph_node(name, "endpoint"))
+			.map(|(_, node)| Endpoint::from_node(node))
+			.collect()
+	}
+}
+
+/// An 'endpoint' or 'endpoint@M' node of a port
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+	node: DeviceTreeNodeWrap,
+	id: u32
+}
+
+impl Endpoint {
+	/// Read the endpoint `node`, whose ID is its 'reg' or else 0
+	pub fn from_node(node: &DeviceTreeNodeWrap) -> Result<Self, DeviceTreeError> {
+		Ok(Self { node: Rc::clone(node), id: graph_reg(&node.borrow())? })
+	}
+
+	pub fn node(&self) -> &DeviceTreeNodeWrap {
+		&self.node
+	}
+
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	/// The port of the endpoint, i.e. its parent
+	pub fn port(&self) -> Result<Option<Port>, DeviceTreeError> {
+		self.node.borrow().parent().map(|port| Port::from_node(&port)).transpose()
+	}
+
+	/// The endpoint that 'remote-endpoint' refers to, None without 'remote-endpoint'
+	///
+	/// Returns DeviceTreeError::BadPhandle if the phandle has no node.
+	pub fn remote(&self, tree: &DeviceTree) -> Result<Option<Endpoint>, DeviceTreeError> {
+		let phandle = match self.node.borrow().prop_value("remote-endpoint") {
+			Some(prop) => prop.phandle()?,
+			None => return Ok(None)
+		};
+
+		let remote = tree.find_phandle(phandle).ok_or(DeviceTreeError::BadPhandle)?;
+
+		Endpoint::from_node(&remote).map(Some)
+	}
+
+	/// The port of the remote endpoint
+	pub fn remote_port(&self, tree: &DeviceTree) -> Result<Option<Port>, DeviceTreeError> {
+		match self.remote(tree)? {
+			Some(remote) => remote.port(),
+			None => Ok(None)
+		}
+	}
+
+	/// The device of the remote endpoint
+	pub fn remote_device(&self, tree: &DeviceTree) -> Result<Option<DeviceTreeNodeWrap>, DeviceTreeError> {
+		Ok(self.remote_port(tree)?.and_then(|port| port.device()))
+	}
+
+	/// Whether the remote endpoint refers back to this one
+	pub fn is_mutual(&self, tree: &DeviceTree) -> Result<bool, DeviceTreeError> {
+		let remote = match self.remote(tree)? {
+			Some(remote) => remote,
+			None => return Ok(false)
+		};
+
+		Ok(remote.remote(tree)?.is_some_and(|back| Rc::ptr_eq(&back.node, &self.node)))
+	}
+}
+
+impl DeviceTreeNode {
+	/// The ports of the device: its 'port' and 'port@N' children, or else those of its 'ports' child
+	pub fn ports(&self) -> Result<Vec<Port>, DeviceTreeError> {
+		let ports = |node: &DeviceTreeNode| -> Result<Vec<Port>, DeviceTreeError> {
+			node.children_iter()
+				.filter(|(name, _)| is_graph_node(name, "port"))
+				.map(|(_, node)| Port::from_node(node))
+				.collect()
+		};
+
+		match self.find_child("ports") {
+			Some(container) => ports(&container.borrow()),
+			None => ports(self)
+		}
+	}
+
+	/// The port whose ID is `id`
+	pub fn port(&self, id: u32) -> Result<Option<Port>, DeviceTreeError> {
+		Ok(self.ports()?.into_iter().find(|port| port.id() == id))
+	}
+
+	/// The endpoints of all ports of the device, in order
+	pub fn endpoints(&self) -> Result<Vec<Endpoint>, DeviceTreeError> {
+		let mut endpoints = Vec::new();
+
+		for port in self.ports()? {
+			endpoints.extend(port.endpoints()?);
+		}
+
+		Ok(endpoints)
+	}
+}
+
+/// Whether `name` is `base` with or without a unit address, e.g. 'port' or 'port@1'
+pub(crate) fn is_graph_node(name: &str, base: &str) -> bool {
+	name.split('@').next() == Some(base)
+}
+
+/// The 'reg' of a port or an endpoint, a single cell which defaults to 0
+fn graph_reg(node: &DeviceTreeNode) -> Result<u32, DeviceTreeError> {
+	match node.prop_value("reg") {
+		Some(reg) => reg.u32(),
+		None => Ok(0)
+	}
+}
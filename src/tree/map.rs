@@ -0,0 +1,121 @@
+use alloc::{
+	string::String,
+	vec::Vec,
+	collections::BTreeMap
+};
+
+/// Map keyed by name which keeps its entries in insertion order
+///
+/// Lookup by name goes through a BTreeMap of positions, so it takes O(log n).
+/// Replacing the value of an existing name keeps its position.
+#[derive(Clone, PartialEq, Debug)]
+pub struct OrderedMap<V> {
+	entries: Vec<(String, V)>,
+	index: BTreeMap<String, usize>
+}
+
+impl<V> Default for OrderedMap<V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<V> OrderedMap<V> {
+	pub fn new() -> Self {
+		Self {
+			entries: Vec::new(),
+			index: BTreeMap::new()
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn get(&self, name: &str) -> Option<&V> {
+		self.index.get(name).map(|&i| &self.entries[i].1)
+	}
+
+	pub fn get_mut(&mut self, name: &str) -> Option<&mut V> {
+		match self.index.get(name) {
+			Some(&i) => Some(&mut self.entries[i].1),
+			None => None
+		}
+	}
+
+	pub fn contains_key(&self, name: &str) -> bool {
+		self.index.contains_key(name)
+	}
+
+	/// Insert a value at the end of the map:
+	///
+	/// If the map did have this name present, the value is updated in place, and the old value is returned.
+	pub fn insert(&mut self, name: String, value: V) -> Option<V> {
+		match self.index.get(&name) {
+			Some(&i) => Some(core::mem::replace(&mut self.entries[i].1, value)),
+			None => {
+				self.index.insert(name.clone(), self.entries.len());
+				self.entries.push((name, value));
+
+				None
+			}
+		}
+	}
+
+	/// Remove an entry, keeping the order of the remaining ones
+	pub fn remove_entry(&mut self, name: &str) -> Option<(String, V)> {
+		let i = self.index.remove(name)?;
+
+		for position in self.index.values_mut() {
+			if *position > i {
+				*position -= 1;
+			}
+		}
+
+		Some(self.entries.remove(i))
+	}
+
+	/// Iterate over the entries in insertion order
+	pub fn iter(&self) -> Iter<'_, V> {
+		Iter(self.entries.iter())
+	}
+}
+
+impl<V> FromIterator<(String, V)> for OrderedMap<V> {
+	fn from_iter<I: IntoIterator<Item = (String, V)>>(iter: I) -> Self {
+		let mut map = Self::new();
+
+		for (name, value) in iter {
+			map.insert(name, value);
+		}
+
+		map
+	}
+}
+
+/// Iterator over the entries of an OrderedMap in insertion order
+pub struct Iter<'a, V>(core::slice::Iter<'a, (String, V)>);
+
+impl<'a, V> Iterator for Iter<'a, V> {
+	type Item = (&'a String, &'a V);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.0.next().map(|(name, value)| (name, value))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.0.size_hint()
+	}
+}
+
+impl<'a, V> DoubleEndedIterator for Iter<'a, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		self.0.next_back().map(|(name, value)| (name, value))
+	}
+}
+
+impl<'a, V> ExactSizeIterator for Iter<'a, V> {}
@@ -0,0 +1,217 @@
+use alloc::{
+	string::String,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::node::DeviceTreeNode;
+
+/// Range of physical memory, whose end saturates at u64::MAX
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MemoryRange {
+	start: u64,
+	end: u64
+}
+
+impl MemoryRange {
+	pub fn new(start: u64, size: u64) -> Self {
+		Self { start, end: start.saturating_add(size) }
+	}
+
+	pub fn start(&self) -> u64 {
+		self.start
+	}
+
+	/// First address after the range
+	pub fn end(&self) -> u64 {
+		self.end
+	}
+
+	pub fn size(&self) -> u64 {
+		self.end - self.start
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.start == self.end
+	}
+
+	pub fn contains(&self, address: u64) -> bool {
+		self.start <= address && address < self.end
+	}
+
+	pub fn overlaps(&self, other: &MemoryRange) -> bool {
+		self.start < other.end && other.start < self.end
+	}
+}
+
+/// Why a range of memory is not usable RAM
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ReservedReason {
+	/// Entry of the memory reservation block
+	MemoryReservation,
+	/// Child of '/reserved-memory' with 'no-map', given by its path
+	NoMap(String),
+	/// Child of '/reserved-memory' without 'no-map', which stays usable RAM in a MemoryMap of the tree
+	Reserved(String)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReservedRange {
+	range: MemoryRange,
+	reason: ReservedReason
+}
+
+impl ReservedRange {
+	pub fn new(range: MemoryRange, reason: ReservedReason) -> Self {
+		Self { range, reason }
+	}
+
+	pub fn range(&self) -> &MemoryRange {
+		&self.range
+	}
+
+	pub fn reason(&self) -> &ReservedReason {
+		&self.reason
+	}
+}
+
+/// Physical memory of the system, split into usable RAM and reserved ranges
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MemoryMap {
+	usable: Vec<MemoryRange>,
+	reserved: Vec<ReservedRange>
+}
+
+impl MemoryMap {
+	/// Build the map from the RAM and the reserved ranges, both in any order and possibly overlapping
+	///
+	/// The usable ranges are the RAM minus every reserved range but those of ReservedReason::Reserved, sorted and
+	/// with touching ranges merged. Reserved ranges are kept as given, sorted by address, whether or not they lie in
+	/// RAM.
+	pub fn new(ram: &[MemoryRange], reserved: Vec<ReservedRange>) -> Self {
+		let holes = reserved.iter()
+			.filter(|reserved| !matches!(reserved.reason, ReservedReason::Reserved(_)))
+			.map(|reserved| reserved.range);
+
+		let usable = subtract_all(&coalesce(ram), holes);
+
+		let mut reserved = reserved;
+
+		reserved.retain(|reserved| !reserved.range.is_empty());
+		reserved.sort_by_key(|reserved| reserved.range);
+
+		Self { usable, reserved }
+	}
+
+	pub fn usable(&self) -> &[MemoryRange] {
+		&self.usable
+	}
+
+	pub fn reserved(&self) -> &[ReservedRange] {
+		&self.reserved
+	}
+
+	/// Total size of usable RAM in bytes
+	pub fn usable_size(&self) -> u64 {
+		self.usable.iter().map(|range| range.size()).sum()
+	}
+
+	pub fn is_usable(&self, address: u64) -> bool {
+		self.usable.iter().any(|range| range.contains(address))
+	}
+}
+
+impl DeviceTree {
+	/// The ranges of 'reg' of every enabled node with 'device_type = "memory"', sorted and merged
+	pub fn memory(&self) -> Result<Vec<MemoryRange>, DeviceTreeError> {
+		let mut ram = Vec::new();
+
+		for node in self.nodes() {
+			let node = node.borrow();
+
+			if is_memory(&node) && node.is_enabled() {
+				ram.extend(node.reg()?.into_iter().map(|(start, size)| MemoryRange::new(start, size)));
+			}
+		}
+
+		Ok(coalesce(&ram))
+	}
+
+	/// The memory map: RAM from the memory nodes, minus the memory reservation block and the 'no-map' regions of
+	/// '/reserved-memory', static or allocated
+	///
+	/// The other regions of '/reserved-memory' are reserved ranges too, with ReservedReason::Reserved, but stay usable.
+	pub fn memory_map(&self) -> Result<MemoryMap, DeviceTreeError> {
+		let mut reserved = self.header_reservations();
+
+		for region in self.reserved_memory()?.regions() {
+			let reason = match region.is_no_map() {
+				true => ReservedReason::NoMap(region.path()),
+				false => ReservedReason::Reserved(region.path())
+			};
+
+			reserved.extend(region.ranges().iter().map(|&range| ReservedRange::new(range, reason.clone())));
+		}
+
+		Ok(MemoryMap::new(&self.memory()?, reserved))
+	}
+
+	/// The entries of the memory reservation block as reserved ranges
+	pub(crate) fn header_reservations(&self) -> Vec<ReservedRange> {
+		self.memory_reservations()
+			.iter()
+			.map(|entry| ReservedRange::new(MemoryRange::new(entry.address(), entry.size()), ReservedReason::MemoryReservation))
+			.collect()
+	}
+}
+
+pub(crate) fn is_memory(node: &DeviceTreeNode) -> bool {
+	node.prop_value("device_type").and_then(|prop| prop.string().ok()) == Some("memory")
+}
+
+/// Sort the ranges and merge those that overlap or touch, dropping empty ones
+pub(crate) fn coalesce(ranges: &[MemoryRange]) -> Vec<MemoryRange> {
+	let mut ranges: Vec<MemoryRange> = ranges.iter().filter(|range| !range.is_empty()).copied().collect();
+
+	ranges.sort();
+
+	let mut merged: Vec<MemoryRange> = Vec::with_capacity(ranges.len());
+
+	for range in ranges {
+		match merged.last_mut() {
+			Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+			_ => merged.push(range)
+		}
+	}
+
+	merged
+}
+
+/// The parts of the sorted `ranges` outside of every hole, still sorted
+pub(crate) fn subtract_all(ranges: &[MemoryRange], holes: impl Iterator<Item = MemoryRange>) -> Vec<MemoryRange> {
+	let mut ranges = ranges.to_vec();
+
+	for hole in holes {
+		ranges = ranges.into_iter().flat_map(|range| subtract(range, &hole)).collect();
+	}
+
+	ranges
+}
+
+/// The parts of `range` outside of `hole`, at most two
+fn subtract(range: MemoryRange, hole: &MemoryRange) -> Vec<MemoryRange> {
+	if !range.overlaps(hole) {
+		return Vec::from([range]);
+	}
+
+	[
+		MemoryRange { start: range.start, end: hole.start },
+		MemoryRange { start: hole.end, end: range.end }
+	]
+		.into_iter()
+		.filter(|part| part.start < part.end)
+		.collect()
+}
@@ -1,6 +1,24 @@
 pub mod node;
 pub mod prop;
+pub mod overlay;
+pub mod map;
+pub mod arena;
+pub mod schema;
+pub mod check;
+pub mod name;
+pub mod cpu;
+pub mod topology;
+pub mod memory;
+pub mod reserved;
+pub mod numa;
+pub mod pci;
+pub mod graph;
+pub mod deps;
+#[cfg(feature = "driver")]
+pub mod driver;
 
+#[allow(clippy::module_inception)]
 mod tree;
+mod dts;
 
 pub const CPU_MAX_NUM: u32 = u32::MAX;
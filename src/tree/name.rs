@@ -0,0 +1,78 @@
+use alloc::{
+	string::{
+		String,
+		ToString
+	},
+	vec::Vec
+};
+
+use crate::{
+	DeviceTreeError,
+	fdt::name::split_node_name
+};
+
+pub use crate::fdt::name::{
+	NAME_MAX_LEN,
+	validate_prop_name
+};
+pub(crate) use crate::fdt::name::{
+	NODE_NAME_CHARS,
+	PROP_NAME_CHARS
+};
+
+/// Name of a node split into its base name and unit address, e.g. 'memory' and '80000000' for 'memory@80000000'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NodeName {
+	base: String,
+	unit_address: Option<String>
+}
+
+impl NodeName {
+	/// Parse and validate a node name
+	///
+	/// The base name has 1 to 31 characters and the unit address at least one, all of them from NODE_NAME_CHARS.
+	/// The root node, named '/', has an empty base name.
+	pub fn parse(name: &str) -> Result<Self, DeviceTreeError> {
+		if name == "/" {
+			return Ok(Self { base: String::new(), unit_address: None });
+		}
+
+		let (base, unit_address) = split_node_name(name)?;
+
+		Ok(Self { base: base.to_string(), unit_address: unit_address.map(|s| s.to_string()) })
+	}
+
+	pub fn base(&self) -> &str {
+		&self.base
+	}
+
+	pub fn unit_address(&self) -> Option<&str> {
+		self.unit_address.as_deref()
+	}
+
+	pub fn is_root(&self) -> bool {
+		self.base.is_empty()
+	}
+
+	/// The unit address as comma-separated hex fields, e.g. [1, 0x8000] for 'pci@1,8000'
+	///
+	/// Returns an empty list if there is no unit address.
+	pub fn unit_address_fields(&self) -> Result<Vec<u64>, DeviceTreeError> {
+		match &self.unit_address {
+			Some(unit_address) => unit_address.split(',')
+				.map(|field| u64::from_str_radix(field, 16).map_err(|_| DeviceTreeError::BadNodeName))
+				.collect(),
+			None => Ok(Vec::new())
+		}
+	}
+}
+
+impl core::fmt::Display for NodeName {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match (&self.unit_address, self.is_root()) {
+			(_, true) => write!(f, "/"),
+			(Some(unit_address), _) => write!(f, "{}@{}", self.base, unit_address),
+			(None, _) => write!(f, "{}", self.base)
+		}
+	}
+}
@@ -1,25 +1,26 @@
 use alloc::{
 	string::{String, ToString},
 	vec::Vec,
-	rc::Rc,
-	collections::{
-		BTreeMap,
-		btree_map::Iter
-	}
+	rc::{Rc, Weak}
 };
 use core::cell::RefCell;
-use log::{
-    info,
-    debug,
-    error
-};
-
-use crate::{DeviceTreeError, utils};
-
-use super::prop::{
-	DeviceTreeProperty,
-	NumCells,
-	Pairs
+use log::debug;
+
+use crate::DeviceTreeError;
+
+use super::{
+	prop::{
+		DeviceTreeProperty,
+		NumCells
+	},
+	map::{
+		OrderedMap,
+		Iter
+	},
+	name::{
+		NodeName,
+		validate_prop_name
+	}
 };
 
 const INDENT_SIZE: usize = 4;
@@ -27,13 +28,15 @@ const INDENT_SIZE: usize = 4;
 static mut INDENT: usize = 0;
 
 /// Node of devicetree 
-#[derive(Default, PartialEq, Debug)]
+#[derive(Default, Debug)]
 pub struct DeviceTreeNode {
 	name: String,
-	parent: Option<DeviceTreeNodeWrap>,
-	children: BTreeMap<String, DeviceTreeNodeWrap>,
-	/// Properties consist of a name and a value. Keys of properties are their names.
-	properties: BTreeMap<String, DeviceTreeProperty>, 
+	/// Weak link back to the parent, so that parent and children do not keep each other alive
+	parent: Option<Weak<RefCell<DeviceTreeNode>>>,
+	/// Children in the order they were added. Keys of children are their names.
+	children: OrderedMap<DeviceTreeNodeWrap>,
+	/// Properties consist of a name and a value. Keys of properties are their names, kept in the order they were added.
+	properties: OrderedMap<DeviceTreeProperty>, 
 	/// Required for all nodes that have children. Default: #address-cells=2 and #size-cells=1
 	num_cells: NumCells, 
 	label: Option<String>
@@ -44,8 +47,8 @@ impl DeviceTreeNode {
 		Self {
 			name: String::new(),
 			parent: None,
-			children: BTreeMap::new(),
-			properties: BTreeMap::new(),
+			children: OrderedMap::new(),
+			properties: OrderedMap::new(),
 			num_cells: NumCells::new(),
 			label: None
 		}
@@ -60,6 +63,13 @@ impl DeviceTreeNode {
 		&self.name
 	}
 
+	/// The name split into base name and unit address
+	/// 
+	/// Fails for a name that is not valid, which only nodes read from a blob can have.
+	pub fn node_name(&self) -> Result<NodeName, DeviceTreeError> {
+		NodeName::parse(&self.name)
+	}
+
 	pub fn set_name(&mut self, name: &str) {
 		self.name = name.to_string();
 	}
@@ -72,19 +82,22 @@ impl DeviceTreeNode {
         self.label = Some(label.to_string());
     }
 
-    pub fn parent(&self) -> Option<&DeviceTreeNodeWrap> {
-        self.parent.as_ref()
+    /// The parent of the node
+    /// 
+    /// Returns None for the root, and for a node whose parent has already been dropped.
+    pub fn parent(&self) -> Option<DeviceTreeNodeWrap> {
+        self.parent.as_ref()?.upgrade()
     }
 
 	pub fn set_parent(&mut self, parent: DeviceTreeNodeWrap) {
-		self.parent = Some(Rc::clone(&parent));
+		self.parent = Some(Rc::downgrade(&parent));
 	}
 
 	pub fn has_parent(&self) -> bool {
-		self.parent.is_some()
+		self.parent().is_some()
 	}
 
-	pub fn children_iter(&self) -> Iter<'_, String, DeviceTreeNodeWrap> {
+	pub fn children_iter(&self) -> Iter<'_, DeviceTreeNodeWrap> {
 		self.children.iter()
 	}
 
@@ -104,7 +117,16 @@ impl DeviceTreeNode {
 		self.properties.get(name)
 	}
 
-	pub fn prop_iter(&self) -> Iter<'_, String, DeviceTreeProperty> {
+	pub fn prop_value_mut(&mut self, name: &str) -> Option<&mut DeviceTreeProperty>{
+		self.properties.get_mut(name)
+	}
+
+	/// Value of the 'phandle' property, if the node has one
+	pub fn phandle(&self) -> Option<u32> {
+		self.prop_value("phandle")?.phandle().ok()
+	}
+
+	pub fn prop_iter(&self) -> Iter<'_, DeviceTreeProperty> {
 		self.properties.iter()
 	}
 
@@ -117,7 +139,16 @@ impl DeviceTreeNode {
 	/// If the map did not have this key present, None is returned. 
 	/// 
 	/// If the map did have this key present, the value is updated, and the old value is returned.
-	pub fn add_prop(&mut self, mut prop: DeviceTreeProperty) -> Option<DeviceTreeProperty> {
+	/// 
+	/// Returns DeviceTreeError::BadPropName if the name is not valid, see validate_prop_name.
+	pub fn add_prop(&mut self, prop: DeviceTreeProperty) -> Result<Option<DeviceTreeProperty>, DeviceTreeError> {
+		validate_prop_name(prop.name())?;
+
+		Ok(self.insert_prop(prop))
+	}
+
+	/// Add a property without validating its name, for properties read from a blob
+	pub(crate) fn insert_prop(&mut self, mut prop: DeviceTreeProperty) -> Option<DeviceTreeProperty> {
 		prop.update_type();
 
 		debug!("Adding property {{ {} {} }} to node '{}'.", prop.name(), prop, self.name());
@@ -132,6 +163,78 @@ impl DeviceTreeNode {
 		self.properties.remove_entry(name)
 	}
 
+	/// Full path of the node from the root, e.g. '/cpus/cpu@0'
+	pub fn path(&self) -> String {
+		match self.parent() {
+			Some(parent) => {
+				let parent_path = parent.borrow().path();
+
+				if parent_path == "/" {
+					format!("/{}", self.name)
+				} else {
+					format!("{}/{}", parent_path, self.name)
+				}
+			},
+			None => String::from("/")
+		}
+	}
+
+	/// Whether 'status' is absent, "okay" or "ok"
+	pub fn is_enabled(&self) -> bool {
+		match self.prop_value("status") {
+			Some(prop) => matches!(prop.string(), Ok("okay") | Ok("ok")),
+			None => true
+		}
+	}
+
+	/// Address and size pairs of 'reg', read with the '#address-cells' and '#size-cells' of the parent
+	///
+	/// The parent defaults to 2 address cells and 1 size cell, and a node without 'reg' has no pairs.
+	pub fn reg(&self) -> Result<Vec<(u64, u64)>, DeviceTreeError> {
+		let reg = match self.prop_value("reg") {
+			Some(reg) => reg,
+			None => return Ok(Vec::new())
+		};
+
+		let (address_cells, size_cells) = match self.parent() {
+			Some(parent) => {
+				let parent = parent.borrow();
+
+				let cells = |name: &str| parent.prop_value(name).map(|prop| prop.u32()).transpose();
+
+				(cells("#address-cells")?.unwrap_or(2), cells("#size-cells")?.unwrap_or(1))
+			},
+			None => (2, 1)
+		};
+
+		Ok(reg.encoded_array(&[address_cells, size_cells])?
+			.into_iter()
+			.map(|entry| (entry[0], entry[1]))
+			.collect())
+	}
+
+	/// Copy the node together with its whole subtree into new nodes
+	/// 
+	/// The copy has no parent.
+	pub fn deep_copy(&self) -> DeviceTreeNodeWrap {
+		let copy = Self::new_wrap();
+
+		{
+			let mut node = copy.borrow_mut();
+
+			node.name = self.name.clone();
+			node.properties = self.properties.clone();
+			node.num_cells = self.num_cells;
+			node.label = self.label.clone();
+		}
+
+		for (name, child) in self.children_iter() {
+			insert_child(&copy, name, child.borrow().deep_copy());
+		}
+
+		copy
+	}
+
     pub fn set_numcells(&mut self, addr_cells: u32, size_cells: u32) {
         self.num_cells.set(addr_cells, size_cells);
     }
@@ -145,17 +248,30 @@ impl DeviceTreeNode {
 	}
 }
 
+impl PartialEq for DeviceTreeNode {
+	/// Two nodes are equal if their contents and subtrees are equal, regardless of their parents
+	fn eq(&self, other: &Self) -> bool {
+		self.name == other.name
+			&& self.children == other.children
+			&& self.properties == other.properties
+			&& self.num_cells == other.num_cells
+			&& self.label == other.label
+	}
+}
+
 impl core::fmt::Display for DeviceTreeNode {
 	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
 		unsafe {
-			writeln!(f, "")?;
+			writeln!(f)?;
 			
-			writeln!(f, "{:indent$}{} {{", "", self.name(), indent = INDENT)?;
+			let indent = INDENT;
+
+			writeln!(f, "{:indent$}{} {{", "", self.name(), indent = indent)?;
 
 			INDENT += INDENT_SIZE;
 
 			for (_, prop) in self.prop_iter() {
-				writeln!(f, "{:indent$}{};", "", prop, indent = INDENT)?;
+				writeln!(f, "{:indent$}{};", "", prop, indent = indent + INDENT_SIZE)?;
 			} 
 
 			for (_, child) in self.children_iter() {
@@ -164,7 +280,7 @@ impl core::fmt::Display for DeviceTreeNode {
 
 			INDENT -= INDENT_SIZE;
 
-			write!(f, "{:indent$}}};", "", indent = INDENT)
+			write!(f, "{:indent$}}};", "", indent = indent)
 		}
 	}
 }
@@ -173,7 +289,7 @@ impl core::fmt::Display for DeviceTreeNode {
 pub type DeviceTreeNodeWrap = Rc<RefCell<DeviceTreeNode>>;
 
 pub trait AddChild {
-	fn add_child(&self, name: &str, child: DeviceTreeNodeWrap) -> Option<DeviceTreeNodeWrap>;
+	fn add_child(&self, name: &str, child: DeviceTreeNodeWrap) -> Result<Option<DeviceTreeNodeWrap>, DeviceTreeError>;
 }
 
 impl AddChild for DeviceTreeNodeWrap {
@@ -182,12 +298,56 @@ impl AddChild for DeviceTreeNodeWrap {
 	/// If the current node did not have the child present, None is returned.
 	/// 
 	/// If the current node did have the child present, the child is updated, and the old child is returned.
-	fn add_child(&self, name: &str, child: DeviceTreeNodeWrap) -> Option<DeviceTreeNodeWrap> {
-		debug!("Adding subnode '{}' to node '{}'.", name, self.borrow().name());
+	/// 
+	/// Returns DeviceTreeError::BadNodeName if the name is not valid, see NodeName::parse.
+	fn add_child(&self, name: &str, child: DeviceTreeNodeWrap) -> Result<Option<DeviceTreeNodeWrap>, DeviceTreeError> {
+		if NodeName::parse(name)?.is_root() {
+			return Err(DeviceTreeError::BadNodeName);
+		}
 
-		child.borrow_mut().set_name(name);
-		child.borrow_mut().set_parent(Rc::clone(&self));
+		Ok(insert_child(self, name, child))
+	}
+}
+
+/// Add a child without validating its name, for nodes read from a blob
+pub(crate) fn insert_child(parent: &DeviceTreeNodeWrap, name: &str, child: DeviceTreeNodeWrap) -> Option<DeviceTreeNodeWrap> {
+	debug!("Adding subnode '{}' to node '{}'.", name, parent.borrow().name());
+
+	child.borrow_mut().set_name(name);
+	child.borrow_mut().set_parent(Rc::clone(parent));
 
-		self.borrow_mut().children.insert(name.to_string(), Rc::clone(&child))
+	parent.borrow_mut().children.insert(name.to_string(), Rc::clone(&child))
+}
+
+/// How the interrupt parent of a node is given, see interrupt_parent
+pub(crate) enum InterruptParent {
+	/// The closest ancestor that is an interrupt controller or nexus
+	Provider(DeviceTreeNodeWrap),
+	/// The 'interrupt-parent' of the node or of its closest ancestor having one, which may not be a valid phandle
+	Phandle(Result<u32, DeviceTreeError>)
+}
+
+/// Where the interrupts of `node` go, like dtc finds it, or None if nothing gives it
+///
+/// It is given by the 'interrupt-parent' of the node, or else by the closest ancestor that is an interrupt
+/// provider or has 'interrupt-parent'.
+pub(crate) fn interrupt_parent(node: &DeviceTreeNode) -> Option<InterruptParent> {
+	let mut interrupt_parent = node.prop_value("interrupt-parent").map(|prop| prop.phandle());
+	let mut ancestor = node.parent();
+
+	while let (None, Some(current)) = (&interrupt_parent, ancestor) {
+		if is_interrupt_provider(&current.borrow()) {
+			return Some(InterruptParent::Provider(current));
+		}
+
+		interrupt_parent = current.borrow().prop_value("interrupt-parent").map(|prop| prop.phandle());
+		ancestor = current.borrow().parent();
 	}
+
+	interrupt_parent.map(InterruptParent::Phandle)
+}
+
+/// Whether `node` is an interrupt controller or nexus, which children use as interrupt parent by default
+fn is_interrupt_provider(node: &DeviceTreeNode) -> bool {
+	node.prop_exists("interrupt-controller") || node.prop_exists("interrupt-map")
 }
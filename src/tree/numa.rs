@@ -0,0 +1,190 @@
+use alloc::{
+	collections::BTreeMap,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::{
+	node::DeviceTreeNode,
+	cpu::Cpu,
+	memory::{
+		MemoryRange,
+		coalesce,
+		is_memory
+	}
+};
+
+/// Distance of a NUMA node to itself
+pub const LOCAL_DISTANCE: u32 = 10;
+
+/// Distance between two NUMA nodes that the distance map does not give
+pub const REMOTE_DISTANCE: u32 = 20;
+
+/// Distances between all NUMA nodes of the system
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DistanceMap {
+	/// Node IDs in increasing order
+	nodes: Vec<u32>,
+	/// Row-major table of nodes.len() * nodes.len() distances
+	distances: Vec<u32>
+}
+
+impl DistanceMap {
+	/// Table of `nodes` with the given distances, the others being LOCAL_DISTANCE or REMOTE_DISTANCE
+	///
+	/// A distance given in one direction only holds for both, as in the Devicetree NUMA binding.
+	/// Returns DeviceTreeError::BadPropValue if a node is not at LOCAL_DISTANCE of itself or is not farther from
+	/// another node.
+	pub fn new(nodes: &[u32], entries: &[(u32, u32, u32)]) -> Result<Self, DeviceTreeError> {
+		let mut nodes: Vec<u32> = nodes.iter()
+			.copied()
+			.chain(entries.iter().flat_map(|&(from, to, _)| [from, to]))
+			.collect();
+
+		nodes.sort();
+		nodes.dedup();
+
+		let n = nodes.len();
+		let mut distances = Vec::with_capacity(n * n);
+
+		for i in 0..n {
+			distances.extend((0..n).map(|j| if i == j { LOCAL_DISTANCE } else { REMOTE_DISTANCE }));
+		}
+
+		let mut map = Self { nodes, distances };
+
+		// Mirror every entry first, so that a distance given in both directions keeps its own value
+		for &(from, to, distance) in entries {
+			let (i, j) = (map.index(from).unwrap(), map.index(to).unwrap());
+
+			if (i == j && distance != LOCAL_DISTANCE) || (i != j && distance <= LOCAL_DISTANCE) {
+				return Err(DeviceTreeError::BadPropValue);
+			}
+
+			map.distances[j * n + i] = distance;
+		}
+
+		for &(from, to, distance) in entries {
+			let (i, j) = (map.index(from).unwrap(), map.index(to).unwrap());
+
+			map.distances[i * n + j] = distance;
+		}
+
+		Ok(map)
+	}
+
+	/// Node IDs in increasing order
+	pub fn nodes(&self) -> &[u32] {
+		&self.nodes
+	}
+
+	pub fn distance(&self, from: u32, to: u32) -> Option<u32> {
+		Some(self.distances[self.index(from)? * self.nodes.len() + self.index(to)?])
+	}
+
+	fn index(&self, node: u32) -> Option<usize> {
+		self.nodes.binary_search(&node).ok()
+	}
+}
+
+/// The CPUs and memory of a NUMA node
+#[derive(Clone, Debug)]
+pub struct NumaNode {
+	id: u32,
+	cpus: Vec<Cpu>,
+	memory: Vec<MemoryRange>
+}
+
+impl NumaNode {
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	pub fn cpus(&self) -> &[Cpu] {
+		&self.cpus
+	}
+
+	/// RAM of the node, sorted and merged
+	pub fn memory(&self) -> &[MemoryRange] {
+		&self.memory
+	}
+}
+
+impl Cpu {
+	/// Value of 'numa-node-id' of the CPU node
+	pub fn numa_node_id(&self) -> Result<Option<u32>, DeviceTreeError> {
+		numa_node_id(&self.node().borrow())
+	}
+}
+
+impl DeviceTree {
+	/// The distances between NUMA nodes, from the 'distance-matrix' of '/distance-map'
+	///
+	/// The table covers every node of the matrix and every 'numa-node-id' of a CPU or memory node.
+	/// Without a '/distance-map' compatible with "numa-distance-map-v1" all distances are the default ones.
+	pub fn numa_distance_map(&self) -> Result<DistanceMap, DeviceTreeError> {
+		let mut entries = Vec::new();
+
+		if let Some(distance_map) = self.find_node("/distance-map") {
+			let distance_map = distance_map.borrow();
+
+			let compatible = match distance_map.prop_value("compatible") {
+				Some(prop) => prop.stringlist()?.contains(&"numa-distance-map-v1"),
+				None => false
+			};
+
+			if let (true, Some(matrix)) = (compatible, distance_map.prop_value("distance-matrix")) {
+				entries = matrix.encoded_array(&[1, 1, 1])?
+					.into_iter()
+					.map(|entry| (entry[0] as u32, entry[1] as u32, entry[2] as u32))
+					.collect();
+			}
+		}
+
+		let nodes: Vec<u32> = self.numa_nodes()?.iter().map(|node| node.id()).collect();
+
+		DistanceMap::new(&nodes, &entries)
+	}
+
+	/// The enabled CPUs and memory nodes grouped by 'numa-node-id', in increasing order of ID
+	///
+	/// CPUs and memory nodes without 'numa-node-id' belong to node 0, so a tree without NUMA has a single node.
+	pub fn numa_nodes(&self) -> Result<Vec<NumaNode>, DeviceTreeError> {
+		let mut nodes: BTreeMap<u32, NumaNode> = BTreeMap::new();
+
+		for cpu in self.cpus()?.into_iter().filter(|cpu| cpu.is_enabled()) {
+			numa_node(&mut nodes, cpu.numa_node_id()?).cpus.push(cpu);
+		}
+
+		for node in self.nodes() {
+			let node = node.borrow();
+
+			if is_memory(&node) && node.is_enabled() {
+				numa_node(&mut nodes, numa_node_id(&node)?).memory.extend(
+					node.reg()?.into_iter().map(|(start, size)| MemoryRange::new(start, size))
+				);
+			}
+		}
+
+		Ok(nodes.into_values()
+			.map(|mut node| {
+				node.memory = coalesce(&node.memory);
+				node
+			})
+			.collect())
+	}
+}
+
+/// The node of `id` in `nodes`, added if missing, where no ID means node 0
+fn numa_node(nodes: &mut BTreeMap<u32, NumaNode>, id: Option<u32>) -> &mut NumaNode {
+	let id = id.unwrap_or(0);
+
+	nodes.entry(id).or_insert_with(|| NumaNode { id, cpus: Vec::new(), memory: Vec::new() })
+}
+
+fn numa_node_id(node: &DeviceTreeNode) -> Result<Option<u32>, DeviceTreeError> {
+	node.prop_value("numa-node-id").map(|prop| prop.u32()).transpose()
+}
@@ -0,0 +1,219 @@
+use alloc::{
+	string::{String, ToString},
+	vec::Vec,
+	rc::Rc,
+	collections::BTreeMap
+};
+use log::debug;
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::{
+	node::{
+		DeviceTreeNode,
+		DeviceTreeNodeWrap,
+		insert_child
+	},
+	prop::{
+		DeviceTreeProperty,
+		DeviceTreePropertyType,
+		InferOptions
+	}
+};
+
+/// Phandle cell of a reference that could not be resolved inside an overlay
+const UNRESOLVED_PHANDLE: u32 = 0xffffffff;
+
+/// Options shared by the DTB writer and the DTS emitter
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct WriteOptions {
+	/// Generate the '__symbols__' node from the node labels, like 'dtc -@'
+	pub symbols: bool,
+	/// Compile the tree as a '/plugin/;' overlay:
+	/// unresolved references go to '__fixups__' and resolved ones to '__local_fixups__'
+	pub plugin: bool,
+	/// Guesses for the type of properties without one when emitting source
+	pub infer: InferOptions
+}
+
+impl WriteOptions {
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+/// A reference cell of a property, collected before the tree is patched
+struct RefSite {
+	node: DeviceTreeNodeWrap,
+	prop: String,
+	offset: usize,
+	label: String
+}
+
+impl DeviceTree {
+	/// Prepare the tree for writing
+	///
+	/// Returns a copy of the tree with all phandle references filled in,
+	/// plus the '__symbols__', '__fixups__' and '__local_fixups__' nodes requested by the options.
+	pub fn resolve(&self, options: &WriteOptions) -> Result<DeviceTree, DeviceTreeError> {
+		let tree = self.clone();
+
+		let nodes = tree.nodes();
+
+		let mut labels: BTreeMap<String, DeviceTreeNodeWrap> = BTreeMap::new();
+
+		for node in nodes.iter() {
+			if let Some(label) = node.borrow().label() {
+				if labels.insert(label.to_string(), Rc::clone(node)).is_some() {
+					return Err(DeviceTreeError::DuplicateLabel);
+				}
+			}
+		}
+
+		let mut sites = Vec::new();
+
+		for node in nodes.iter() {
+			for (name, prop) in node.borrow().prop_iter() {
+				for r in prop.refs() {
+					sites.push(RefSite {
+						node: Rc::clone(node),
+						prop: name.to_string(),
+						offset: r.offset,
+						label: r.label.clone()
+					});
+				}
+			}
+		}
+
+		let mut next_phandle = nodes.iter().filter_map(|node| node.borrow().phandle()).max().unwrap_or(0) + 1;
+
+		let mut fixups: Vec<(String, String)> = Vec::new();
+		let mut local_fixups: Vec<(String, String, usize)> = Vec::new();
+
+		for site in sites {
+			let path = site.node.borrow().path();
+
+			let phandle = match labels.get(&site.label) {
+				Some(target) => {
+					let phandle = target.borrow().phandle();
+
+					let phandle = match phandle {
+						Some(phandle) => phandle,
+						None => {
+							let phandle = next_phandle;
+
+							next_phandle += 1;
+
+							debug!("Assigning phandle {:#x} to node labeled '{}'.", phandle, site.label);
+
+							target.borrow_mut().insert_prop(DeviceTreeProperty::from_bytes("phandle", &phandle.to_be_bytes()));
+
+							phandle
+						}
+					};
+
+					if options.plugin {
+						local_fixups.push((path, site.prop.clone(), site.offset));
+					}
+
+					phandle
+				},
+				None if options.plugin => {
+					fixups.push((site.label.clone(), format!("{}:{}:{}", path, site.prop, site.offset)));
+
+					UNRESOLVED_PHANDLE
+				},
+				None => {
+					debug!("Reference to unknown label '{}' in '{}'.", site.label, path);
+
+					return Err(DeviceTreeError::UnresolvedReference);
+				}
+			};
+
+			site.node.borrow_mut().prop_value_mut(&site.prop).unwrap().set_cell(site.offset, phandle)?;
+		}
+
+		let root = tree.root();
+
+		if options.symbols && !labels.is_empty() {
+			// Entries of an existing '__symbols__', e.g. read from a blob built with 'dtc -@', are kept
+			let existing = root.borrow().find_child("__symbols__").map(Rc::clone);
+
+			let symbols = match existing {
+				Some(symbols) => symbols,
+				None => {
+					let symbols = DeviceTreeNode::new_wrap();
+					insert_child(root, "__symbols__", Rc::clone(&symbols));
+					symbols
+				}
+			};
+
+			for (label, node) in labels.iter() {
+				let path = node.borrow().path();
+
+				add_typed_prop(&symbols, label, format!("{}\0", path).as_bytes(), DeviceTreePropertyType::String);
+			}
+		}
+
+		if options.plugin && !fixups.is_empty() {
+			let node = DeviceTreeNode::new_wrap();
+
+			let mut entries: BTreeMap<String, String> = BTreeMap::new();
+
+			for (label, entry) in fixups {
+				let value = entries.entry(label).or_default();
+				value.push_str(&entry);
+				value.push('\0');
+			}
+
+			for (label, value) in entries.iter() {
+				add_typed_prop(&node, label, value.as_bytes(), DeviceTreePropertyType::StringList);
+			}
+
+			insert_child(root, "__fixups__", node);
+		}
+
+		if options.plugin && !local_fixups.is_empty() {
+			let local = DeviceTreeNode::new_wrap();
+
+			for (path, prop, offset) in local_fixups {
+				let mut current = Rc::clone(&local);
+
+				for name in path.split('/').filter(|name| !name.is_empty()) {
+					let child = current.borrow().find_child(name).map(Rc::clone);
+
+					let next = match child {
+						Some(child) => child,
+						None => {
+							let child = DeviceTreeNode::new_wrap();
+							insert_child(&current, name, Rc::clone(&child));
+							child
+						}
+					};
+
+					current = next;
+				}
+
+				let mut value = current.borrow().prop_value(&prop).map(|p| p.raw_value().to_vec()).unwrap_or_default();
+
+				value.extend_from_slice(&(offset as u32).to_be_bytes());
+
+				add_typed_prop(&current, &prop, &value, DeviceTreePropertyType::Raw);
+			}
+
+			insert_child(root, "__local_fixups__", local);
+		}
+
+		Ok(tree)
+	}
+}
+
+/// Add a generated property, named after a label or a property of the tree and therefore not validated
+fn add_typed_prop(node: &DeviceTreeNodeWrap, name: &str, value: &[u8], value_type: DeviceTreePropertyType) {
+	let mut node = node.borrow_mut();
+
+	node.insert_prop(DeviceTreeProperty::from_bytes(name, value));
+	node.prop_value_mut(name).unwrap().set_type(value_type);
+}
@@ -0,0 +1,505 @@
+use alloc::{
+	rc::Rc,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::node::{
+	DeviceTreeNode,
+	DeviceTreeNodeWrap
+};
+
+/// Address space given by the 'ss' bits of phys.hi
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PciSpace {
+	Config,
+	Io,
+	Memory32,
+	Memory64
+}
+
+/// 3-cell PCI address: phys.hi, phys.mid and phys.lo
+///
+/// phys.hi is 'npt000ss bbbbbbbb dddddfff rrrrrrrr': non-relocatable, prefetchable and aliased bits, space code,
+/// bus, device, function and register numbers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PciAddress {
+	hi: u32,
+	mid: u32,
+	lo: u32
+}
+
+impl PciAddress {
+	pub fn new(hi: u32, mid: u32, lo: u32) -> Self {
+		Self { hi, mid, lo }
+	}
+
+	/// Address of the function `bus:device.function` in the configuration space
+	pub fn config(bus: u8, device: u8, function: u8) -> Self {
+		Self::new((bus as u32) << 16 | (device as u32 & 0x1f) << 11 | (function as u32 & 0x7) << 8, 0, 0)
+	}
+
+	pub fn hi(&self) -> u32 {
+		self.hi
+	}
+
+	pub fn space(&self) -> PciSpace {
+		match (self.hi >> 24) & 0x3 {
+			0 => PciSpace::Config,
+			1 => PciSpace::Io,
+			2 => PciSpace::Memory32,
+			_ => PciSpace::Memory64
+		}
+	}
+
+	pub fn is_prefetchable(&self) -> bool {
+		self.hi & (1 << 30) != 0
+	}
+
+	/// Whether the address may be moved, i.e. the 'n' bit is clear
+	pub fn is_relocatable(&self) -> bool {
+		self.hi & (1 << 31) == 0
+	}
+
+	pub fn is_aliased(&self) -> bool {
+		self.hi & (1 << 29) != 0
+	}
+
+	pub fn bus(&self) -> u8 {
+		(self.hi >> 16) as u8
+	}
+
+	pub fn device(&self) -> u8 {
+		((self.hi >> 11) & 0x1f) as u8
+	}
+
+	pub fn function(&self) -> u8 {
+		((self.hi >> 8) & 0x7) as u8
+	}
+
+	pub fn register(&self) -> u8 {
+		self.hi as u8
+	}
+
+	/// The 64-bit address in the space, from phys.mid and phys.lo
+	pub fn address(&self) -> u64 {
+		(self.mid as u64) << 32 | self.lo as u64
+	}
+}
+
+/// Entry of 'ranges' of a host bridge: a window of a PCI space and where the CPU sees it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PciWindow {
+	pci_address: PciAddress,
+	cpu_address: u64,
+	size: u64
+}
+
+impl PciWindow {
+	pub fn pci_address(&self) -> &PciAddress {
+		&self.pci_address
+	}
+
+	pub fn space(&self) -> PciSpace {
+		self.pci_address.space()
+	}
+
+	pub fn cpu_address(&self) -> u64 {
+		self.cpu_address
+	}
+
+	pub fn size(&self) -> u64 {
+		self.size
+	}
+
+	/// The CPU address of `address` in the space of the window, if the window contains it
+	pub fn translate(&self, address: u64) -> Option<u64> {
+		let offset = address.checked_sub(self.pci_address.address())?;
+
+		(offset < self.size).then(|| self.cpu_address + offset)
+	}
+}
+
+/// Entry of 'interrupt-map' routing an INTx pin of a device to an interrupt parent
+#[derive(Clone, Debug)]
+pub struct InterruptMapEntry {
+	child_address: PciAddress,
+	child_pin: u32,
+	parent: DeviceTreeNodeWrap,
+	parent_address: Vec<u32>,
+	parent_interrupt: Vec<u32>
+}
+
+impl InterruptMapEntry {
+	pub fn child_address(&self) -> &PciAddress {
+		&self.child_address
+	}
+
+	/// INTx pin, 1 to 4 for INTA to INTD
+	pub fn child_pin(&self) -> u32 {
+		self.child_pin
+	}
+
+	pub fn parent(&self) -> &DeviceTreeNodeWrap {
+		&self.parent
+	}
+
+	/// Unit address in the parent, as many cells as its '#address-cells', which defaults to 0 here
+	pub fn parent_address(&self) -> &[u32] {
+		&self.parent_address
+	}
+
+	/// Interrupt specifier in the parent, as many cells as its '#interrupt-cells'
+	pub fn parent_interrupt(&self) -> &[u32] {
+		&self.parent_interrupt
+	}
+}
+
+/// Entry of 'msi-map' mapping a range of requester IDs to MSI specifiers of a controller
+///
+/// The MSI specifier has the '#msi-cells' of the controller, 1 if it has none, and is at most one cell here.
+#[derive(Clone, Debug)]
+pub struct MsiMapEntry {
+	rid_base: u32,
+	controller: DeviceTreeNodeWrap,
+	msi_base: u32,
+	length: u32
+}
+
+impl MsiMapEntry {
+	pub fn rid_base(&self) -> u32 {
+		self.rid_base
+	}
+
+	pub fn controller(&self) -> &DeviceTreeNodeWrap {
+		&self.controller
+	}
+
+	/// First MSI specifier of the range, 0 for a controller with '#msi-cells = <0>'
+	pub fn msi_base(&self) -> u32 {
+		self.msi_base
+	}
+
+	pub fn length(&self) -> u32 {
+		self.length
+	}
+}
+
+/// A PCI host bridge, i.e. a node with 'device_type = "pci"' whose parent is not a PCI node
+#[derive(Clone, Debug)]
+pub struct PciHostBridge {
+	node: DeviceTreeNodeWrap,
+	reg: Vec<(u64, u64)>,
+	bus_range: (u32, u32),
+	windows: Vec<PciWindow>,
+	interrupt_map_mask: [u32; 4],
+	interrupt_map: Vec<InterruptMapEntry>,
+	msi_map_mask: u32,
+	msi_map: Vec<MsiMapEntry>,
+	msi_parent: Option<DeviceTreeNodeWrap>
+}
+
+impl PciHostBridge {
+	/// Decode the host bridge `node`, whose '#address-cells' must be 3 and '#interrupt-cells' 1
+	///
+	/// Returns DeviceTreeError::BadPropValue for malformed properties or a '#msi-cells' above 1 in a controller of
+	/// 'msi-map', and DeviceTreeError::BadPhandle if
+	/// 'interrupt-map', 'msi-map' or 'msi-parent' refers to an unknown node.
+	pub fn from_node(tree: &DeviceTree, node: &DeviceTreeNodeWrap) -> Result<Self, DeviceTreeError> {
+		let bridge = node.borrow();
+
+		if cells(&bridge, "#address-cells", 3)? != 3 || cells(&bridge, "#interrupt-cells", 1)? != 1 {
+			return Err(DeviceTreeError::BadPropValue);
+		}
+
+		let size_cells = cells(&bridge, "#size-cells", 2)?;
+		let parent_address_cells = match bridge.parent() {
+			Some(parent) => cells(&parent.borrow(), "#address-cells", 2)?,
+			None => 2
+		};
+
+		let bus_range = match bridge.prop_value("bus-range") {
+			Some(prop) => match prop.cells()?[..] {
+				[start, end] if start <= end && end <= 0xff => (start, end),
+				_ => return Err(DeviceTreeError::BadPropValue)
+			},
+			None => (0, 0xff)
+		};
+
+		let mut windows = Vec::new();
+
+		if let Some(ranges) = bridge.prop_value("ranges") {
+			let cells = ranges.cells()?;
+
+			for mut entry in cells.chunks(3 + parent_address_cells as usize + size_cells as usize) {
+				let pci_address = take_pci_address(&mut entry)?;
+
+				windows.push(PciWindow {
+					pci_address,
+					cpu_address: take_u64(&mut entry, parent_address_cells)?,
+					size: take_u64(&mut entry, size_cells)?
+				});
+			}
+		}
+
+		let interrupt_map_mask = match bridge.prop_value("interrupt-map-mask") {
+			Some(prop) => prop.cells()?.try_into().map_err(|_| DeviceTreeError::BadPropValue)?,
+			None => [u32::MAX; 4]
+		};
+
+		let interrupt_map = match bridge.prop_value("interrupt-map") {
+			Some(prop) => parse_interrupt_map(tree, &prop.cells()?)?,
+			None => Vec::new()
+		};
+
+		let msi_map_mask = match bridge.prop_value("msi-map-mask") {
+			Some(prop) => prop.u32()?,
+			None => u32::MAX
+		};
+
+		let msi_map = match bridge.prop_value("msi-map") {
+			Some(prop) => parse_msi_map(tree, &prop.cells()?)?,
+			None => Vec::new()
+		};
+
+		let msi_parent = match bridge.prop_value("msi-parent") {
+			Some(prop) => Some(tree.find_phandle(prop.cells()?.first().copied().ok_or(DeviceTreeError::BadPropValue)?)
+				.ok_or(DeviceTreeError::BadPhandle)?),
+			None => None
+		};
+
+		Ok(Self {
+			node: Rc::clone(node),
+			reg: bridge.reg()?,
+			bus_range,
+			windows,
+			interrupt_map_mask,
+			interrupt_map,
+			msi_map_mask,
+			msi_map,
+			msi_parent
+		})
+	}
+
+	pub fn node(&self) -> &DeviceTreeNodeWrap {
+		&self.node
+	}
+
+	/// The 'reg' of the bridge in the address space of its parent, e.g. the ECAM region
+	pub fn reg(&self) -> &[(u64, u64)] {
+		&self.reg
+	}
+
+	/// First and last bus number, 0 to 255 without 'bus-range'
+	pub fn bus_range(&self) -> (u32, u32) {
+		self.bus_range
+	}
+
+	pub fn windows(&self) -> &[PciWindow] {
+		&self.windows
+	}
+
+	/// The CPU address of `address` in `space`, through the first window that contains it
+	pub fn translate(&self, space: PciSpace, address: u64) -> Option<u64> {
+		self.windows.iter()
+			.filter(|window| window.space() == space)
+			.find_map(|window| window.translate(address))
+	}
+
+	/// CPU address of the configuration space of `bus:device.function` in the ECAM region of a
+	/// 'pci-host-ecam-generic' bridge, which starts at the first bus of 'bus-range'
+	pub fn ecam_address(&self, bus: u8, device: u8, function: u8) -> Option<u64> {
+		let (base, size) = *self.reg.first()?;
+		let bus = (bus as u32).checked_sub(self.bus_range.0).filter(|_| bus as u32 <= self.bus_range.1)?;
+
+		let offset = (bus as u64) << 20 | (device as u64 & 0x1f) << 15 | (function as u64 & 0x7) << 12;
+
+		(offset < size).then(|| base + offset)
+	}
+
+	pub fn interrupt_map(&self) -> &[InterruptMapEntry] {
+		&self.interrupt_map
+	}
+
+	/// The 'interrupt-map' entry for INTx `pin` (1 for INTA) of `bus:device.function`, after 'interrupt-map-mask'
+	pub fn route_intx(&self, bus: u8, device: u8, function: u8, pin: u32) -> Option<&InterruptMapEntry> {
+		let address = PciAddress::config(bus, device, function);
+		let mask = &self.interrupt_map_mask;
+
+		let key = |address: &PciAddress, pin: u32| {
+			[address.hi & mask[0], address.mid & mask[1], address.lo & mask[2], pin & mask[3]]
+		};
+
+		let wanted = key(&address, pin);
+
+		self.interrupt_map.iter().find(|entry| key(&entry.child_address, entry.child_pin) == wanted)
+	}
+
+	pub fn msi_map(&self) -> &[MsiMapEntry] {
+		&self.msi_map
+	}
+
+	pub fn msi_parent(&self) -> Option<&DeviceTreeNodeWrap> {
+		self.msi_parent.as_ref()
+	}
+
+	/// The MSI controller and MSI specifier of requester ID `rid`, i.e. bus << 8 | device << 3 | function
+	///
+	/// With 'msi-map' the masked ID goes through the entry that covers it, if any. Without it the ID is passed
+	/// unchanged to 'msi-parent'.
+	pub fn map_rid(&self, rid: u32) -> Option<(&DeviceTreeNodeWrap, u32)> {
+		if self.msi_map.is_empty() {
+			return self.msi_parent.as_ref().map(|parent| (parent, rid));
+		}
+
+		let rid = rid & self.msi_map_mask;
+
+		self.msi_map.iter().find_map(|entry| {
+			let offset = rid.checked_sub(entry.rid_base).filter(|&offset| offset < entry.length)?;
+
+			Some((&entry.controller, entry.msi_base + offset))
+		})
+	}
+}
+
+/// The PCI host bridges of a tree, see DeviceTree::pci_host_bridges
+#[derive(Clone, Debug, Default)]
+pub struct PciHostBridges {
+	bridges: Vec<PciHostBridge>,
+	invalid: Vec<(DeviceTreeNodeWrap, DeviceTreeError)>
+}
+
+impl PciHostBridges {
+	/// The bridges that could be decoded, in the order of their nodes
+	pub fn bridges(&self) -> &[PciHostBridge] {
+		&self.bridges
+	}
+
+	/// The host bridge nodes left out because they could not be decoded, with the error of PciHostBridge::from_node
+	pub fn invalid(&self) -> &[(DeviceTreeNodeWrap, DeviceTreeError)] {
+		&self.invalid
+	}
+}
+
+impl DeviceTree {
+	/// The PCI host bridges of the tree
+	///
+	/// A bridge that cannot be decoded is left out and listed by PciHostBridges::invalid, the others are still there.
+	pub fn pci_host_bridges(&self) -> PciHostBridges {
+		let mut bridges = PciHostBridges::default();
+
+		let nodes = self.nodes().into_iter().filter(|node| {
+			let node = node.borrow();
+
+			is_pci(&node) && !node.parent().is_some_and(|parent| is_pci(&parent.borrow()))
+		});
+
+		for node in nodes {
+			match PciHostBridge::from_node(self, &node) {
+				Ok(bridge) => bridges.bridges.push(bridge),
+				Err(error) => bridges.invalid.push((node, error))
+			}
+		}
+
+		bridges
+	}
+}
+
+fn is_pci(node: &DeviceTreeNode) -> bool {
+	node.prop_value("device_type").and_then(|prop| prop.string().ok()) == Some("pci")
+}
+
+fn cells(node: &DeviceTreeNode, name: &str, default: u32) -> Result<u32, DeviceTreeError> {
+	Ok(node.prop_value(name).map(|prop| prop.u32()).transpose()?.unwrap_or(default))
+}
+
+/// Take a field of `cells` cells, at most two, from the front of `entry`
+fn take_u64(entry: &mut &[u32], cells: u32) -> Result<u64, DeviceTreeError> {
+	if cells > 2 || entry.len() < cells as usize {
+		return Err(DeviceTreeError::BadPropValue);
+	}
+
+	let (field, rest) = entry.split_at(cells as usize);
+
+	*entry = rest;
+
+	Ok(field.iter().fold(0, |value, &cell| value << 32 | cell as u64))
+}
+
+fn take_cells(entry: &mut &[u32], cells: u32) -> Result<Vec<u32>, DeviceTreeError> {
+	if entry.len() < cells as usize {
+		return Err(DeviceTreeError::BadPropValue);
+	}
+
+	let (field, rest) = entry.split_at(cells as usize);
+
+	*entry = rest;
+
+	Ok(field.to_vec())
+}
+
+fn take_pci_address(entry: &mut &[u32]) -> Result<PciAddress, DeviceTreeError> {
+	match take_cells(entry, 3)?[..] {
+		[hi, mid, lo] => Ok(PciAddress::new(hi, mid, lo)),
+		_ => Err(DeviceTreeError::BadPropValue)
+	}
+}
+
+/// Entries of 'interrupt-map', whose length depends on the '#address-cells' and '#interrupt-cells' of each parent
+fn parse_interrupt_map(tree: &DeviceTree, map: &[u32]) -> Result<Vec<InterruptMapEntry>, DeviceTreeError> {
+	let mut entries = Vec::new();
+	let mut map = map;
+
+	while !map.is_empty() {
+		let child_address = take_pci_address(&mut map)?;
+		let child_pin = take_cells(&mut map, 1)?[0];
+		let parent = tree.find_phandle(take_cells(&mut map, 1)?[0]).ok_or(DeviceTreeError::BadPhandle)?;
+
+		let (address_cells, interrupt_cells) = {
+			let parent = parent.borrow();
+
+			let interrupt_cells = parent.prop_value("#interrupt-cells").ok_or(DeviceTreeError::BadPropValue)?.u32()?;
+
+			(cells(&parent, "#address-cells", 0)?, interrupt_cells)
+		};
+
+		entries.push(InterruptMapEntry {
+			child_address,
+			child_pin,
+			parent_address: take_cells(&mut map, address_cells)?,
+			parent_interrupt: take_cells(&mut map, interrupt_cells)?,
+			parent
+		});
+	}
+
+	Ok(entries)
+}
+
+/// Entries of 'msi-map', whose MSI specifier has the '#msi-cells' of each controller, 1 if it has none
+fn parse_msi_map(tree: &DeviceTree, map: &[u32]) -> Result<Vec<MsiMapEntry>, DeviceTreeError> {
+	let mut entries = Vec::new();
+	let mut map = map;
+
+	while !map.is_empty() {
+		let rid_base = take_cells(&mut map, 1)?[0];
+		let controller = tree.find_phandle(take_cells(&mut map, 1)?[0]).ok_or(DeviceTreeError::BadPhandle)?;
+
+		let msi_cells = cells(&controller.borrow(), "#msi-cells", 1)?;
+
+		if msi_cells > 1 {
+			return Err(DeviceTreeError::BadPropValue);
+		}
+
+		entries.push(MsiMapEntry {
+			rid_base,
+			msi_base: take_cells(&mut map, msi_cells)?.first().copied().unwrap_or(0),
+			length: take_cells(&mut map, 1)?[0],
+			controller
+		});
+	}
+
+	Ok(entries)
+}
@@ -11,27 +11,57 @@ use crate::{
 	utils, 
 	DeviceTreeError
 };
+use super::{
+	schema,
+	dts
+};
 
 /* Property of devicetree: 
 Each node in the devicetree has properties that describe the characteristics of the node. */
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct  DeviceTreeProperty {
 	name: String,
 	raw_value: Vec<u8>,
-	value_type: DeviceTreePropertyType
+	value_type: DeviceTreePropertyType,
+	/// Cells of the value that refer to a labeled node, resolved when the tree is written
+	refs: Vec<PhandleRef>
+}
+
+/// A reference from a cell of a property value to the node carrying `label`
+#[derive(Clone, PartialEq, Debug)]
+pub struct PhandleRef {
+	/// Offset in bytes of the referring cell within the property value
+	pub offset: usize,
+	pub label: String
 }
 
 impl core::fmt::Display for DeviceTreeProperty {
+	/// The property in source syntax, formatted like DeviceTree::to_dts with the default InferOptions
 	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-		match self.value_type {
-			DeviceTreePropertyType::Empty => write!(f, "{}", self.name),
-			DeviceTreePropertyType::String => write!(f, "{} = \"{}\"", self.name, String::from_utf8(self.raw_value.to_vec()).unwrap()),
-			DeviceTreePropertyType::StringList => write!(f, "{} = \"{}\"", self.name, String::from_utf8(self.raw_value.to_vec()).unwrap()),
-			DeviceTreePropertyType::U32 => write!(f, "{} = <{:#x}>", self.name, utils::read_first_be_u32(&mut self.raw_value.as_slice()).unwrap()),
-			DeviceTreePropertyType::U64 => write!(f, "{} = <{:#x}>", self.name, utils::read_first_be_u64(&mut self.raw_value.as_slice()).unwrap()),
-			DeviceTreePropertyType::Bytes => write!(f, "{} = [{}]", self.name, self.raw_value.iter().map(|i| format!("{:02x}", i)).collect::<Vec<String>>().join(" ")),
-			DeviceTreePropertyType::Raw => write!(f, "{} = (raw) [{}]", self.name, self.raw_value.iter().map(|i| format!("{:x}", i)).collect::<Vec<String>>().join(" "))
-		}
+		write!(f, "{}", dts::prop_fmt(self, &InferOptions::new()))
+	}
+}
+
+/// Which guesses DeviceTreeProperty::guess_type may make, like dtc does when decompiling
+/// 
+/// A value that none of the enabled guesses fits is shown as bytes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct InferOptions {
+	/// Show NUL-terminated printable text as a string or a string list
+	pub strings: bool,
+	/// Show values whose length is a multiple of 4 as cells
+	pub cells: bool
+}
+
+impl InferOptions {
+	pub fn new() -> Self {
+		Self { strings: true, cells: true }
+	}
+}
+
+impl Default for InferOptions {
+	fn default() -> Self {
+		Self::new()
 	}
 }
 
@@ -40,7 +70,8 @@ impl DeviceTreeProperty {
 		Self { 
 			name: name.to_string(), 
 			raw_value: bytes.to_vec(), 
-			value_type: DeviceTreePropertyType::Raw 
+			value_type: DeviceTreePropertyType::Raw,
+			refs: Vec::new()
 		}
 	}
 
@@ -48,94 +79,200 @@ impl DeviceTreeProperty {
 		&self.name
 	}
 
+	pub fn raw_value(&self) -> &[u8] {
+		&self.raw_value
+	}
+
+	pub fn value_type(&self) -> &DeviceTreePropertyType {
+		&self.value_type
+	}
+
+	/// Overwrite the cell at `offset` with a u32 in big endian
+	pub fn set_cell(&mut self, offset: usize, value: u32) -> Result<(), DeviceTreeError> {
+		let cell = self.raw_value.get_mut(offset..offset + 4).ok_or(DeviceTreeError::BadPropValue)?;
+
+		cell.copy_from_slice(&value.to_be_bytes());
+
+		Ok(())
+	}
+
+	/// Mark the cell at `offset` as a reference to the node labeled `label`
+	/// 
+	/// The cell is filled in with the phandle of that node when the tree is written.
+	pub fn add_ref(&mut self, offset: usize, label: &str) -> Result<(), DeviceTreeError> {
+		if !offset.is_multiple_of(4) || offset + 4 > self.raw_value.len() {
+			return Err(DeviceTreeError::BadPropValue);
+		}
+
+		self.refs.push(PhandleRef { offset, label: label.to_string() });
+
+		Ok(())
+	}
+
+	pub fn refs(&self) -> &[PhandleRef] {
+		&self.refs
+	}
+
 	pub fn set_type(&mut self, value_type: DeviceTreePropertyType) {
 		self.value_type = value_type;
 	}
 
+	/// Guess the type of the value the way dtc does, ignoring the type set on the property
+	pub fn guess_type(&self, options: &InferOptions) -> DeviceTreePropertyType {
+		let value = self.raw_value.as_slice();
+
+		if value.is_empty() {
+			return DeviceTreePropertyType::Empty;
+		}
+
+		if options.strings && is_printable_strings(value) {
+			return match value.iter().filter(|&&b| b == 0).count() {
+				1 => DeviceTreePropertyType::String,
+				_ => DeviceTreePropertyType::StringList
+			};
+		}
+
+		if options.cells && value.len().is_multiple_of(4) {
+			return DeviceTreePropertyType::Cells;
+		}
+
+		DeviceTreePropertyType::Bytes
+	}
+
+	/// Set the type of a property that update_type does not know from a guess, see guess_type
+	pub fn infer_type(&mut self, options: &InferOptions) {
+		if self.value_type == DeviceTreePropertyType::Raw {
+			self.value_type = self.guess_type(options);
+		}
+	}
+
+	/// The type to display the value with: the type of the property, or a guess if it has none
+	pub fn display_type(&self, options: &InferOptions) -> DeviceTreePropertyType {
+		match self.value_type {
+			DeviceTreePropertyType::Raw => self.guess_type(options),
+			ref value_type => value_type.clone()
+		}
+	}
+
+	/// Set the type of the properties defined by the Devicetree Specification, the ones of BindingSchema::core
+	/// 
+	/// The name is looked up in a static table, so this does not build the schema.
+	/// Other properties get their type from a SchemaRegistry or from infer_type.
+	/// The type only decides how the value is displayed, the readers below work on any property.
 	pub fn update_type(&mut self) {
-		self.value_type = match self.name.as_str() {
-			"#address-cells" => DeviceTreePropertyType::U32,
-			"#size-cells" => DeviceTreePropertyType::U32,
-			"#interrupt-cells" => DeviceTreePropertyType::U32,
-			"compatible" => DeviceTreePropertyType::StringList,
-			"model" => DeviceTreePropertyType::String,
-			"phandle" => DeviceTreePropertyType::U32,
-			"status" => DeviceTreePropertyType::String,
-			"virtual-reg" => DeviceTreePropertyType::U32,
-			"dma-coherent" => DeviceTreePropertyType::Empty,
-			"name" => DeviceTreePropertyType::String,
-			"device_type" => DeviceTreePropertyType::String,
-			"timebase-frequency" => DeviceTreePropertyType::U32,
-			"clock-frequency" => DeviceTreePropertyType::U32,
-			"local-mac-address" => DeviceTreePropertyType::Bytes,
-			_ => DeviceTreePropertyType::Raw
-		};
+		self.value_type = schema::core_type(self).unwrap_or(DeviceTreePropertyType::Raw);
 	}
 
+	/// The value as a single u32 cell
+	/// 
+	/// Like the other readers, this checks the length and encoding of the value, not the type set on the property.
 	pub fn u32(&self) -> Result<u32, DeviceTreeError> {
-		if self.value_type != DeviceTreePropertyType::U32 {
-			return Err(DeviceTreeError::BadPropType);
-		}
+		let bytes: [u8; 4] = self.raw_value.as_slice().try_into().map_err(|_| DeviceTreeError::BadPropValue)?;
 
-		Ok(utils::read_first_be_u32(&mut self.raw_value.as_slice()).unwrap())
+		Ok(u32::from_be_bytes(bytes))
 	}
 
-    pub fn u64(&self) -> Result<u64, DeviceTreeError> {
-		if self.value_type != DeviceTreePropertyType::U64 {
-			return Err(DeviceTreeError::BadPropType);
-		}
+	/// The value as a u64 made of two cells
+	pub fn u64(&self) -> Result<u64, DeviceTreeError> {
+		let bytes: [u8; 8] = self.raw_value.as_slice().try_into().map_err(|_| DeviceTreeError::BadPropValue)?;
 
-		Ok(utils::read_first_be_u64(&mut self.raw_value.as_slice()).unwrap())
+		Ok(u64::from_be_bytes(bytes))
 	}
 
-    pub fn string(&self) -> Result<String, DeviceTreeError> {
-		if self.value_type != DeviceTreePropertyType::String {
-			return Err(DeviceTreeError::BadPropType);
+	/// The value as a phandle, which is a single cell other than 0 and 0xffffffff
+	pub fn phandle(&self) -> Result<u32, DeviceTreeError> {
+		match self.u32()? {
+			0 | 0xffffffff => Err(DeviceTreeError::BadPropValue),
+			phandle => Ok(phandle)
 		}
+	}
 
-		Ok(String::from_utf8(self.raw_value.to_vec()).unwrap())
+	/// The value as a single NUL-terminated UTF-8 string
+	pub fn string(&self) -> Result<&str, DeviceTreeError> {
+		match self.raw_value.split_last() {
+			Some((0, s)) if !s.contains(&0) => core::str::from_utf8(s).map_err(|_| DeviceTreeError::BadPropValue),
+			_ => Err(DeviceTreeError::BadPropValue)
+		}
 	}
 
-    pub fn stringlist(&self) -> Result<Vec<String>, DeviceTreeError> {
-		if self.value_type != DeviceTreePropertyType::StringList {
-			return Err(DeviceTreeError::BadPropType);
+	/// The value as a list of NUL-terminated UTF-8 strings
+	pub fn stringlist(&self) -> Result<Vec<&str>, DeviceTreeError> {
+		match self.raw_value.split_last() {
+			Some((0, s)) => s.split(|&b| b == 0)
+				.map(|s| core::str::from_utf8(s).map_err(|_| DeviceTreeError::BadPropValue))
+				.collect(),
+			_ => Err(DeviceTreeError::BadPropValue)
 		}
+	}
 
-		let mut vec_string: Vec<String> = Vec::new();
+	/// The value as an array of u32 cells
+	pub fn cells(&self) -> Result<Vec<u32>, DeviceTreeError> {
+		if !self.raw_value.len().is_multiple_of(4) {
+			return Err(DeviceTreeError::BadPropValue);
+		}
 
-		loop {
-			let s = utils::take_utf8_until_nul(&mut self.raw_value.as_slice().clone()).unwrap();
+		Ok(self.raw_value.chunks_exact(4).map(|c| u32::from_be_bytes(c.try_into().unwrap())).collect())
+	}
 
-			if s.is_empty() {
-				break;
-			}
+	/// The value as a byte array, which any value is
+	pub fn bytes(&self) -> &[u8] {
+		&self.raw_value
+	}
 
-			vec_string.push(s.to_string());
+	/// The value as a prop-encoded-array of tuples, e.g. `reg` with `&[address_cells, size_cells]`
+	/// 
+	/// Each entry of `fields` is the number of cells of a field of the tuple, at most two, so that a field fits in a u64.
+	/// The value must consist of whole tuples.
+	pub fn encoded_array(&self, fields: &[u32]) -> Result<Vec<Vec<u64>>, DeviceTreeError> {
+		if fields.iter().any(|&cells| cells > 2) {
+			return Err(DeviceTreeError::BadPropValue);
 		}
 
-		Ok(vec_string)
-	}
+		let tuple_size = fields.iter().sum::<u32>() as usize * 4;
 
-    pub fn bytes(&self) -> Result<Vec<u8>, DeviceTreeError> {
-		if self.value_type != DeviceTreePropertyType::StringList {
-			return Err(DeviceTreeError::BadPropType);
+		if tuple_size == 0 || !self.raw_value.len().is_multiple_of(tuple_size) {
+			return Err(DeviceTreeError::BadPropValue);
 		}
 
-		Ok(self.raw_value.clone())
+		let tuples = self.raw_value.chunks_exact(tuple_size).map(|mut tuple| {
+			fields.iter().map(|&cells| {
+				let mut field = 0;
+
+				for _ in 0..cells {
+					field = (field << 32) | utils::take_be_u32(&mut tuple).unwrap() as u64;
+				}
+
+				field
+			}).collect()
+		}).collect();
+
+		Ok(tuples)
 	}
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum DeviceTreePropertyType {
 	Empty,
 	StringList,
 	String, 
 	U32,
 	U64,
+	/// Array of u32 cells
+	Cells,
 	Bytes,
 	Raw
 }
 
+/// Whether `value` is one or more non-empty NUL-terminated strings of printable ASCII
+fn is_printable_strings(value: &[u8]) -> bool {
+	match value.split_last() {
+		Some((0, s)) => s.split(|&b| b == 0).all(|s| {
+			!s.is_empty() && s.iter().all(|&b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+		}),
+		_ => false
+	}
+}
+
 /* The #address-cells and #size-cells properties may be used in any device node that has children in the devicetree
 hierarchy and describes how child device nodes should be addressed. 
 The #address-cells property defines the number of <u32> cells used to encode the address field in a child node’s reg property. 
@@ -176,6 +313,12 @@ impl Pairs {
 	}
 }
 
+impl Default for Pairs {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl From<Pairs> for String {
 	fn from(pairs: Pairs) -> Self {
 		let mut v = Vec::new();
@@ -0,0 +1,292 @@
+use alloc::{
+	rc::Rc,
+	string::{
+		String,
+		ToString
+	},
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::{
+	node::{
+		DeviceTreeNode,
+		DeviceTreeNodeWrap
+	},
+	memory::{
+		MemoryMap,
+		MemoryRange,
+		subtract_all
+	}
+};
+
+/// Where a reserved memory region is placed
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Placement {
+	/// Fixed ranges given by 'reg'
+	Static(Vec<MemoryRange>),
+	/// Placed by the allocator from 'size', 'alignment' and 'alloc-ranges'
+	Dynamic {
+		size: u64,
+		alignment: u64,
+		alloc_ranges: Vec<MemoryRange>
+	}
+}
+
+/// A child of '/reserved-memory'
+#[derive(Clone, Debug)]
+pub struct ReservedRegion {
+	node: DeviceTreeNodeWrap,
+	placement: Placement,
+	ranges: Vec<MemoryRange>,
+	no_map: bool,
+	reusable: bool,
+	compatible: Vec<String>
+}
+
+impl ReservedRegion {
+	/// Read a region, with the '#address-cells' and '#size-cells' of '/reserved-memory'
+	fn from_node(node: &DeviceTreeNodeWrap, address_cells: u32, size_cells: u32) -> Result<Self, DeviceTreeError> {
+		let region = node.borrow();
+
+		let ranges = |name: &str| -> Result<Vec<MemoryRange>, DeviceTreeError> {
+			match region.prop_value(name) {
+				Some(prop) => Ok(prop.encoded_array(&[address_cells, size_cells])?
+					.into_iter()
+					.map(|entry| MemoryRange::new(entry[0], entry[1]))
+					.collect()),
+				None => Ok(Vec::new())
+			}
+		};
+
+		let size_prop = |name: &str| -> Result<Option<u64>, DeviceTreeError> {
+			match region.prop_value(name) {
+				Some(prop) => Ok(Some(prop.encoded_array(&[size_cells])?.first().ok_or(DeviceTreeError::BadPropValue)?[0])),
+				None => Ok(None)
+			}
+		};
+
+		let placement = match (region.prop_exists("reg"), size_prop("size")?) {
+			(true, _) => Placement::Static(ranges("reg")?),
+			(false, Some(size)) => Placement::Dynamic {
+				size,
+				alignment: size_prop("alignment")?.unwrap_or(1),
+				alloc_ranges: ranges("alloc-ranges")?
+			},
+			(false, None) => return Err(DeviceTreeError::BadPropValue)
+		};
+
+		let compatible = match region.prop_value("compatible") {
+			Some(prop) => prop.stringlist()?.into_iter().map(|s| s.to_string()).collect(),
+			None => Vec::new()
+		};
+
+		Ok(Self {
+			node: Rc::clone(node),
+			ranges: match &placement {
+				Placement::Static(ranges) => ranges.clone(),
+				Placement::Dynamic { .. } => Vec::new()
+			},
+			placement,
+			no_map: region.prop_exists("no-map"),
+			reusable: region.prop_exists("reusable"),
+			compatible
+		})
+	}
+
+	pub fn node(&self) -> &DeviceTreeNodeWrap {
+		&self.node
+	}
+
+	pub fn path(&self) -> String {
+		self.node.borrow().path()
+	}
+
+	pub fn placement(&self) -> &Placement {
+		&self.placement
+	}
+
+	pub fn is_dynamic(&self) -> bool {
+		matches!(self.placement, Placement::Dynamic { .. })
+	}
+
+	/// The memory of the region: the 'reg' of a static region, the allocation of a dynamic one
+	///
+	/// Empty for a dynamic region that could not be allocated.
+	pub fn ranges(&self) -> &[MemoryRange] {
+		&self.ranges
+	}
+
+	pub fn is_allocated(&self) -> bool {
+		!self.ranges.is_empty()
+	}
+
+	/// Whether the operating system must not map the region, so it is not usable RAM
+	pub fn is_no_map(&self) -> bool {
+		self.no_map
+	}
+
+	/// Whether the operating system may use the region while its owner does not
+	pub fn is_reusable(&self) -> bool {
+		self.reusable
+	}
+
+	pub fn compatible(&self) -> &[String] {
+		&self.compatible
+	}
+
+	/// Whether the region is a pool of memory for DMA shared by devices
+	pub fn is_shared_dma_pool(&self) -> bool {
+		self.compatible.iter().any(|compatible| compatible == "shared-dma-pool")
+	}
+}
+
+/// The regions of '/reserved-memory', dynamic ones placed by the allocator
+#[derive(Clone, Debug, Default)]
+pub struct ReservedMemory {
+	regions: Vec<ReservedRegion>,
+	invalid: Vec<(DeviceTreeNodeWrap, DeviceTreeError)>
+}
+
+impl ReservedMemory {
+	/// Place the dynamic regions in RAM, in the order of the regions
+	///
+	/// `map` gives the free memory, from which the static regions and each allocation are taken out in turn.
+	/// A region goes at the highest address that is aligned, within its 'alloc-ranges' if it has any, like the
+	/// top-down allocator of Linux. A region that does not fit anywhere is left unallocated.
+	pub fn allocate(regions: Vec<ReservedRegion>, map: &MemoryMap) -> Self {
+		let mut regions = regions;
+		let mut taken: Vec<MemoryRange> = regions.iter().flat_map(|region| region.ranges.iter().copied()).collect();
+
+		for region in regions.iter_mut() {
+			let (size, alignment, alloc_ranges) = match &region.placement {
+				Placement::Dynamic { size, alignment, alloc_ranges } => (*size, *alignment, alloc_ranges),
+				Placement::Static(_) => continue
+			};
+
+			let free = subtract_all(map.usable(), taken.iter().copied());
+
+			let windows: Vec<MemoryRange> = match alloc_ranges.is_empty() {
+				true => free,
+				false => free.iter()
+					.flat_map(|range| alloc_ranges.iter().filter_map(|alloc_range| intersect(range, alloc_range)))
+					.collect()
+			};
+
+			let start = windows.iter().filter_map(|window| top_down(window, size, alignment)).max();
+
+			if let Some(start) = start {
+				let range = MemoryRange::new(start, size);
+
+				region.ranges.push(range);
+				taken.push(range);
+			}
+		}
+
+		Self { regions, invalid: Vec::new() }
+	}
+
+	pub fn regions(&self) -> &[ReservedRegion] {
+		&self.regions
+	}
+
+	/// The children of '/reserved-memory' left out because they could not be read, with the reason
+	///
+	/// DeviceTreeError::BadPropValue for a node with neither 'reg' nor 'size', or with a malformed property.
+	pub fn invalid(&self) -> &[(DeviceTreeNodeWrap, DeviceTreeError)] {
+		&self.invalid
+	}
+
+	pub fn find(&self, path: &str) -> Option<&ReservedRegion> {
+		self.regions.iter().find(|region| region.path() == path)
+	}
+
+	/// The regions that `consumer` refers to with the phandles of its 'memory-region', in the same order
+	///
+	/// Returns DeviceTreeError::BadPhandle if a phandle is not the one of a region.
+	pub fn regions_of(&self, consumer: &DeviceTreeNode) -> Result<Vec<&ReservedRegion>, DeviceTreeError> {
+		let phandles = match consumer.prop_value("memory-region") {
+			Some(prop) => prop.cells()?,
+			None => return Ok(Vec::new())
+		};
+
+		phandles.into_iter()
+			.map(|phandle| {
+				self.regions.iter()
+					.find(|region| region.node.borrow().phandle() == Some(phandle))
+					.ok_or(DeviceTreeError::BadPhandle)
+			})
+			.collect()
+	}
+}
+
+impl DeviceTree {
+	/// The enabled regions of '/reserved-memory', with dynamic ones allocated, see ReservedMemory::allocate
+	///
+	/// Dynamic regions are allocated from RAM without the memory reservation block and the static regions.
+	/// A region that cannot be read is left out and listed by ReservedMemory::invalid.
+	pub fn reserved_memory(&self) -> Result<ReservedMemory, DeviceTreeError> {
+		let reserved_memory = match self.find_node("/reserved-memory") {
+			Some(reserved_memory) => reserved_memory,
+			None => return Ok(ReservedMemory::default())
+		};
+
+		let reserved_memory = reserved_memory.borrow();
+
+		let cells = |name: &str, default: u32| -> Result<u32, DeviceTreeError> {
+			Ok(reserved_memory.prop_value(name).map(|prop| prop.u32()).transpose()?.unwrap_or(default))
+		};
+
+		let (address_cells, size_cells) = (cells("#address-cells", 2)?, cells("#size-cells", 1)?);
+
+		let mut regions = Vec::new();
+		let mut invalid = Vec::new();
+
+		for (_, node) in reserved_memory.children_iter().filter(|(_, node)| node.borrow().is_enabled()) {
+			match ReservedRegion::from_node(node, address_cells, size_cells) {
+				Ok(region) => regions.push(region),
+				Err(error) => invalid.push((Rc::clone(node), error))
+			}
+		}
+
+		let map = MemoryMap::new(&self.memory()?, self.header_reservations());
+
+		Ok(ReservedMemory { invalid, ..ReservedMemory::allocate(regions, &map) })
+	}
+
+	/// The nodes whose 'memory-region' refers to `region`
+	pub fn memory_region_consumers(&self, region: &ReservedRegion) -> Vec<DeviceTreeNodeWrap> {
+		let phandle = match region.node.borrow().phandle() {
+			Some(phandle) => phandle,
+			None => return Vec::new()
+		};
+
+		self.nodes()
+			.into_iter()
+			.filter(|node| {
+				node.borrow()
+					.prop_value("memory-region")
+					.and_then(|prop| prop.cells().ok())
+					.is_some_and(|phandles| phandles.contains(&phandle))
+			})
+			.collect()
+	}
+}
+
+fn intersect(a: &MemoryRange, b: &MemoryRange) -> Option<MemoryRange> {
+	let start = a.start().max(b.start());
+	let end = a.end().min(b.end());
+
+	(start < end).then(|| MemoryRange::new(start, end - start))
+}
+
+/// Highest start of `size` bytes aligned to `alignment` in `window`
+fn top_down(window: &MemoryRange, size: u64, alignment: u64) -> Option<u64> {
+	let start = window.end().checked_sub(size)?;
+	let start = start - start % alignment.max(1);
+
+	(start >= window.start()).then_some(start)
+}
@@ -0,0 +1,791 @@
+use alloc::{
+	string::{
+		String,
+		ToString
+	},
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::{
+	node::DeviceTreeNode,
+	prop::{
+		DeviceTreeProperty,
+		DeviceTreePropertyType
+	}
+};
+
+/// Properties made of phandles followed by specifiers whose size the provider decides
+///
+/// Their items cannot be counted without the provider, so their lengths are not checked.
+const SPECIFIER_PROPERTIES: &[&str] = &[
+	"interrupts",
+	"interrupts-extended",
+	"clocks",
+	"resets",
+	"dmas",
+	"phys",
+	"power-domains",
+	"mboxes",
+	"iommus"
+];
+
+/// Types of the properties defined by the Devicetree Specification, the usual one first
+///
+/// BindingSchema::core is built from it and DeviceTreeProperty::update_type looks names up in it directly.
+const CORE_PROPERTIES: &[(&str, &[DeviceTreePropertyType])] = &[
+	("#address-cells", &[DeviceTreePropertyType::U32]),
+	("#size-cells", &[DeviceTreePropertyType::U32]),
+	("#interrupt-cells", &[DeviceTreePropertyType::U32]),
+	("compatible", &[DeviceTreePropertyType::StringList]),
+	("model", &[DeviceTreePropertyType::String]),
+	("phandle", &[DeviceTreePropertyType::U32]),
+	("status", &[DeviceTreePropertyType::String]),
+	("virtual-reg", &[DeviceTreePropertyType::U32]),
+	("dma-coherent", &[DeviceTreePropertyType::Empty]),
+	("name", &[DeviceTreePropertyType::String]),
+	("device_type", &[DeviceTreePropertyType::String]),
+	("timebase-frequency", &[DeviceTreePropertyType::U32, DeviceTreePropertyType::U64]),
+	("clock-frequency", &[DeviceTreePropertyType::U32, DeviceTreePropertyType::U64]),
+	("local-mac-address", &[DeviceTreePropertyType::Bytes])
+];
+
+/// Accepted types of a property defined by the Devicetree Specification, see CORE_PROPERTIES
+fn core_types(name: &str) -> Option<&'static [DeviceTreePropertyType]> {
+	CORE_PROPERTIES.iter().find(|(core, _)| *core == name).map(|(_, value_types)| *value_types)
+}
+
+/// The type of `prop` if the Devicetree Specification defines it, without building BindingSchema::core
+///
+/// Like PropertySchema::type_of, this is the first accepted type the value is encoded as.
+pub(crate) fn core_type(prop: &DeviceTreeProperty) -> Option<DeviceTreePropertyType> {
+	let value_types = core_types(prop.name())?;
+
+	let value_type = value_types.iter().find(|value_type| type_items(prop, value_type).is_ok()).unwrap_or(&value_types[0]);
+
+	Some(value_type.clone())
+}
+
+/// What a binding expects of one property
+#[derive(Clone, PartialEq, Debug)]
+pub struct PropertySchema {
+	name: String,
+	value_type: DeviceTreePropertyType,
+	/// Other types the value may be encoded as, e.g. U64 for a frequency that is usually U32
+	other_types: Vec<DeviceTreePropertyType>,
+	required: bool,
+	/// Bounds on the number of items: cells, strings, bytes or 'reg' entries depending on the type
+	min_items: Option<usize>,
+	max_items: Option<usize>,
+	/// Allowed values, empty if any value is allowed
+	enum_values: Vec<String>
+}
+
+impl PropertySchema {
+	/// A property of type `value_type`, optional and without further constraints
+	///
+	/// DeviceTreePropertyType::Raw leaves the type open, e.g. for a property that is only required.
+	pub fn new(name: &str, value_type: DeviceTreePropertyType) -> Self {
+		Self {
+			name: name.to_string(),
+			value_type,
+			other_types: Vec::new(),
+			required: false,
+			min_items: None,
+			max_items: None,
+			enum_values: Vec::new()
+		}
+	}
+
+	/// Also accept values encoded as `value_type`
+	pub fn or_type(mut self, value_type: DeviceTreePropertyType) -> Self {
+		self.other_types.push(value_type);
+		self
+	}
+
+	pub fn required(mut self) -> Self {
+		self.required = true;
+		self
+	}
+
+	pub fn items(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+		self.min_items = min;
+		self.max_items = max;
+		self
+	}
+
+	/// Restrict strings to the given ones, or cells to the given numbers in decimal or '0x' hex
+	pub fn enum_values(mut self, values: &[&str]) -> Self {
+		self.enum_values = values.iter().map(|value| value.to_string()).collect();
+		self
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn value_type(&self) -> &DeviceTreePropertyType {
+		&self.value_type
+	}
+
+	/// The accepted types, the one of the schema first
+	pub fn value_types(&self) -> impl Iterator<Item = &DeviceTreePropertyType> {
+		core::iter::once(&self.value_type).chain(self.other_types.iter())
+	}
+
+	pub fn is_required(&self) -> bool {
+		self.required
+	}
+
+	/// The first accepted type `prop` is encoded as, or the type of the schema if there is none
+	pub fn type_of(&self, prop: &DeviceTreeProperty) -> DeviceTreePropertyType {
+		self.value_types()
+			.find(|value_type| type_items(prop, value_type).is_ok())
+			.unwrap_or(&self.value_type)
+			.clone()
+	}
+}
+
+/// Schema of a binding, e.g. 'sifive,uart0', with the properties of the nodes it applies to
+#[derive(Clone, PartialEq, Debug)]
+pub struct BindingSchema {
+	/// The schema applies to nodes compatible with one of these, or to every node if empty
+	compatible: Vec<String>,
+	properties: Vec<PropertySchema>
+}
+
+impl BindingSchema {
+	pub fn new(compatible: &[&str]) -> Self {
+		Self {
+			compatible: compatible.iter().map(|c| c.to_string()).collect(),
+			properties: Vec::new()
+		}
+	}
+
+	/// Schema of the properties defined by the Devicetree Specification, applying to every node
+	///
+	/// DeviceTreeProperty::update_type types properties from it.
+	pub fn core() -> Self {
+		CORE_PROPERTIES.iter().fold(Self::new(&[]), |schema, (name, value_types)| {
+			let property = value_types[1..].iter().fold(PropertySchema::new(name, value_types[0].clone()), |property, value_type| {
+				property.or_type(value_type.clone())
+			});
+
+			schema.property(property)
+		})
+	}
+
+	/// Add a property, replacing an earlier one of the same name in place
+	pub fn property(mut self, property: PropertySchema) -> Self {
+		match self.properties.iter_mut().find(|p| p.name == property.name) {
+			Some(p) => *p = property,
+			None => self.properties.push(property)
+		}
+
+		self
+	}
+
+	pub fn compatible(&self) -> &[String] {
+		&self.compatible
+	}
+
+	pub fn find_property(&self, name: &str) -> Option<&PropertySchema> {
+		self.properties.iter().find(|p| p.name == name)
+	}
+
+	pub fn matches(&self, node: &DeviceTreeNode) -> bool {
+		if self.compatible.is_empty() {
+			return true;
+		}
+
+		match node.prop_value("compatible").map(|prop| prop.stringlist()) {
+			Some(Ok(compatible)) => compatible.iter().any(|c| self.compatible.iter().any(|s| s == c)),
+			_ => false
+		}
+	}
+
+	/// Load a schema from the subset of dt-schema YAML shown below
+	///
+	/// ```yaml
+	/// properties:
+	///   compatible:
+	///     const: sifive,uart0
+	///   reg:
+	///     maxItems: 1
+	///   current-speed:
+	///     $ref: /schemas/types.yaml#/definitions/uint32
+	///     enum: [ 9600, 115200 ]
+	/// required:
+	///   - compatible
+	///   - reg
+	/// ```
+	///
+	/// The compatible strings are taken from every 'const' and 'enum' below 'compatible'.
+	/// Properties support '$ref' to the types of types.yaml or a 'oneOf' of them, 'const', 'enum', 'minItems', 'maxItems' and 'items'.
+	/// Other keys are ignored. Block mappings, block sequences, flow sequences, quoted scalars,
+	/// comments and '|' or '>' block scalars are understood.
+	pub fn from_yaml(src: &str) -> Result<Self, DeviceTreeError> {
+		let document = Yaml::parse(src)?;
+
+		let mut schema = Self::new(&[]);
+
+		if let Some(properties) = document.get("properties") {
+			for (name, spec) in properties.entries()? {
+				if name == "compatible" {
+					spec.collect_strings(&mut schema.compatible);
+				}
+
+				schema = schema.property(property_from_yaml(name, spec)?);
+			}
+		}
+
+		if let Some(required) = document.get("required") {
+			for name in required.list()? {
+				let name = name.scalar()?;
+
+				let property = match schema.find_property(name) {
+					Some(property) => property.clone(),
+					None => PropertySchema::new(name, DeviceTreePropertyType::Raw)
+				};
+
+				schema = schema.property(property.required());
+			}
+		}
+
+		Ok(schema)
+	}
+}
+
+/// Set of binding schemas used to type and validate the properties of a tree
+#[derive(Clone, PartialEq, Debug)]
+pub struct SchemaRegistry {
+	schemas: Vec<BindingSchema>
+}
+
+impl Default for SchemaRegistry {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl SchemaRegistry {
+	/// A registry holding the schema of the core properties
+	pub fn new() -> Self {
+		Self { schemas: vec![BindingSchema::core()] }
+	}
+
+	/// Add a schema; where schemas disagree on a property, the one added last wins
+	pub fn add(&mut self, schema: BindingSchema) {
+		self.schemas.push(schema);
+	}
+
+	pub fn add_yaml(&mut self, src: &str) -> Result<(), DeviceTreeError> {
+		self.add(BindingSchema::from_yaml(src)?);
+
+		Ok(())
+	}
+
+	/// Schemas that apply to `node`
+	pub fn schemas_for<'s>(&'s self, node: &'s DeviceTreeNode) -> impl Iterator<Item = &'s BindingSchema> + 's {
+		self.schemas.iter().filter(move |schema| schema.matches(node))
+	}
+
+	/// Set the types of the properties of every node from the schemas that apply to it
+	pub fn apply_types(&self, tree: &DeviceTree) {
+		for node in tree.nodes() {
+			let mut node = node.borrow_mut();
+
+			let properties: Vec<PropertySchema> = self.schemas_for(&node)
+				.flat_map(|schema| schema.properties.iter())
+				.filter(|p| p.value_type != DeviceTreePropertyType::Raw)
+				.cloned()
+				.collect();
+
+			for property in properties {
+				if let Some(prop) = node.prop_value_mut(&property.name) {
+					let value_type = property.type_of(prop);
+
+					prop.set_type(value_type);
+				}
+			}
+		}
+	}
+
+	/// Check every node of `tree` against the schemas that apply to it
+	pub fn validate(&self, tree: &DeviceTree) -> Vec<SchemaViolation> {
+		tree.nodes().iter().flat_map(|node| self.validate_node(&node.borrow())).collect()
+	}
+
+	pub fn validate_node(&self, node: &DeviceTreeNode) -> Vec<SchemaViolation> {
+		let mut violations = Vec::new();
+
+		for schema in self.schemas_for(node) {
+			for property in &schema.properties {
+				let kind = match node.prop_value(&property.name) {
+					Some(prop) => check_property(node, prop, property),
+					None if property.required => Some(ViolationKind::Missing),
+					None => None
+				};
+
+				if let Some(kind) = kind {
+					violations.push(SchemaViolation {
+						path: node.path(),
+						property: property.name.clone(),
+						kind
+					});
+				}
+			}
+		}
+
+		violations
+	}
+}
+
+/// A property of a node that does not meet its schema
+#[derive(Clone, PartialEq, Debug)]
+pub struct SchemaViolation {
+	pub path: String,
+	pub property: String,
+	pub kind: ViolationKind
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ViolationKind {
+	/// A required property is missing
+	Missing,
+	/// The value is not encoded as the type of the schema
+	BadValue,
+	/// The number of items is out of the bounds of the schema
+	BadLength(usize),
+	/// The value is not one of the allowed ones
+	BadEnum
+}
+
+impl core::fmt::Display for SchemaViolation {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self.kind {
+			ViolationKind::Missing => write!(f, "{}: missing required property '{}'", self.path, self.property),
+			ViolationKind::BadValue => write!(f, "{}: bad value of property '{}'", self.path, self.property),
+			ViolationKind::BadLength(items) => write!(f, "{}: wrong number of items ({}) in property '{}'", self.path, items, self.property),
+			ViolationKind::BadEnum => write!(f, "{}: value of property '{}' is not allowed", self.path, self.property)
+		}
+	}
+}
+
+fn check_property(node: &DeviceTreeNode, prop: &DeviceTreeProperty, schema: &PropertySchema) -> Option<ViolationKind> {
+	let items = match count_items(node, prop, schema) {
+		Ok(items) => items,
+		Err(_) => return Some(ViolationKind::BadValue)
+	};
+
+	if let Some(items) = items {
+		let too_few = schema.min_items.is_some_and(|min| items < min);
+		let too_many = schema.max_items.is_some_and(|max| items > max);
+
+		if too_few || too_many {
+			return Some(ViolationKind::BadLength(items));
+		}
+	}
+
+	if !schema.enum_values.is_empty() && !enum_allows(prop, schema) {
+		return Some(ViolationKind::BadEnum);
+	}
+
+	None
+}
+
+/// Number of items of the value, checking that it is encoded as one of the types of `schema`
+///
+/// Returns None if the items cannot be counted.
+fn count_items(node: &DeviceTreeNode, prop: &DeviceTreeProperty, schema: &PropertySchema) -> Result<Option<usize>, DeviceTreeError> {
+	if prop.name() == "reg" {
+		return reg_entries(node, prop).map(Some);
+	}
+
+	if SPECIFIER_PROPERTIES.contains(&prop.name()) {
+		return prop.cells().map(|_| None);
+	}
+
+	schema.value_types()
+		.map(|value_type| type_items(prop, value_type))
+		.find(Result::is_ok)
+		.unwrap_or(Err(DeviceTreeError::BadPropValue))
+}
+
+/// Number of items of the value, checking that it is encoded as `value_type`
+fn type_items(prop: &DeviceTreeProperty, value_type: &DeviceTreePropertyType) -> Result<Option<usize>, DeviceTreeError> {
+	let items = match value_type {
+		DeviceTreePropertyType::Empty if prop.bytes().is_empty() => 0,
+		DeviceTreePropertyType::Empty => return Err(DeviceTreeError::BadPropValue),
+		DeviceTreePropertyType::String => prop.string().map(|_| 1)?,
+		DeviceTreePropertyType::StringList => prop.stringlist()?.len(),
+		DeviceTreePropertyType::U32 => prop.u32().map(|_| 1)?,
+		DeviceTreePropertyType::U64 => prop.u64().map(|_| 1)?,
+		DeviceTreePropertyType::Cells => prop.cells()?.len(),
+		DeviceTreePropertyType::Bytes => prop.bytes().len(),
+		DeviceTreePropertyType::Raw => return Ok(None)
+	};
+
+	Ok(Some(items))
+}
+
+/// Number of address and size pairs of 'reg', sized by '#address-cells' and '#size-cells' of the parent
+fn reg_entries(node: &DeviceTreeNode, reg: &DeviceTreeProperty) -> Result<usize, DeviceTreeError> {
+	let (address_cells, size_cells) = match node.parent() {
+		Some(parent) => {
+			let parent = parent.borrow();
+
+			let cells = |name: &str, default: u32| parent.prop_value(name).map_or(Ok(default), |prop| prop.u32());
+
+			(cells("#address-cells", 2)?, cells("#size-cells", 1)?)
+		},
+		None => (2, 1)
+	};
+
+	let entry_size = (address_cells + size_cells) as usize * 4;
+
+	if entry_size == 0 || !reg.bytes().len().is_multiple_of(entry_size) {
+		return Err(DeviceTreeError::BadPropValue);
+	}
+
+	Ok(reg.bytes().len() / entry_size)
+}
+
+fn enum_allows(prop: &DeviceTreeProperty, schema: &PropertySchema) -> bool {
+	let allowed_str = |s: &str| schema.enum_values.iter().any(|value| value == s);
+	let allowed_cell = |cell: u32| schema.enum_values.iter().any(|value| parse_u32(value) == Some(cell));
+
+	match schema.value_type {
+		DeviceTreePropertyType::String => prop.string().is_ok_and(allowed_str),
+		DeviceTreePropertyType::StringList => prop.stringlist().is_ok_and(|strings| strings.into_iter().all(allowed_str)),
+		DeviceTreePropertyType::U32 | DeviceTreePropertyType::Cells => prop.cells().is_ok_and(|cells| cells.into_iter().all(allowed_cell)),
+		_ => true
+	}
+}
+
+fn parse_u32(value: &str) -> Option<u32> {
+	match value.strip_prefix("0x") {
+		Some(hex) => u32::from_str_radix(hex, 16).ok(),
+		None => value.parse().ok()
+	}
+}
+
+fn property_from_yaml(name: &str, spec: &Yaml) -> Result<PropertySchema, DeviceTreeError> {
+	let mut value_types = match (spec.get("$ref"), spec.get("oneOf").or(spec.get("anyOf"))) {
+		(Some(reference), _) => vec![ref_type(reference)?],
+		// 'oneOf' of '$ref's, e.g. a frequency that is either a uint32 or a uint64
+		(None, Some(choices)) => choices.list()?.iter()
+			.filter_map(|choice| choice.get("$ref"))
+			.map(ref_type)
+			.collect::<Result<Vec<_>, _>>()?,
+		(None, None) => Vec::new()
+	};
+
+	if value_types.is_empty() {
+		value_types = match core_types(name) {
+			Some(core) => core.to_vec(),
+			None if name == "reg" || name == "ranges" || SPECIFIER_PROPERTIES.contains(&name) => vec![DeviceTreePropertyType::Cells],
+			None if name.ends_with("-names") => vec![DeviceTreePropertyType::StringList],
+			None => vec![DeviceTreePropertyType::Raw]
+		};
+	}
+
+	let value_type = value_types.remove(0);
+
+	let mut property = PropertySchema::new(name, value_type);
+
+	property.other_types = value_types;
+
+	// The compatible strings select the schema, the combinations are not checked
+	if name == "compatible" {
+		return Ok(property);
+	}
+
+	let count = |key: &str| -> Result<Option<usize>, DeviceTreeError> {
+		match spec.get(key) {
+			Some(value) => value.scalar()?.parse().map(Some).map_err(|_| DeviceTreeError::BadSchema),
+			None => Ok(None)
+		}
+	};
+
+	let mut min_items = count("minItems")?;
+	let mut max_items = count("maxItems")?;
+
+	if let Some(Yaml::List(items)) = spec.get("items") {
+		min_items = min_items.or(Some(items.len()));
+		max_items = max_items.or(Some(items.len()));
+	}
+
+	property = property.items(min_items, max_items);
+
+	if let Some(value) = spec.get("const") {
+		property.enum_values = vec![value.scalar()?.to_string()];
+	}
+
+	if let Some(values) = spec.get("enum") {
+		property.enum_values = values.list()?.iter().map(|value| value.scalar().map(|s| s.to_string())).collect::<Result<_, _>>()?;
+	}
+
+	Ok(property)
+}
+
+/// Type of a '$ref' to the definitions of types.yaml
+fn ref_type(reference: &Yaml) -> Result<DeviceTreePropertyType, DeviceTreeError> {
+	let reference = reference.scalar()?;
+
+	Ok(match reference.rsplit('/').next().unwrap_or(reference) {
+		"flag" => DeviceTreePropertyType::Empty,
+		"string" => DeviceTreePropertyType::String,
+		"string-array" | "non-unique-string-array" => DeviceTreePropertyType::StringList,
+		"uint32" | "phandle" => DeviceTreePropertyType::U32,
+		"uint64" => DeviceTreePropertyType::U64,
+		"uint32-array" | "uint32-matrix" | "phandle-array" | "uint64-array" => DeviceTreePropertyType::Cells,
+		"uint8-array" => DeviceTreePropertyType::Bytes,
+		_ => DeviceTreePropertyType::Raw
+	})
+}
+
+/// Document of the YAML subset read by BindingSchema::from_yaml
+#[derive(Clone, PartialEq, Debug)]
+enum Yaml {
+	Scalar(String),
+	List(Vec<Yaml>),
+	Map(Vec<(String, Yaml)>)
+}
+
+/// A line of YAML without indentation and comment
+struct Line {
+	indent: usize,
+	text: String
+}
+
+impl Yaml {
+	fn parse(src: &str) -> Result<Self, DeviceTreeError> {
+		let mut lines: Vec<Line> = src.lines()
+			.filter(|line| !matches!(line.trim(), "---" | "...") && !line.starts_with('%'))
+			.map(|line| Line {
+				indent: line.len() - line.trim_start().len(),
+				text: strip_comment(line.trim()).to_string()
+			})
+			.filter(|line| !line.text.is_empty())
+			.collect();
+
+		if lines.is_empty() {
+			return Ok(Self::Map(Vec::new()));
+		}
+
+		let indent = lines[0].indent;
+		let mut pos = 0;
+
+		let document = Self::parse_block(&mut lines, &mut pos, indent)?;
+
+		if pos != lines.len() {
+			return Err(DeviceTreeError::BadSchema);
+		}
+
+		Ok(document)
+	}
+
+	fn parse_block(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Self, DeviceTreeError> {
+		if is_list_item(&lines[*pos].text) {
+			Self::parse_list(lines, pos, indent)
+		} else {
+			Self::parse_map(lines, pos, indent)
+		}
+	}
+
+	fn parse_list(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Self, DeviceTreeError> {
+		let mut items = Vec::new();
+
+		while *pos < lines.len() && lines[*pos].indent == indent && is_list_item(&lines[*pos].text) {
+			let rest = lines[*pos].text[1..].trim_start().to_string();
+
+			if rest.is_empty() {
+				*pos += 1;
+
+				items.push(Self::parse_nested(lines, pos, indent)?);
+			} else if split_key(&rest).is_some() {
+				// '- key: value' starts a map indented like 'key'
+				let line = &mut lines[*pos];
+
+				line.indent += line.text.len() - rest.len();
+				line.text = rest;
+
+				let indent = line.indent;
+
+				items.push(Self::parse_map(lines, pos, indent)?);
+			} else {
+				*pos += 1;
+
+				items.push(Self::parse_flow(&rest)?);
+			}
+		}
+
+		Ok(Self::List(items))
+	}
+
+	fn parse_map(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Self, DeviceTreeError> {
+		let mut entries = Vec::new();
+
+		while *pos < lines.len() && lines[*pos].indent == indent && !is_list_item(&lines[*pos].text) {
+			let (key, value) = split_key(&lines[*pos].text).ok_or(DeviceTreeError::BadSchema)?;
+			let (key, value) = (unquote(key).to_string(), value.to_string());
+
+			*pos += 1;
+
+			let value = match value.as_str() {
+				"" => {
+					// A sequence may start at the indentation of its key
+					if *pos < lines.len() && lines[*pos].indent == indent && is_list_item(&lines[*pos].text) {
+						Self::parse_list(lines, pos, indent)?
+					} else {
+						Self::parse_nested(lines, pos, indent)?
+					}
+				},
+				block if block.starts_with('|') || block.starts_with('>') => {
+					while *pos < lines.len() && lines[*pos].indent > indent {
+						*pos += 1;
+					}
+
+					Self::Scalar(String::new())
+				},
+				flow => Self::parse_flow(flow)?
+			};
+
+			entries.push((key, value));
+		}
+
+		if *pos < lines.len() && lines[*pos].indent > indent {
+			return Err(DeviceTreeError::BadSchema);
+		}
+
+		Ok(Self::Map(entries))
+	}
+
+	/// The block indented deeper than `indent` at `pos`, or an empty scalar if there is none
+	fn parse_nested(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Self, DeviceTreeError> {
+		match lines.get(*pos) {
+			Some(line) if line.indent > indent => {
+				let indent = line.indent;
+
+				Self::parse_block(lines, pos, indent)
+			},
+			_ => Ok(Self::Scalar(String::new()))
+		}
+	}
+
+	/// A scalar or a flow sequence like '[ 1, 2 ]'
+	fn parse_flow(text: &str) -> Result<Self, DeviceTreeError> {
+		match text.strip_prefix('[') {
+			Some(list) => {
+				let list = list.strip_suffix(']').ok_or(DeviceTreeError::BadSchema)?.trim();
+
+				if list.is_empty() {
+					return Ok(Self::List(Vec::new()));
+				}
+
+				Ok(Self::List(list.split(',').map(|item| Self::Scalar(unquote(item.trim()).to_string())).collect()))
+			},
+			None => Ok(Self::Scalar(unquote(text).to_string()))
+		}
+	}
+
+	fn get(&self, key: &str) -> Option<&Self> {
+		match self {
+			Self::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, value)| value),
+			_ => None
+		}
+	}
+
+	fn entries(&self) -> Result<impl Iterator<Item = (&str, &Self)>, DeviceTreeError> {
+		match self {
+			Self::Map(entries) => Ok(entries.iter().map(|(key, value)| (key.as_str(), value))),
+			_ => Err(DeviceTreeError::BadSchema)
+		}
+	}
+
+	fn list(&self) -> Result<&[Self], DeviceTreeError> {
+		match self {
+			Self::List(items) => Ok(items),
+			_ => Err(DeviceTreeError::BadSchema)
+		}
+	}
+
+	fn scalar(&self) -> Result<&str, DeviceTreeError> {
+		match self {
+			Self::Scalar(s) => Ok(s),
+			_ => Err(DeviceTreeError::BadSchema)
+		}
+	}
+
+	/// Push the values of every 'const' and 'enum' found in the document
+	fn collect_strings(&self, strings: &mut Vec<String>) {
+		match self {
+			Self::Map(entries) => {
+				for (key, value) in entries {
+					match (key.as_str(), value) {
+						("const", Self::Scalar(s)) => strings.push(s.clone()),
+						("enum", Self::List(items)) => {
+							strings.extend(items.iter().filter_map(|item| item.scalar().ok()).map(|s| s.to_string()));
+						},
+						_ => value.collect_strings(strings)
+					}
+				}
+			},
+			Self::List(items) => items.iter().for_each(|item| item.collect_strings(strings)),
+			Self::Scalar(_) => ()
+		}
+	}
+}
+
+fn is_list_item(text: &str) -> bool {
+	text == "-" || text.starts_with("- ")
+}
+
+/// Split 'key: value' or 'key:' at the first ': '
+fn split_key(text: &str) -> Option<(&str, &str)> {
+	if let Some(key) = text.strip_suffix(':') {
+		if !key.contains(": ") {
+			return Some((key, ""));
+		}
+	}
+
+	text.split_once(": ").map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// Cut a comment starting at a '#' preceded by whitespace, outside of quotes
+fn strip_comment(text: &str) -> &str {
+	if text.starts_with('#') {
+		return "";
+	}
+
+	let mut quote = None;
+	let mut previous = ' ';
+
+	for (i, c) in text.char_indices() {
+		match quote {
+			Some(q) if c == q => quote = None,
+			Some(_) => (),
+			None if c == '\'' || c == '"' => quote = Some(c),
+			None if c == '#' && previous.is_whitespace() => return text[..i].trim_end(),
+			None => ()
+		}
+
+		previous = c;
+	}
+
+	text
+}
+
+fn unquote(text: &str) -> &str {
+	for quote in ['"', '\''] {
+		if let Some(s) = text.strip_prefix(quote).and_then(|s| s.strip_suffix(quote)) {
+			return s;
+		}
+	}
+
+	text
+}
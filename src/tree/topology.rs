@@ -0,0 +1,279 @@
+use alloc::{
+	rc::Rc,
+	vec::Vec
+};
+
+use crate::{
+	DeviceTree,
+	DeviceTreeError
+};
+use super::{
+	node::{
+		DeviceTreeNode,
+		DeviceTreeNodeWrap
+	},
+	cpu::{
+		Cpu,
+		is_cpu
+	}
+};
+
+/// Level of a node of '/cpus/cpu-map'
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TopologyLevel {
+	Socket,
+	Cluster,
+	Core,
+	Thread
+}
+
+impl TopologyLevel {
+	/// Prefix of the node names of the level, followed by the index, e.g. 'core' in 'core0'
+	pub fn prefix(&self) -> &'static str {
+		match self {
+			TopologyLevel::Socket => "socket",
+			TopologyLevel::Cluster => "cluster",
+			TopologyLevel::Core => "core",
+			TopologyLevel::Thread => "thread"
+		}
+	}
+
+	/// Whether a node of this level may be a child of a node of `parent`, or of 'cpu-map' itself if None
+	///
+	/// Clusters may nest, other levels strictly follow socket, cluster, core and thread.
+	fn fits_under(&self, parent: Option<TopologyLevel>) -> bool {
+		matches!(
+			(parent, self),
+			(None, TopologyLevel::Socket | TopologyLevel::Cluster)
+				| (Some(TopologyLevel::Socket), TopologyLevel::Cluster)
+				| (Some(TopologyLevel::Cluster), TopologyLevel::Cluster | TopologyLevel::Core)
+				| (Some(TopologyLevel::Core), TopologyLevel::Thread)
+		)
+	}
+}
+
+/// Node of the CPU topology read from '/cpus/cpu-map'
+#[derive(Clone, Debug)]
+pub struct TopologyNode {
+	level: TopologyLevel,
+	index: u32,
+	children: Vec<TopologyNode>,
+	cpu: Option<Cpu>
+}
+
+impl TopologyNode {
+	fn from_node(tree: &DeviceTree, node: &DeviceTreeNode, parent: Option<TopologyLevel>) -> Result<Self, DeviceTreeError> {
+		let (level, index) = parse_name(node.name()).ok_or(DeviceTreeError::BadTopology)?;
+
+		if !level.fits_under(parent) {
+			return Err(DeviceTreeError::BadTopology);
+		}
+
+		let children = node.children_iter()
+			.map(|(_, child)| Self::from_node(tree, &child.borrow(), Some(level)))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let cpu = match node.prop_value("cpu") {
+			Some(prop) => Some(resolve_cpu(tree, prop.phandle()?)?),
+			None => None
+		};
+
+		// Only cores without threads and threads are leaves, and every leaf is a CPU
+		let is_leaf = level == TopologyLevel::Thread || (level == TopologyLevel::Core && children.is_empty());
+
+		if is_leaf != cpu.is_some() || (!is_leaf && children.is_empty()) {
+			return Err(DeviceTreeError::BadTopology);
+		}
+
+		Ok(Self { level, index, children, cpu })
+	}
+
+	pub fn level(&self) -> TopologyLevel {
+		self.level
+	}
+
+	/// Index N of the node name, e.g. 1 for 'cluster1'
+	pub fn index(&self) -> u32 {
+		self.index
+	}
+
+	pub fn children(&self) -> &[TopologyNode] {
+		&self.children
+	}
+
+	/// The CPU of a leaf, i.e. of a thread or of a core without threads
+	pub fn cpu(&self) -> Option<&Cpu> {
+		self.cpu.as_ref()
+	}
+
+	/// The CPUs of the leaves below this node, in the order of the cpu-map
+	pub fn cpus(&self) -> Vec<&Cpu> {
+		let mut cpus = Vec::new();
+
+		self.collect_cpus(&mut cpus);
+
+		cpus
+	}
+
+	fn collect_cpus<'a>(&'a self, cpus: &mut Vec<&'a Cpu>) {
+		cpus.extend(self.cpu.as_ref());
+
+		for child in &self.children {
+			child.collect_cpus(cpus);
+		}
+	}
+}
+
+/// A cache reached from a CPU through 'next-level-cache'
+#[derive(Clone, Debug)]
+pub struct Cache {
+	node: DeviceTreeNodeWrap,
+	level: u32,
+	unified: bool,
+	size: Option<u32>,
+	line_size: Option<u32>,
+	sets: Option<u32>
+}
+
+impl Cache {
+	fn from_node(node: &DeviceTreeNodeWrap, default_level: u32) -> Result<Self, DeviceTreeError> {
+		let cache = node.borrow();
+
+		let u32_prop = |name: &str| cache.prop_value(name).map(|prop| prop.u32()).transpose();
+
+		Ok(Self {
+			node: Rc::clone(node),
+			level: u32_prop("cache-level")?.unwrap_or(default_level),
+			unified: cache.prop_exists("cache-unified"),
+			size: u32_prop("cache-size")?,
+			line_size: match u32_prop("cache-line-size")? {
+				Some(line_size) => Some(line_size),
+				None => u32_prop("cache-block-size")?
+			},
+			sets: u32_prop("cache-sets")?
+		})
+	}
+
+	pub fn node(&self) -> &DeviceTreeNodeWrap {
+		&self.node
+	}
+
+	/// Value of 'cache-level', else one more than the level of the previous cache of the chain
+	pub fn level(&self) -> u32 {
+		self.level
+	}
+
+	/// Whether the cache holds both instructions and data
+	pub fn is_unified(&self) -> bool {
+		self.unified
+	}
+
+	/// Size in bytes
+	pub fn size(&self) -> Option<u32> {
+		self.size
+	}
+
+	/// Line size in bytes, from 'cache-line-size' or else from 'cache-block-size'
+	pub fn line_size(&self) -> Option<u32> {
+		self.line_size
+	}
+
+	pub fn sets(&self) -> Option<u32> {
+		self.sets
+	}
+
+	/// Whether both are read from the same node, i.e. the cache is shared
+	pub fn is_same(&self, other: &Cache) -> bool {
+		Rc::ptr_eq(&self.node, &other.node)
+	}
+}
+
+impl Cpu {
+	/// The caches of the CPU, following 'next-level-cache' from the CPU node
+	///
+	/// The CPU node itself describes the level 1 caches, so the first cache of the chain defaults to level 2.
+	/// Returns DeviceTreeError::BadPhandle if a phandle has no node and DeviceTreeError::BadTopology if the chain loops.
+	pub fn caches(&self, tree: &DeviceTree) -> Result<Vec<Cache>, DeviceTreeError> {
+		let mut caches: Vec<Cache> = Vec::new();
+		let mut node = Rc::clone(self.node());
+
+		loop {
+			let phandle = match node.borrow().prop_value("next-level-cache") {
+				Some(prop) => prop.phandle()?,
+				None => break
+			};
+
+			let next = tree.find_phandle(phandle).ok_or(DeviceTreeError::BadPhandle)?;
+
+			if Rc::ptr_eq(&next, self.node()) || caches.iter().any(|cache| Rc::ptr_eq(cache.node(), &next)) {
+				return Err(DeviceTreeError::BadTopology);
+			}
+
+			let level = caches.last().map_or(2, |cache| cache.level() + 1);
+
+			caches.push(Cache::from_node(&next, level)?);
+			node = next;
+		}
+
+		Ok(caches)
+	}
+}
+
+impl DeviceTree {
+	/// The topology read from '/cpus/cpu-map', one node per socket or top-level cluster
+	///
+	/// Returns an empty list if there is no cpu-map, and DeviceTreeError::BadTopology if its nodes are misnamed,
+	/// misnested or a leaf has no 'cpu'.
+	pub fn cpu_topology(&self) -> Result<Vec<TopologyNode>, DeviceTreeError> {
+		let cpu_map = match self.find_node("/cpus/cpu-map") {
+			Some(cpu_map) => cpu_map,
+			None => return Ok(Vec::new())
+		};
+
+		let cpu_map = cpu_map.borrow();
+
+		cpu_map.children_iter()
+			.map(|(_, node)| TopologyNode::from_node(self, &node.borrow(), None))
+			.collect()
+	}
+
+	/// The CPUs whose cache chain contains `cache`
+	pub fn cpus_sharing(&self, cache: &Cache) -> Result<Vec<Cpu>, DeviceTreeError> {
+		let mut sharing = Vec::new();
+
+		for cpu in self.cpus()? {
+			if cpu.caches(self)?.iter().any(|other| other.is_same(cache)) {
+				sharing.push(cpu);
+			}
+		}
+
+		Ok(sharing)
+	}
+}
+
+/// Split a cpu-map node name like 'cluster1' into its level and index
+fn parse_name(name: &str) -> Option<(TopologyLevel, u32)> {
+	[TopologyLevel::Socket, TopologyLevel::Cluster, TopologyLevel::Core, TopologyLevel::Thread]
+		.into_iter()
+		.find_map(|level| {
+			let index = name.strip_prefix(level.prefix())?;
+
+			match index.starts_with(|c: char| c.is_ascii_digit()) {
+				true => index.parse().ok().map(|index| (level, index)),
+				false => None
+			}
+		})
+}
+
+fn resolve_cpu(tree: &DeviceTree, phandle: u32) -> Result<Cpu, DeviceTreeError> {
+	let node = tree.find_phandle(phandle).ok_or(DeviceTreeError::BadPhandle)?;
+
+	if !is_cpu(&node.borrow()) {
+		return Err(DeviceTreeError::BadTopology);
+	}
+
+	let cpus = node.borrow().parent().ok_or(DeviceTreeError::BadTopology)?;
+	let cpus = cpus.borrow();
+
+	Cpu::from_node(&node, &cpus)
+}
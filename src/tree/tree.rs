@@ -1,53 +1,152 @@
-use alloc::rc::Rc;
+use alloc::{
+	collections::BTreeMap,
+	rc::Rc,
+	string::String,
+	vec::Vec
+};
 
 use crate::{
 	DeviceTree, 
-	DeviceTreeError
+	fdt::blob::FdtReserveEntry
 };
 use crate::tree::{
 	node::{
 		DeviceTreeNodeWrap, 
 		DeviceTreeNode
 	},
-	prop::DeviceTreeProperty
+	prop::InferOptions,
+	cpu
 };
 
-use super::CPU_MAX_NUM;
-
 impl DeviceTree {
 	pub fn new_empty_root() -> Self {
-		DeviceTree {
-			root: DeviceTreeNode::new_wrap()
-		}
+		Self::new(DeviceTreeNode::new_wrap())
 	}
 
 	pub fn new(root: DeviceTreeNodeWrap) -> Self {
 		root.borrow_mut().set_name("/");
 
-		Self { root: Rc::clone(&root) }
+		Self { 
+			root: Rc::clone(&root),
+			memory_reservations: Vec::new(),
+			boot_cpuid_phys: 0
+		}
 	}
 
 	pub fn root(&self) -> &DeviceTreeNodeWrap {
 		&self.root
 	}
 
+	/// Entries of the memory reservation block
+	pub fn memory_reservations(&self) -> &[FdtReserveEntry] {
+		&self.memory_reservations
+	}
+
+	pub fn add_memory_reservation(&mut self, entry: FdtReserveEntry) {
+		self.memory_reservations.push(entry);
+	}
+
+	/// The physical ID of the system’s boot CPU
+	pub fn boot_cpuid_phys(&self) -> u32 {
+		self.boot_cpuid_phys
+	}
+
+	pub fn set_boot_cpuid_phys(&mut self, boot_cpuid_phys: u32) {
+		self.boot_cpuid_phys = boot_cpuid_phys;
+	}
+
+	/// All nodes of the tree in depth-first pre-order, starting with the root
+	pub fn nodes(&self) -> Vec<DeviceTreeNodeWrap> {
+		let mut nodes = Vec::new();
+		let mut stack = vec![Rc::clone(&self.root)];
+
+		while let Some(node) = stack.pop() {
+			let children: Vec<DeviceTreeNodeWrap> = node.borrow().children_iter().map(|(_, child)| Rc::clone(child)).collect();
+
+			stack.extend(children.into_iter().rev());
+
+			nodes.push(node);
+		}
+
+		nodes
+	}
+
+	/// Find a node by its full path, e.g. '/cpus/cpu@0'
+	pub fn find_node(&self, path: &str) -> Option<DeviceTreeNodeWrap> {
+		let mut current = Rc::clone(&self.root);
+
+		for name in path.split('/').filter(|name| !name.is_empty()) {
+			let next = Rc::clone(current.borrow().find_child(name)?);
+
+			current = next;
+		}
+
+		Some(current)
+	}
+
+	/// Find the node carrying the given label
+	pub fn find_label(&self, label: &str) -> Option<DeviceTreeNodeWrap> {
+		self.nodes().into_iter().find(|node| node.borrow().label().map(|l| l.as_str()) == Some(label))
+	}
+
+	/// Find the node whose 'phandle' property has the given value
+	pub fn find_phandle(&self, phandle: u32) -> Option<DeviceTreeNodeWrap> {
+		self.nodes().into_iter().find(|node| node.borrow().phandle() == Some(phandle))
+	}
+
+	/// The nodes by their 'phandle', the first one in tree order for a phandle used twice
+	///
+	/// Unlike find_phandle, this goes over the tree once for any number of lookups.
+	pub(crate) fn phandles(&self) -> BTreeMap<u32, DeviceTreeNodeWrap> {
+		let mut phandles = BTreeMap::new();
+
+		for node in self.nodes() {
+			let phandle = node.borrow().phandle();
+
+			if let Some(phandle) = phandle {
+				phandles.entry(phandle).or_insert(node);
+			}
+		}
+
+		phandles
+	}
+
+	/// Guess the type of every property that update_type does not know, see DeviceTreeProperty::infer_type
+	pub fn infer_types(&self, options: &InferOptions) {
+		for node in self.nodes() {
+			let mut node = node.borrow_mut();
+
+			let names: Vec<String> = node.prop_iter().map(|(name, _)| name.clone()).collect();
+
+			for name in names {
+				node.prop_value_mut(&name).unwrap().infer_type(options);
+			}
+		}
+	}
+
+	/// Number of children of '/cpus' with 'device_type = "cpu"', see DeviceTree::cpus
 	pub fn num_cpus(&self) -> usize {
 		let root = &self.root;
 
 		if let Some(cpus) = root.borrow().find_child("cpus") {
-			return cpus.borrow().num_children();
+			return cpus.borrow().children_iter().filter(|(_, node)| cpu::is_cpu(&node.borrow())).count();
 		}
 
-		return 0;
+		0
 	}
 
 	pub fn has_cpus(&self) -> bool {
-		let root = self.root();
+		self.root().borrow().find_child("cpus").is_some()
+	}
+}
 
-		if root.borrow().find_child("cpus").is_some() {
-			return true;
-		} else {
-			return false;
+impl Clone for DeviceTree {
+	/// Deep copy of the tree: the nodes of the copy are not shared with the original
+	fn clone(&self) -> Self {
+		Self {
+			root: self.root.borrow().deep_copy(),
+			memory_reservations: self.memory_reservations.clone(),
+			boot_cpuid_phys: self.boot_cpuid_phys
 		}
 	}
 }